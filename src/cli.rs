@@ -0,0 +1,944 @@
+//! Headless interface over the same server list the GUI uses.
+//!
+//! Meant for machines without a display, scripts and cron jobs.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead},
+    net::Ipv4Addr,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use iced::futures::{StreamExt, channel::mpsc};
+use snafu::prelude::*;
+
+use crate::{
+    core::{
+        self, Game,
+        addons::InstallProgress,
+        appmanifest,
+        depotdownloader::{self, DepotDownloader},
+        installer::InstallOptions,
+        launch::Region,
+        updates,
+        workshop::WorkshopItems,
+    },
+    ui::{
+        games::{self, SOURCE_GAMES},
+        screen::{
+            serverboot::{self, Console, ServerCommunicationTwoWay, TextType, find_available_port},
+            servercreation::{self, download_server},
+            serverlist::{self, create_config_file_path, get_config_path, setup_sourcemod},
+        },
+        server::{
+            InstallStatus, RestartPolicy, RunningProcess, RuntimeState, Server, ServerInfo,
+            Servers, StopPolicy, UpdateMode, runtime_state_path,
+        },
+    },
+};
+
+const USAGE: &str = "\
+Usage: mannager <command> [options]
+
+Commands:
+  list                                List the tracked servers
+  create --game <game> --name <name> --path <path> [--map <map>]
+         [--max-players <n>] [--port <port>] [--password <password>] [--gslt <token>]
+         [--rcon-password <password>] [--restart never|on-crash|always]
+         [--region <sv_region code>] [--tags <a,b,c>] [--tickrate <n>]
+         [--args <extra launch arguments>] [<install options>]
+                                      Download a new server and add it to the list.
+                                      It's listed as incomplete until the download
+                                      is done.
+  update <server> [<install options>] [--validate true]
+                                      Download the latest server files, resuming an
+                                      incomplete download. Validating
+                                      checks the installed files too, repairing
+                                      and listing the missing or damaged ones.
+  check-updates [<server>] [--update true]
+                                      Compare the installed builds with the latest
+                                      ones, of every server when none is given.
+                                      Updating only touches stopped servers.
+  update-depotdownloader [--version <version>|latest] [--sha256 <hash>]
+                                      Replace the DepotDownloader in the data folder,
+                                      with the latest release by default. It's
+                                      checked against --sha256, or the digest GitHub
+                                      lists for it.
+  start <server>                      Run the server in the foreground
+  stop <server>                       Stop a running server
+  install-sourcemod <server> [--sourcemod stable|dev|<version>]
+         [--metamod stable|dev|<version>]
+         [--sourcemod-sha256 <hash>] [--metamod-sha256 <hash>]
+         [--checksums required|optional]
+                                      Install Metamod and SourceMod, e.g. pinned to
+                                      --sourcemod 1.11.0.6968. The choice is saved.
+                                      Pinned builds are checked against the hashes.
+                                      With optional checksums, builds without a
+                                      published or pinned hash install unchecked.
+
+Install options, saved with the server:
+  --installer depotdownloader|steamcmd
+  --beta <branch>                     A beta branch, the public one by default
+  --beta-password <password>          The password of a private beta branch
+  --manifest <depot:manifest,...>     Holds depots at a manifest. Only the pinned
+                                      depots are downloaded. DepotDownloader only.
+  --login <account>                   The account SteamCMD logs in with, using its
+                                      cached credentials. Anonymous by default.
+An empty value unsets any of them.
+
+Set MANNAGER_DEPOTDOWNLOADER to the path of a DepotDownloader executable to use it
+instead of downloading one.
+
+<server> is either the index shown by `list` or the server's name.
+Running without a command opens the GUI.";
+
+/// Runs the command described by `args` and returns the process exit code.
+pub fn run(args: Vec<String>) -> i32 {
+    let runtime = match tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("error: failed to start the async runtime: {err}");
+
+            return 1;
+        }
+    };
+
+    match runtime.block_on(execute(args)) {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("error: {err}");
+
+            1
+        }
+    }
+}
+
+async fn execute(args: Vec<String>) -> Result<(), Error> {
+    let mut args = args.into_iter();
+
+    let Some(command) = args.next() else {
+        println!("{USAGE}");
+
+        return Ok(());
+    };
+
+    let (positional, flags) = parse_args(args)?;
+
+    match command.as_str() {
+        "list" => {
+            reject_unknown_flags(&flags)?;
+
+            list().await
+        }
+        "create" => create(flags).await,
+        "update" => update(server_query(&positional)?, flags).await,
+        "check-updates" => check_updates(positional.first().map(String::as_str), flags).await,
+        "update-depotdownloader" => update_depotdownloader(flags).await,
+        "start" => {
+            reject_unknown_flags(&flags)?;
+
+            start(server_query(&positional)?).await
+        }
+        "stop" => {
+            reject_unknown_flags(&flags)?;
+
+            stop(server_query(&positional)?).await
+        }
+        "install-sourcemod" => install_sourcemod(server_query(&positional)?, flags).await,
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+
+            Ok(())
+        }
+        _ => UnknownCommandSnafu { command }.fail(),
+    }
+}
+
+async fn list() -> Result<(), Error> {
+    let (config_path, servers) = load_servers().await?;
+
+    if servers.is_empty() {
+        println!("No servers are being tracked.");
+
+        return Ok(());
+    }
+
+    for (id, Server { info, .. }) in servers.iter().enumerate() {
+        let mut status = match running_process(&config_path, info).await {
+            Some(running) => format!("running (pid {})", running.pid),
+            None => "stopped".to_string(),
+        };
+
+        if let Some(manifest) = appmanifest::read(&info.path, info.game.into()).await {
+            status.push_str(&format!(" · build {}", manifest.build_id));
+        }
+
+        if info.install_status == InstallStatus::Incomplete {
+            status.push_str(" · incomplete, resume it with `update`");
+        }
+
+        println!(
+            "[{id}] {name}\n    game: {game}\n    path: {path}\n    map: {map} · players: {players} · port: {port}\n    status: {status}",
+            name = info.name,
+            game = info.game,
+            path = info.path.display(),
+            map = info.map,
+            players = info.max_players,
+            port = info
+                .port
+                .map_or_else(|| "auto".to_string(), |port| port.to_string()),
+        );
+    }
+
+    Ok(())
+}
+
+async fn create(mut flags: HashMap<String, String>) -> Result<(), Error> {
+    let mut required = |flag: &'static str| -> Result<String, Error> {
+        flags
+            .remove(flag)
+            .context(MissingArgumentSnafu { argument: flag })
+    };
+
+    let game = parse_game(&required("game")?)?;
+    let name = required("name")?;
+    let path = PathBuf::from(required("path")?);
+    let map = match flags.remove("map") {
+        Some(map) => map,
+        None => games::source_game(game)
+            .map(|game_info| game_info.launch.default_map.to_string())
+            .context(UnsupportedGameSnafu { game })?,
+    };
+
+    let max_players = match flags.remove("max-players") {
+        Some(value) => parse_value("max-players", &value)?,
+        None => 24,
+    };
+
+    let port = flags
+        .remove("port")
+        .map(|value| parse_value("port", &value))
+        .transpose()?;
+
+    let region = flags
+        .remove("region")
+        .map(|value| {
+            parse_value("region", &value)
+                .ok()
+                .and_then(Region::from_code)
+                .context(InvalidValueSnafu {
+                    argument: "region",
+                    value,
+                })
+        })
+        .transpose()?;
+
+    let tickrate = flags
+        .remove("tickrate")
+        .map(|value| parse_value("tickrate", &value))
+        .transpose()?;
+
+    let restart_mode = flags
+        .remove("restart")
+        .map(|value| parse_value("restart", &value))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut install = InstallOptions::default();
+
+    apply_install_options(&mut install, &mut flags)?;
+
+    let info = ServerInfo {
+        name,
+        game,
+        description: None,
+        path,
+        map,
+        max_players,
+        password: flags.remove("password"),
+        port,
+        gslt: flags.remove("gslt"),
+        rcon_password: flags.remove("rcon-password"),
+        region,
+        tags: flags.remove("tags"),
+        tickrate,
+        restart_policy: RestartPolicy {
+            mode: restart_mode,
+            ..Default::default()
+        },
+        stop_policy: StopPolicy::default(),
+        extra_args: flags.remove("args"),
+        convars: vec![],
+        workshop: WorkshopItems::default(),
+        sourcemod: Default::default(),
+        metamod: Default::default(),
+        sourcemod_sha256: None,
+        metamod_sha256: None,
+        addon_verification: Default::default(),
+        install,
+        update_mode: UpdateMode::default(),
+        install_status: InstallStatus::Incomplete,
+    };
+
+    reject_unknown_flags(&flags)?;
+
+    let (config_path, mut servers) = load_servers().await?;
+
+    let id = servers.len();
+
+    // Listed before downloading, so an interrupted download can be resumed with `update`.
+    servers.push(Server::with_info(info.clone()));
+    servers.save(&config_path).await.context(ServerListSnafu)?;
+
+    download(&info, false).await?;
+
+    println!("Added '{}' as server [{id}].", info.name);
+
+    servers[id].info.install_status = InstallStatus::Installed;
+
+    servers.save(&config_path).await.context(ServerListSnafu)
+}
+
+async fn update(query: &str, mut flags: HashMap<String, String>) -> Result<(), Error> {
+    let (config_path, mut servers) = load_servers().await?;
+
+    let mut info = find_server(&servers, query)?.info.clone();
+
+    let is_changed = apply_install_options(&mut info.install, &mut flags)?;
+
+    let validate = flags
+        .remove("validate")
+        .map(|value| parse_value("validate", &value))
+        .transpose()?
+        .unwrap_or(false);
+
+    reject_unknown_flags(&flags)?;
+
+    download(&info, validate).await?;
+
+    let is_resumed = info.install_status == InstallStatus::Incomplete;
+
+    info.install_status = InstallStatus::Installed;
+
+    if (is_changed || is_resumed)
+        && let Some(server) = servers
+            .iter_mut()
+            .find(|server| server.info.path == info.path)
+    {
+        server.info = info;
+
+        servers.save(&config_path).await.context(ServerListSnafu)?;
+    }
+
+    Ok(())
+}
+
+async fn check_updates(
+    query: Option<&str>,
+    mut flags: HashMap<String, String>,
+) -> Result<(), Error> {
+    let (config_path, servers) = load_servers().await?;
+
+    let update = flags
+        .remove("update")
+        .map(|value| parse_value("update", &value))
+        .transpose()?
+        .unwrap_or(false);
+
+    reject_unknown_flags(&flags)?;
+
+    let checked = match query {
+        Some(query) => vec![find_server(&servers, query)?],
+        None => servers.iter().collect(),
+    };
+
+    for Server { info, .. } in checked {
+        let check = match updates::check(
+            &info.path,
+            info.game.into(),
+            info.install.beta.as_deref(),
+            &info.install.manifests,
+        )
+        .await
+        {
+            Ok(check) => check,
+            Err(error) => {
+                println!("{}: couldn't check for updates: {error}", info.name);
+
+                continue;
+            }
+        };
+
+        let build = |build_id: Option<u32>| {
+            build_id.map_or_else(|| "unknown".to_string(), |build_id| build_id.to_string())
+        };
+
+        if !check.is_outdated {
+            println!(
+                "{}: up to date (build {})",
+                info.name,
+                build(check.installed)
+            );
+
+            continue;
+        }
+
+        println!(
+            "{}: outdated (build {} -> {})",
+            info.name,
+            build(check.installed),
+            build(check.latest)
+        );
+
+        if update {
+            if running_process(&config_path, info).await.is_some() {
+                println!("{}: running, not updating it", info.name);
+            } else {
+                download(info, false).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn update_depotdownloader(mut flags: HashMap<String, String>) -> Result<(), Error> {
+    let version = flags
+        .remove("version")
+        .filter(|version| version != "latest");
+
+    let sha256 = flags.remove("sha256");
+
+    reject_unknown_flags(&flags)?;
+
+    let path = DepotDownloader::default_path().context(DepotDownloaderSnafu)?;
+    let previous = depotdownloader::installed_version(&path).await;
+
+    let version = depotdownloader::download(&path, version.as_deref(), sha256.as_deref())
+        .await
+        .context(DepotDownloaderSnafu)?;
+
+    match previous {
+        Some(previous) => println!("Replaced DepotDownloader {previous} with {version}"),
+        None => println!("Downloaded DepotDownloader {version}"),
+    }
+
+    if let Some(executable) = depotdownloader::local_executable() {
+        println!(
+            "{} is set, so {} is used instead",
+            depotdownloader::EXECUTABLE_VARIABLE,
+            executable.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads the install options out of `flags`, returning whether any was given.
+fn apply_install_options(
+    options: &mut InstallOptions,
+    flags: &mut HashMap<String, String>,
+) -> Result<bool, Error> {
+    let backend = flags.remove("installer");
+    let beta = flags.remove("beta");
+    let beta_password = flags.remove("beta-password");
+    let manifests = flags.remove("manifest");
+    let login = flags.remove("login");
+
+    let is_changed = backend.is_some()
+        || beta.is_some()
+        || beta_password.is_some()
+        || manifests.is_some()
+        || login.is_some();
+
+    if let Some(value) = backend {
+        options.backend = parse_value("installer", &value)?;
+    }
+
+    if let Some(value) = beta {
+        options.beta = (!value.is_empty()).then_some(value);
+    }
+
+    if let Some(value) = beta_password {
+        options.beta_password = (!value.is_empty()).then_some(value);
+    }
+
+    if let Some(value) = manifests {
+        options.manifests = value
+            .split(',')
+            .filter(|pin| !pin.trim().is_empty())
+            .map(|pin| parse_value("manifest", pin))
+            .collect::<Result<_, _>>()?;
+    }
+
+    if let Some(value) = login {
+        options.login = (!value.is_empty()).then_some(value);
+    }
+
+    Ok(is_changed)
+}
+
+async fn start(query: &str) -> Result<(), Error> {
+    let (config_path, servers) = load_servers().await?;
+
+    let server = find_server(&servers, query)?;
+    let info = &server.info;
+
+    if info.install_status == InstallStatus::Incomplete {
+        return IncompleteInstallSnafu { name: &info.name }.fail();
+    }
+
+    if let Some(running) = running_process(&config_path, info).await {
+        return AlreadyRunningSnafu { pid: running.pid }.fail();
+    }
+
+    let port = info
+        .port
+        .unwrap_or_else(|| find_available_port(Ipv4Addr::UNSPECIFIED));
+
+    let stdin_target = Arc::new(Mutex::new(None));
+
+    forward_stdin(stdin_target.clone());
+
+    let mut attempt = 0;
+
+    let result = loop {
+        println!("Starting '{}' on port {port}...", info.name);
+
+        let launched_at = Instant::now();
+
+        let exit_code = match run_console(&config_path, server, port, &stdin_target).await {
+            Ok(exit_code) => exit_code,
+            Err(err) => break Err(err),
+        };
+
+        if launched_at.elapsed() >= RestartPolicy::STABLE_UPTIME {
+            attempt = 0;
+        }
+
+        match info.restart_policy.restart_delay(exit_code, attempt) {
+            Some(delay) => {
+                attempt += 1;
+
+                println!(
+                    "The server stopped, restarting in {} seconds ({attempt}/{})...",
+                    delay.as_secs(),
+                    info.restart_policy.max_retries
+                );
+
+                tokio::time::sleep(delay).await;
+            }
+            None if exit_code == Some(0) => break Ok(()),
+            None => break ServerExitedSnafu { code: exit_code }.fail(),
+        }
+    };
+
+    update_runtime_state(&config_path, |state| state.remove(&info.path)).await;
+
+    result
+}
+
+/// Runs the server until its process exits, and returns the exit code.
+async fn run_console(
+    config_path: &Path,
+    server: &Server,
+    port: u16,
+    stdin_target: &StdinTarget,
+) -> Result<Option<i32>, Error> {
+    let info = &server.info;
+
+    let (binary_path, args) = server
+        .launch_command(port)
+        .context(UnsupportedGameSnafu { game: info.game })?;
+
+    if let Err(err) = server.write_motd() {
+        eprintln!("warning: unable to write the MOTD: {err}");
+    }
+
+    if let Err(err) = server.rotate_console_log() {
+        eprintln!("warning: unable to rotate the console log: {err}");
+    }
+
+    let mut console = pin!(Console::start(binary_path, args));
+
+    loop {
+        let Some(communication) = console.next().await else {
+            return Ok(None);
+        };
+
+        match communication.context(ConsoleSnafu)? {
+            ServerCommunicationTwoWay::Input(sender) => {
+                *stdin_target.lock().unwrap() = Some(sender);
+            }
+            ServerCommunicationTwoWay::Started(pid, started) => {
+                let Some(started) = started else {
+                    eprintln!(
+                        "warning: unable to read when the server started, `stop` won't find it"
+                    );
+
+                    continue;
+                };
+
+                let running = RunningProcess {
+                    path: info.path.clone(),
+                    pid,
+                    started,
+                    port,
+                    hosting_mode: server.hosting_mode,
+                };
+
+                update_runtime_state(config_path, |state| state.insert(running)).await;
+            }
+            ServerCommunicationTwoWay::Output(TextType::Output(line)) => println!("{line}"),
+            ServerCommunicationTwoWay::Output(TextType::Input(_)) => {}
+            ServerCommunicationTwoWay::Exited(exit_code) => return Ok(exit_code),
+        }
+    }
+}
+
+async fn stop(query: &str) -> Result<(), Error> {
+    let (config_path, servers) = load_servers().await?;
+
+    let info = &find_server(&servers, query)?.info;
+
+    let running = running_process(&config_path, info)
+        .await
+        .context(NotRunningSnafu { name: &info.name })?;
+
+    println!("Stopping '{}'...", info.name);
+
+    running
+        .stop(info.stop_policy, info.rcon_password.clone())
+        .await
+        .context(ProcessSnafu)?;
+
+    update_runtime_state(&config_path, |state| state.remove(&info.path)).await;
+
+    println!("Stopped '{}'.", info.name);
+
+    Ok(())
+}
+
+async fn install_sourcemod(query: &str, mut flags: HashMap<String, String>) -> Result<(), Error> {
+    let (config_path, mut servers) = load_servers().await?;
+
+    let mut info = find_server(&servers, query)?.info.clone();
+
+    let sourcemod = flags.remove("sourcemod");
+    let metamod = flags.remove("metamod");
+    let sourcemod_sha256 = flags.remove("sourcemod-sha256");
+    let metamod_sha256 = flags.remove("metamod-sha256");
+    let checksums = flags.remove("checksums");
+
+    reject_unknown_flags(&flags)?;
+
+    let is_changed = sourcemod.is_some()
+        || metamod.is_some()
+        || sourcemod_sha256.is_some()
+        || metamod_sha256.is_some()
+        || checksums.is_some();
+
+    if let Some(value) = sourcemod {
+        info.sourcemod = parse_value("sourcemod", &value)?;
+    }
+
+    if let Some(value) = metamod {
+        info.metamod = parse_value("metamod", &value)?;
+    }
+
+    // An empty hash unpins it.
+    if let Some(value) = sourcemod_sha256 {
+        info.sourcemod_sha256 = (!value.is_empty()).then_some(value);
+    }
+
+    if let Some(value) = metamod_sha256 {
+        info.metamod_sha256 = (!value.is_empty()).then_some(value);
+    }
+
+    if let Some(value) = checksums {
+        info.addon_verification = parse_value("checksums", &value)?;
+    }
+
+    let engine = games::source_game(info.game)
+        .map(|game_info| game_info.engine.clone())
+        .context(UnsupportedGameSnafu { game: info.game })?;
+
+    println!(
+        "Installing Metamod ({}) and SourceMod ({}) for '{}'...",
+        info.metamod, info.sourcemod, info.name
+    );
+
+    let mut install = Box::pin(setup_sourcemod(info.clone(), engine));
+    let mut last_stage = None;
+
+    while let Some((addon, progress)) = StreamExt::next(&mut install).await {
+        // Downloads report every chunk, so only the start of each stage is printed.
+        let stage = match progress {
+            InstallProgress::Resolving => "Looking up",
+            InstallProgress::Downloading { .. } => "Downloading",
+            InstallProgress::Verifying => "Verifying",
+            InstallProgress::Extracting => "Extracting",
+        };
+
+        if last_stage != Some((addon, stage)) {
+            println!("{stage} {addon}...");
+            last_stage = Some((addon, stage));
+        }
+    }
+
+    install.await.context(SourcemodSnafu)?;
+
+    println!("SourceMod has been installed.");
+
+    if is_changed
+        && let Some(server) = servers
+            .iter_mut()
+            .find(|server| server.info.path == info.path)
+    {
+        server.info = info;
+
+        servers.save(&config_path).await.context(ServerListSnafu)?;
+    }
+
+    Ok(())
+}
+
+async fn download(info: &ServerInfo, validate: bool) -> Result<(), Error> {
+    println!(
+        "Downloading {} into {} with {}...",
+        info.game,
+        info.path.display(),
+        info.install.backend
+    );
+
+    let mut download = Box::pin(download_server(
+        info.path.clone(),
+        info.game,
+        info.install.clone(),
+        validate,
+    ));
+
+    let mut repairs = None;
+
+    while let Some(update) = StreamExt::next(&mut download).await {
+        if let Some(line) = update.log {
+            println!("{}", line.trim_end());
+        }
+
+        repairs = Some(update.repairs);
+    }
+
+    download.await.context(DownloadSnafu)?;
+
+    if validate {
+        let repairs = repairs.unwrap_or_default();
+
+        println!(
+            "Validation repaired {} missing and {} damaged files",
+            repairs.missing.len(),
+            repairs.mismatched.len()
+        );
+
+        for path in &repairs.missing {
+            println!("  missing: {path}");
+        }
+
+        for path in &repairs.mismatched {
+            println!("  damaged: {path}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Where stdin goes, replaced every time the server is (re)started.
+type StdinTarget = Arc<Mutex<Option<mpsc::Sender<String>>>>;
+
+/// Sends every line typed on stdin to the server console.
+fn forward_stdin(target: StdinTarget) {
+    std::thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+
+            if let Some(sender) = target.lock().unwrap().as_mut() {
+                let _ = sender.try_send(line);
+            }
+        }
+    });
+}
+
+async fn load_servers() -> Result<(PathBuf, Servers), Error> {
+    let config_path = match get_config_path() {
+        Ok(path) => path,
+        Err(_) => create_config_file_path().await.context(ServerListSnafu)?,
+    };
+
+    let servers = Servers::fetch(&config_path)
+        .await
+        .context(ServerListSnafu)?;
+
+    Ok((config_path, servers))
+}
+
+fn find_server<'a>(servers: &'a Servers, query: &str) -> Result<&'a Server, Error> {
+    if let Some(server) = query.parse::<usize>().ok().and_then(|id| servers.get(id)) {
+        return Ok(server);
+    }
+
+    servers
+        .iter()
+        .find(|server| server.info.name.eq_ignore_ascii_case(query))
+        .context(ServerNotFoundSnafu { query })
+}
+
+fn server_query(positional: &[String]) -> Result<&str, Error> {
+    positional
+        .first()
+        .map(String::as_str)
+        .context(MissingArgumentSnafu { argument: "server" })
+}
+
+/// Accepts both the display name ("Team Fortress 2") and the game folder ("tf").
+fn parse_game(value: &str) -> Result<Game, Error> {
+    value
+        .parse::<Game>()
+        .ok()
+        .or_else(|| {
+            SOURCE_GAMES
+                .iter()
+                .map(|game_info| game_info.game)
+                .find(|game| game.arg_name().eq_ignore_ascii_case(value))
+        })
+        .context(InvalidValueSnafu {
+            argument: "game",
+            value,
+        })
+}
+
+fn parse_value<T: std::str::FromStr>(argument: &'static str, value: &str) -> Result<T, Error> {
+    value
+        .parse()
+        .ok()
+        .context(InvalidValueSnafu { argument, value })
+}
+
+fn parse_args(
+    args: impl Iterator<Item = String>,
+) -> Result<(Vec<String>, HashMap<String, String>), Error> {
+    let mut positional = vec![];
+    let mut flags = HashMap::new();
+
+    let mut args = args.peekable();
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            positional.push(arg);
+
+            continue;
+        };
+
+        let value = args
+            .next_if(|value| !value.starts_with("--"))
+            .context(MissingValueSnafu { flag })?;
+
+        flags.insert(flag.to_string(), value);
+    }
+
+    Ok((positional, flags))
+}
+
+/// Fails on a flag the command didn't take, e.g. a misspelled one, instead of going ahead
+/// without it.
+fn reject_unknown_flags(flags: &HashMap<String, String>) -> Result<(), Error> {
+    match flags.keys().min() {
+        Some(flag) => UnknownFlagSnafu { flag }.fail(),
+        None => Ok(()),
+    }
+}
+
+/// The process started for `info` by either the GUI or the CLI, if it's still alive.
+async fn running_process(config_path: &Path, info: &ServerInfo) -> Option<RunningProcess> {
+    RuntimeState::load(&runtime_state_path(config_path))
+        .await
+        .get(&info.path)
+        .filter(|running| running.is_alive())
+        .cloned()
+}
+
+async fn update_runtime_state(config_path: &Path, update: impl FnOnce(&mut RuntimeState)) {
+    let path = runtime_state_path(config_path);
+
+    let mut state = RuntimeState::load(&path).await;
+
+    update(&mut state);
+
+    let _ = state.save(&path).await;
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("unknown command '{command}', run `mannager help` for the list of commands"))]
+    UnknownCommand { command: String },
+
+    #[snafu(display("missing required argument '{argument}'"))]
+    MissingArgument { argument: &'static str },
+
+    #[snafu(display("'--{flag}' expects a value"))]
+    MissingValue { flag: String },
+
+    #[snafu(display("unknown flag '--{flag}', run `mannager help` for the flags of each command"))]
+    UnknownFlag { flag: String },
+
+    #[snafu(display("invalid value '{value}' for '{argument}'"))]
+    InvalidValue {
+        argument: &'static str,
+        value: String,
+    },
+
+    #[snafu(display("no server matches '{query}'"))]
+    ServerNotFound { query: String },
+
+    #[snafu(display("{game} is not supported"))]
+    UnsupportedGame { game: Game },
+
+    #[snafu(display("the server is already running (pid {pid})"))]
+    AlreadyRunning { pid: u32 },
+
+    #[snafu(display("'{name}' is not running"))]
+    NotRunning { name: String },
+
+    #[snafu(display("'{name}' wasn't fully downloaded, resume it with `mannager update`"))]
+    IncompleteInstall { name: String },
+
+    #[snafu(display(
+        "the server exited with {}",
+        code.map_or("a signal".to_string(), |code| format!("code {code}"))
+    ))]
+    ServerExited { code: Option<i32> },
+
+    #[snafu(display("{source}"))]
+    ServerListError { source: serverlist::Error },
+
+    #[snafu(display("{source}"))]
+    SourcemodError { source: serverlist::Error },
+
+    #[snafu(display("{source}"))]
+    DownloadError { source: servercreation::Error },
+
+    #[snafu(display("{source}"))]
+    ConsoleError { source: serverboot::Error },
+
+    #[snafu(display("{source}"))]
+    ProcessError { source: core::Error },
+
+    #[snafu(display("{source}"))]
+    DepotDownloaderError { source: core::Error },
+}
@@ -1,13 +1,32 @@
-use std::{io, sync::Arc};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use decoder::Value;
 use snafu::prelude::*;
 use zip::result::ZipError;
 
+pub mod a2s;
+pub mod addons;
+pub mod alliedmods;
+pub mod appmanifest;
+pub mod archive;
+pub mod cfg;
 pub mod depotdownloader;
+pub mod installer;
+pub mod launch;
+pub mod maps;
 pub mod metamod;
+pub mod plugins;
 pub mod portforwarder;
+pub mod process;
+pub mod rcon;
 pub mod sourcemod;
+pub mod steamcmd;
+pub mod updates;
+pub mod workshop;
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub enum SourceEngineVersion {
@@ -58,6 +77,19 @@ impl Game {
         }
     }
 
+    /// The folder of the game inside a server installed in `path`, e.g. `tf` or `game/csgo`.
+    /// Source 2 games keep theirs in `game`, under the name the game had in development.
+    pub fn directory(&self, path: &Path, engine: &SourceEngineVersion) -> PathBuf {
+        match engine {
+            SourceEngineVersion::Source1 => path.join(self.arg_name()),
+            SourceEngineVersion::Source2 => path.join("game").join(match self {
+                Game::CounterStrike2 => "csgo",
+                Game::Deadlock => "citadel",
+                game => game.arg_name(),
+            }),
+        }
+    }
+
     pub fn decode(value: Value) -> Result<Self, decoder::Error> {
         use decoder::decode::string;
 
@@ -130,6 +162,12 @@ impl From<Game> for u32 {
     }
 }
 
+/// The folder MANNager keeps its own files in, e.g. the tools it downloads.
+pub fn data_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "MANNager", "mannager-source")
+        .map(|project_path| project_path.data_dir().to_path_buf())
+}
+
 #[derive(Snafu, Debug, Clone)]
 #[snafu(visibility(pub))]
 pub enum Error {
@@ -157,8 +195,54 @@ pub enum Error {
         source: Arc<io::Error>,
     },
 
-    #[snafu(display("Failed to retrieve the latest version"))]
-    UnableToFindLatestVersionError,
+    #[snafu(display("No running process with PID {pid}"))]
+    ProcessNotFoundError { pid: u32 },
+
+    #[snafu(display("{url} answered with HTTP {status}"))]
+    HttpStatusError { url: String, status: u16 },
+
+    #[snafu(display("Couldn't find a release in {url}"))]
+    ListingParseError { url: String },
+
+    #[snafu(display("Version {version} isn't available for this platform"))]
+    UnableToFindVersionError { version: String },
+
+    #[snafu(display("'{name}' isn't a .smx plugin, nor a .zip or .tar.gz archive"))]
+    UnsupportedPluginFileError { name: String },
+
+    #[snafu(display("The archive doesn't contain any SourceMod plugin"))]
+    EmptyPluginArchiveError,
+
+    #[snafu(display("'{name}' doesn't match its checksum, expected {expected} but got {actual}"))]
+    ChecksumMismatchError {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[snafu(display("'{name}' is {size} bytes, more than the {limit} bytes allowed"))]
+    ArchiveTooLargeError { name: String, size: u64, limit: u64 },
+
+    #[snafu(display("Couldn't find a folder to keep MANNager's files in"))]
+    NoDataDirectoryError,
+
+    #[snafu(display("The install failed: {message}"))]
+    InstallFailedError { message: String },
+
+    #[snafu(display("{installer} doesn't support {option}"))]
+    UnsupportedInstallOptionError { installer: String, option: String },
+
+    #[snafu(display("Couldn't read the builds of app {app}"))]
+    AppInfoParseError { app: u32 },
+
+    #[snafu(display("'{name}' has no published checksum, pass one to check it against"))]
+    MissingChecksumError { name: String },
+
+    #[snafu(display("There's no executable at {path}"))]
+    ExecutableNotFoundError { path: String },
+
+    #[snafu(display("The plugin index couldn't be read"))]
+    InvalidPluginIndexError,
 
     #[snafu(display("io failed: {source}"))]
     Io {
@@ -175,4 +259,16 @@ pub enum ExtractError {
 
     #[snafu(display("{source}"))]
     TarError { source: io::Error },
+
+    #[snafu(display("Failed to write an extracted file: {source}"))]
+    WriteError { source: io::Error },
+
+    #[snafu(display("Unknown archive format"))]
+    UnknownFormatError,
+
+    #[snafu(display("Refusing to extract '{path}', it points outside of the destination"))]
+    UnsafeEntryError { path: String },
+
+    #[snafu(display("The archive unpacks to more than the {limit} bytes allowed"))]
+    ExtractedSizeError { limit: u64 },
 }
@@ -0,0 +1,527 @@
+//! Source server queries ( A2S_INFO, A2S_PLAYER and A2S_RULES ) over UDP.
+//!
+//! See https://developer.valvesoftware.com/wiki/Server_queries
+
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use snafu::{OptionExt, ResultExt, Snafu};
+use tokio::{net::UdpSocket, time::timeout};
+
+const SINGLE_PACKET: i32 = -1;
+const SPLIT_PACKET: i32 = -2;
+
+const A2S_INFO: u8 = 0x54;
+const A2S_PLAYER: u8 = 0x55;
+const A2S_RULES: u8 = 0x56;
+
+const S2C_CHALLENGE: u8 = 0x41;
+const S2A_INFO: u8 = 0x49;
+const S2A_PLAYER: u8 = 0x44;
+const S2A_RULES: u8 = 0x45;
+
+const MAX_PACKET_SIZE: usize = 1400;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Info {
+    pub protocol: u8,
+    pub name: String,
+    pub map: String,
+    pub folder: String,
+    pub game: String,
+    pub app_id: u16,
+    pub players: u8,
+    pub max_players: u8,
+    pub bots: u8,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub name: String,
+    pub score: i32,
+    pub duration: Duration,
+}
+
+/// Everything the app shows about a running server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Status {
+    pub info: Info,
+    pub players: Vec<Player>,
+    pub latency: Duration,
+}
+
+pub struct Client {
+    socket: UdpSocket,
+    timeout: Duration,
+}
+
+impl Client {
+    pub async fn connect(address: SocketAddr) -> Result<Self, Error> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+            .await
+            .context(IoSnafu)?;
+
+        socket.connect(address).await.context(IoSnafu)?;
+
+        Ok(Self {
+            socket,
+            timeout: DEFAULT_TIMEOUT,
+        })
+    }
+
+    pub async fn info(&self) -> Result<Info, Error> {
+        let mut request = vec![0xFF, 0xFF, 0xFF, 0xFF, A2S_INFO];
+        request.extend_from_slice(b"Source Engine Query\0");
+
+        let mut response = self.request(&request).await?;
+
+        // Newer servers answer with a challenge that has to be appended to the request.
+        if let Some(challenge) = parse_challenge(&response)? {
+            request.extend_from_slice(&challenge);
+
+            response = self.request(&request).await?;
+        }
+
+        parse_info(&response)
+    }
+
+    pub async fn players(&self) -> Result<Vec<Player>, Error> {
+        let response = self.challenged_request(A2S_PLAYER).await?;
+
+        parse_players(&response)
+    }
+
+    pub async fn rules(&self) -> Result<BTreeMap<String, String>, Error> {
+        let response = self.challenged_request(A2S_RULES).await?;
+
+        parse_rules(&response)
+    }
+
+    async fn challenged_request(&self, kind: u8) -> Result<Vec<u8>, Error> {
+        let mut request = [0xFF, 0xFF, 0xFF, 0xFF, kind, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        let response = self.request(&request).await?;
+
+        let Some(challenge) = parse_challenge(&response)? else {
+            return Ok(response);
+        };
+
+        request[5..].copy_from_slice(&challenge);
+
+        self.request(&request).await
+    }
+
+    /// Sends `request` and returns the payload of the answer, without the packet header.
+    async fn request(&self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        self.socket.send(request).await.context(IoSnafu)?;
+
+        let mut fragments: BTreeMap<u8, Vec<u8>> = BTreeMap::new();
+
+        loop {
+            let mut buffer = [0u8; MAX_PACKET_SIZE];
+
+            let length = timeout(self.timeout, self.socket.recv(&mut buffer))
+                .await
+                .map_err(|_| Error::TimedOut)?
+                .context(IoSnafu)?;
+
+            if let Some(payload) = reassemble(&buffer[..length], &mut fragments)? {
+                return Ok(payload);
+            }
+        }
+    }
+}
+
+/// Adds `packet` to the `fragments` of a split answer, returning the payload once it's whole.
+fn reassemble(
+    packet: &[u8],
+    fragments: &mut BTreeMap<u8, Vec<u8>>,
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut packet = Reader::new(packet);
+
+    match packet.i32()? {
+        SINGLE_PACKET => Ok(Some(packet.rest().to_vec())),
+        SPLIT_PACKET => {
+            let id = packet.i32()?;
+
+            // The high bit marks bzip2 compressed payloads, only used by ancient engine versions.
+            if id as u32 & 0x8000_0000 != 0 {
+                return Err(Error::CompressedPacket);
+            }
+
+            let total = packet.u8()?;
+            let number = packet.u8()?;
+            let _size = packet.u16()?;
+
+            fragments.insert(number, packet.rest().to_vec());
+
+            if fragments.len() < total as usize {
+                return Ok(None);
+            }
+
+            let payload: Vec<u8> = std::mem::take(fragments).into_values().flatten().collect();
+
+            let mut payload = Reader::new(&payload);
+
+            if payload.i32()? != SINGLE_PACKET {
+                return Err(Error::MalformedPacket);
+            }
+
+            Ok(Some(payload.rest().to_vec()))
+        }
+        _ => Err(Error::MalformedPacket),
+    }
+}
+
+/// The challenge to send the request again with, if the server answered with one.
+fn parse_challenge(payload: &[u8]) -> Result<Option<[u8; 4]>, Error> {
+    let mut packet = Reader::new(payload);
+
+    if packet.u8()? != S2C_CHALLENGE {
+        return Ok(None);
+    }
+
+    packet.take().map(Some)
+}
+
+/// Queries the info and the player list of the server at `address`.
+pub async fn query(address: SocketAddr) -> Result<Status, Error> {
+    let client = Client::connect(address).await?;
+
+    let start = Instant::now();
+
+    let info = client.info().await?;
+
+    let latency = start.elapsed();
+
+    let players = client.players().await.unwrap_or_default();
+
+    Ok(Status {
+        info,
+        players,
+        latency,
+    })
+}
+
+fn parse_info(payload: &[u8]) -> Result<Info, Error> {
+    let mut packet = Reader::new(payload);
+
+    if packet.u8()? != S2A_INFO {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let protocol = packet.u8()?;
+    let name = packet.string()?;
+    let map = packet.string()?;
+    let folder = packet.string()?;
+    let game = packet.string()?;
+    let app_id = packet.u16()?;
+    let players = packet.u8()?;
+    let max_players = packet.u8()?;
+    let bots = packet.u8()?;
+    let _server_type = packet.u8()?;
+    let _environment = packet.u8()?;
+    let _visibility = packet.u8()?;
+    let _vac = packet.u8()?;
+    let version = packet.string()?;
+
+    Ok(Info {
+        protocol,
+        name,
+        map,
+        folder,
+        game,
+        app_id,
+        players,
+        max_players,
+        bots,
+        version,
+    })
+}
+
+fn parse_players(payload: &[u8]) -> Result<Vec<Player>, Error> {
+    let mut packet = Reader::new(payload);
+
+    if packet.u8()? != S2A_PLAYER {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let count = packet.u8()?;
+
+    (0..count)
+        .map(|_| {
+            let _index = packet.u8()?;
+
+            Ok(Player {
+                name: packet.string()?,
+                score: packet.i32()?,
+                duration: Duration::try_from_secs_f32(packet.f32()?).unwrap_or_default(),
+            })
+        })
+        .collect()
+}
+
+fn parse_rules(payload: &[u8]) -> Result<BTreeMap<String, String>, Error> {
+    let mut packet = Reader::new(payload);
+
+    if packet.u8()? != S2A_RULES {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let count = packet.u16()?;
+
+    (0..count)
+        .map(|_| Ok((packet.string()?, packet.string()?)))
+        .collect()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let (head, tail) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .context(MalformedPacketSnafu)?;
+
+        self.bytes = tail;
+
+        Ok(*head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(u8::from_le_bytes(self.take()?))
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    fn f32(&mut self) -> Result<f32, Error> {
+        Ok(f32::from_le_bytes(self.take()?))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let end = self
+            .bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .context(MalformedPacketSnafu)?;
+
+        let string = String::from_utf8_lossy(&self.bytes[..end]).into_owned();
+
+        self.bytes = &self.bytes[end + 1..];
+
+        Ok(string)
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+#[derive(Snafu, Debug, Clone)]
+pub enum Error {
+    #[snafu(display("The server didn't answer in time"))]
+    TimedOut,
+
+    #[snafu(display("The server sent a malformed packet"))]
+    MalformedPacket,
+
+    #[snafu(display("The server answered with an unexpected packet"))]
+    UnexpectedResponse,
+
+    #[snafu(display("Compressed responses are not supported"))]
+    CompressedPacket,
+
+    #[snafu(display("io failed: {source}"))]
+    Io {
+        #[snafu(source(from(io::Error, Arc::new)))]
+        source: Arc<io::Error>,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: [u8; 4] = [0xFF; 4];
+
+    fn split(id: i32, total: u8, number: u8, payload: &[u8]) -> Vec<u8> {
+        let mut packet = SPLIT_PACKET.to_le_bytes().to_vec();
+        packet.extend(id.to_le_bytes());
+        packet.extend([total, number]);
+        packet.extend(1248u16.to_le_bytes());
+        packet.extend(payload);
+        packet
+    }
+
+    #[test]
+    fn info_is_parsed() {
+        let mut payload = vec![S2A_INFO, 17];
+        payload.extend(b"My Server\0ctf_2fort\0tf\0Team Fortress\0");
+        payload.extend(440u16.to_le_bytes());
+        payload.extend([12, 24, 2, b'd', b'l', 0, 1]);
+        payload.extend(b"9543365\0");
+
+        let info = parse_info(&payload).unwrap();
+
+        assert_eq!(
+            info,
+            Info {
+                protocol: 17,
+                name: "My Server".to_string(),
+                map: "ctf_2fort".to_string(),
+                folder: "tf".to_string(),
+                game: "Team Fortress".to_string(),
+                app_id: 440,
+                players: 12,
+                max_players: 24,
+                bots: 2,
+                version: "9543365".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn truncated_info_is_malformed() {
+        let payload = [S2A_INFO, 17, b'M', b'y'];
+
+        assert!(matches!(parse_info(&payload), Err(Error::MalformedPacket)));
+    }
+
+    #[test]
+    fn other_answers_are_unexpected() {
+        assert!(matches!(
+            parse_info(&[S2A_RULES, 0, 0]),
+            Err(Error::UnexpectedResponse)
+        ));
+        assert!(matches!(
+            parse_players(&[S2A_INFO, 0]),
+            Err(Error::UnexpectedResponse)
+        ));
+    }
+
+    #[test]
+    fn players_are_parsed() {
+        let mut payload = vec![S2A_PLAYER, 2];
+
+        for (name, score, duration) in [("Scout", 10, 61.5f32), ("Heavy", -2, 3.0)] {
+            payload.push(0);
+            payload.extend(name.as_bytes());
+            payload.push(0);
+            payload.extend(i32::to_le_bytes(score));
+            payload.extend(duration.to_le_bytes());
+        }
+
+        let players = parse_players(&payload).unwrap();
+
+        assert_eq!(
+            players,
+            [
+                Player {
+                    name: "Scout".to_string(),
+                    score: 10,
+                    duration: Duration::from_secs_f32(61.5),
+                },
+                Player {
+                    name: "Heavy".to_string(),
+                    score: -2,
+                    duration: Duration::from_secs(3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rules_are_parsed() {
+        let mut payload = vec![S2A_RULES];
+        payload.extend(2u16.to_le_bytes());
+        payload.extend(b"mp_timelimit\0");
+        payload.extend(b"30\0");
+        payload.extend(b"sv_tags\0payload,cp\0");
+
+        let rules = parse_rules(&payload).unwrap();
+
+        assert_eq!(rules.get("mp_timelimit").map(String::as_str), Some("30"));
+        assert_eq!(rules.get("sv_tags").map(String::as_str), Some("payload,cp"));
+    }
+
+    #[test]
+    fn challenges_are_recognized() {
+        assert_eq!(
+            parse_challenge(&[S2C_CHALLENGE, 0x12, 0x34, 0x56, 0x78]).unwrap(),
+            Some([0x12, 0x34, 0x56, 0x78])
+        );
+        assert_eq!(parse_challenge(&[S2A_INFO, 17]).unwrap(), None);
+        assert!(matches!(
+            parse_challenge(&[S2C_CHALLENGE, 0x12]),
+            Err(Error::MalformedPacket)
+        ));
+    }
+
+    #[test]
+    fn single_packets_are_returned_whole() {
+        let mut packet = HEADER.to_vec();
+        packet.extend([S2A_INFO, 17]);
+
+        let payload = reassemble(&packet, &mut BTreeMap::new()).unwrap();
+
+        assert_eq!(payload, Some(vec![S2A_INFO, 17]));
+    }
+
+    #[test]
+    fn split_packets_are_joined_in_order() {
+        let mut message = HEADER.to_vec();
+        message.extend([S2A_RULES, 1, 0]);
+        message.extend(b"key\0value\0");
+
+        let (first, second) = message.split_at(6);
+
+        let mut fragments = BTreeMap::new();
+
+        // Fragments can arrive out of order.
+        assert_eq!(
+            reassemble(&split(7, 2, 1, second), &mut fragments).unwrap(),
+            None
+        );
+
+        let payload = reassemble(&split(7, 2, 0, first), &mut fragments)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(payload, message[4..]);
+        assert!(fragments.is_empty());
+
+        let rules = parse_rules(&payload).unwrap();
+
+        assert_eq!(rules.get("key").map(String::as_str), Some("value"));
+    }
+
+    #[test]
+    fn compressed_split_packets_are_refused() {
+        let packet = split(0x8000_0007u32 as i32, 2, 0, &[]);
+
+        assert!(matches!(
+            reassemble(&packet, &mut BTreeMap::new()),
+            Err(Error::CompressedPacket)
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+//! The SourceMod and Metamod:Source versions installed in a server.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    Error, SourceEngineVersion,
+    metamod::{self, MetamodBranch},
+    sourcemod::{self, SourcemodBranch},
+};
+
+/// A release, e.g. `1.12.0.7110`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+    pub build: u32,
+}
+
+impl Version {
+    /// Reads the version of a release archive, e.g. `sourcemod-1.12.0-git7110-linux.tar.gz`.
+    pub fn from_archive_name(name: &str) -> Option<Self> {
+        let (_, rest) = name.split_once('-')?;
+
+        parse_prefix(rest.as_bytes()).map(|(version, _)| version)
+    }
+
+    /// The newest version string found in a binary, e.g. `1.12.0.7110` or `1.12.0-dev+1219`.
+    pub fn find_in(bytes: &[u8]) -> Option<Self> {
+        let mut newest = None;
+        let mut index = 0;
+
+        while index < bytes.len() {
+            let is_start = bytes[index].is_ascii_digit()
+                && (index == 0 || !matches!(bytes[index - 1], b'0'..=b'9' | b'.'));
+
+            match is_start.then(|| parse_prefix(&bytes[index..])).flatten() {
+                Some((version, length)) => {
+                    newest = newest.max(Some(version));
+                    index += length;
+                }
+                None => index += 1,
+            }
+        }
+
+        newest
+    }
+}
+
+impl std::str::FromStr for Version {
+    type Err = String;
+
+    /// Parses a version written like [`Version`] displays it, e.g. `1.11.0.6968`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_prefix(s.as_bytes())
+            .filter(|(_, length)| *length == s.len())
+            .map(|(version, _)| version)
+            .ok_or_else(|| format!("'{s}' is not a valid version"))
+    }
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}.{}",
+            self.major, self.minor, self.patch, self.build
+        )
+    }
+}
+
+/// Parses a version at the start of `bytes`, returning it with its length.
+fn parse_prefix(bytes: &[u8]) -> Option<(Version, usize)> {
+    let mut position = 0;
+
+    let number = |position: &mut usize| -> Option<u32> {
+        let length = bytes[*position..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+
+        // Longer numbers aren't part of a version.
+        if length == 0 || length > 6 {
+            return None;
+        }
+
+        let number = std::str::from_utf8(&bytes[*position..*position + length])
+            .ok()?
+            .parse()
+            .ok()?;
+
+        *position += length;
+
+        Some(number)
+    };
+
+    let separator = |position: &mut usize, separators: &[&str]| -> Option<()> {
+        let separator = separators
+            .iter()
+            .find(|separator| bytes[*position..].starts_with(separator.as_bytes()))?;
+
+        *position += separator.len();
+
+        Some(())
+    };
+
+    let major = number(&mut position)?;
+    separator(&mut position, &["."])?;
+    let minor = number(&mut position)?;
+    separator(&mut position, &["."])?;
+    let patch = number(&mut position)?;
+    separator(&mut position, &[".", "-dev+", "-git", "+"])?;
+    let build = number(&mut position)?;
+
+    // Something like an IP address, or a longer dotted number.
+    if !(1..=2).contains(&major) || matches!(bytes.get(position), Some(b'.')) {
+        return None;
+    }
+
+    Some((
+        Version {
+            major,
+            minor,
+            patch,
+            build,
+        },
+        position,
+    ))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Addon {
+    Sourcemod,
+    Metamod,
+}
+
+impl std::fmt::Display for Addon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Addon::Sourcemod => "SourceMod",
+            Addon::Metamod => "Metamod:Source",
+        })
+    }
+}
+
+/// How far the install of an addon got, reported as it goes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InstallProgress {
+    /// Looking up the archive of the build to install.
+    Resolving,
+    Downloading {
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    Verifying,
+    Extracting,
+}
+
+impl InstallProgress {
+    /// The share of the archive downloaded, from 0 to 100, once its size is known.
+    pub fn percent(&self) -> Option<f32> {
+        match self {
+            InstallProgress::Downloading {
+                downloaded,
+                total: Some(total),
+            } if *total > 0 => Some(*downloaded as f32 / *total as f32 * 100.0),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AddonVersions {
+    pub sourcemod: Option<Version>,
+    pub metamod: Option<Version>,
+}
+
+impl AddonVersions {
+    pub fn is_empty(&self) -> bool {
+        self.sourcemod.is_none() && self.metamod.is_none()
+    }
+
+    /// Whether any installed addon is older than its version in `latest`.
+    pub fn is_outdated(&self, latest: &AddonVersions) -> bool {
+        let is_older = |installed: Option<Version>, latest: Option<Version>| {
+            installed
+                .zip(latest)
+                .is_some_and(|(installed, latest)| installed < latest)
+        };
+
+        is_older(self.sourcemod, latest.sourcemod) || is_older(self.metamod, latest.metamod)
+    }
+}
+
+/// The versions installed in `game_directory`, read from the addons' binaries.
+pub async fn installed(game_directory: PathBuf) -> AddonVersions {
+    tokio::task::spawn_blocking(move || {
+        let addons = game_directory.join("addons");
+
+        AddonVersions {
+            sourcemod: find_version(&addons.join("sourcemod").join("bin"), &["sourcemod.logic"]),
+            metamod: find_version(&addons.join("metamod").join("bin"), &["metamod.", "server"]),
+        }
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// The latest stable versions for `engine`.
+pub async fn latest(engine: SourceEngineVersion) -> Result<AddonVersions, Error> {
+    let (sourcemod, metamod) = tokio::join!(
+        sourcemod::latest_version(&SourcemodBranch::Stable, &engine),
+        metamod::latest_version(&MetamodBranch::Stable, &engine),
+    );
+
+    Ok(AddonVersions {
+        sourcemod: Some(sourcemod?),
+        metamod: Some(metamod?),
+    })
+}
+
+/// The newest version in the binaries of `directory`, and of its 64 bit subfolders, whose
+/// name starts with one of `prefixes`.
+fn find_version(directory: &Path, prefixes: &[&str]) -> Option<Version> {
+    ["", "x64", "linux64", "win64"]
+        .iter()
+        .filter_map(|subdirectory| fs::read_dir(directory.join(subdirectory)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            prefixes.iter().any(|prefix| name.starts_with(prefix))
+                && (name.ends_with(".so") || name.ends_with(".dll"))
+        })
+        .filter_map(|entry| fs::read(entry.path()).ok())
+        .filter_map(|bytes| Version::find_in(&bytes))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(major: u32, minor: u32, patch: u32, build: u32) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+            build,
+        }
+    }
+
+    #[test]
+    fn versions_are_read_from_archive_names() {
+        assert_eq!(
+            Version::from_archive_name("sourcemod-1.12.0-git7110-linux.tar.gz"),
+            Some(version(1, 12, 0, 7110))
+        );
+        assert_eq!(
+            Version::from_archive_name("mmsource-2.0.0-git1313-windows.zip"),
+            Some(version(2, 0, 0, 1313))
+        );
+        assert_eq!(Version::from_archive_name("sourcemod-latest-linux"), None);
+    }
+
+    #[test]
+    fn versions_are_parsed() {
+        assert_eq!("1.11.0.6968".parse(), Ok(version(1, 11, 0, 6968)));
+        assert!("1.11.0".parse::<Version>().is_err());
+        assert!("1.11.0.6968-linux".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn versions_are_found_in_binaries() {
+        let bytes = b"\0\x7fELF\0127.0.0.1\0GCC 4.8.5\0SourceMod 1.12.0.7110\0\x01\x02";
+
+        assert_eq!(Version::find_in(bytes), Some(version(1, 12, 0, 7110)));
+
+        let bytes = b"Metamod:Source\01.11.0-dev+1148\0\01.12.0-dev+1219\0";
+
+        assert_eq!(Version::find_in(bytes), Some(version(1, 12, 0, 1219)));
+        assert_eq!(Version::find_in(b"1.2.3.4.5 and 10.0.0.1"), None);
+    }
+
+    #[test]
+    fn older_installs_are_outdated() {
+        let installed = AddonVersions {
+            sourcemod: Some(version(1, 11, 0, 6968)),
+            metamod: Some(version(1, 12, 0, 1219)),
+        };
+
+        let latest = AddonVersions {
+            sourcemod: Some(version(1, 12, 0, 7110)),
+            metamod: Some(version(1, 12, 0, 1219)),
+        };
+
+        assert!(installed.is_outdated(&latest));
+        assert!(!latest.is_outdated(&latest));
+        assert!(!AddonVersions::default().is_outdated(&latest));
+    }
+}
@@ -0,0 +1,224 @@
+//! The drop listings SourceMod and Metamod:Source builds are published in, e.g.
+//! <https://sm.alliedmods.net/smdrop>.
+//!
+//! A listing is a plain directory index: the root has a `major.minor` folder per release
+//! line, and every folder has the archives of its builds, plus a `<prefix>-latest-<os>` file
+//! with the name of the newest one.
+
+use scraper::{Html, Selector};
+
+use super::{SourceEngineVersion, addons::Version};
+
+#[cfg(target_os = "windows")]
+pub const TARGET_OS: &str = "windows";
+
+#[cfg(target_os = "linux")]
+pub const TARGET_OS: &str = "linux";
+
+/// A release line folder, e.g. `1.12`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Folder {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl std::fmt::Display for Folder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The newest release lines of an engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatestFolders {
+    pub stable: Folder,
+    /// The newest line, which is still in development.
+    pub dev: Folder,
+}
+
+/// The drop listing URL set through `variable`, or `default`, e.g. to use a mirror.
+pub fn base_url(variable: &str, default: &str) -> String {
+    std::env::var(variable)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// The release lines for `engine` in the root listing, oldest first.
+pub fn folders(page_contents: &str, engine: &SourceEngineVersion) -> Vec<Folder> {
+    let major: u32 = engine.clone().into();
+
+    let mut folders: Vec<Folder> = links(page_contents)
+        .iter()
+        .filter_map(|link| {
+            let (folder_major, minor) = link.trim_end_matches('/').split_once('.')?;
+
+            Some(Folder {
+                major: folder_major.parse().ok()?,
+                minor: minor.parse().ok()?,
+            })
+        })
+        .filter(|folder| folder.major == major)
+        .collect();
+
+    folders.sort_unstable();
+    folders.dedup();
+
+    folders
+}
+
+/// The newest line for `engine` is the development one, and the one before it the stable one.
+/// When there's a single line, it's both.
+pub fn latest_folders(page_contents: &str, engine: &SourceEngineVersion) -> Option<LatestFolders> {
+    let folders = folders(page_contents, engine);
+
+    let (&dev, older) = folders.split_last()?;
+
+    Some(LatestFolders {
+        stable: older.last().copied().unwrap_or(dev),
+        dev,
+    })
+}
+
+/// The archives for `os` in the listing of a folder, named like `<prefix>-1.12.0-git7110-linux.tar.gz`.
+pub fn archives(page_contents: &str, prefix: &str, os: &str) -> Vec<(Version, String)> {
+    links(page_contents)
+        .into_iter()
+        .filter(|name| {
+            name.strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with('-'))
+                && name.contains(&format!("-{os}"))
+                && (name.ends_with(".tar.gz") || name.ends_with(".zip"))
+        })
+        .filter_map(|name| Some((Version::from_archive_name(&name)?, name)))
+        .collect()
+}
+
+/// The archive name in a `<prefix>-latest-<os>` file.
+pub fn latest_archive_name(contents: &str) -> Option<String> {
+    let name = contents.trim();
+
+    (!name.contains(['/', '\\']) && Version::from_archive_name(name).is_some())
+        .then(|| name.to_string())
+}
+
+/// The targets of the links in a listing. Directory indexes may shorten the text of long
+/// names, so the `href` is used when there's one.
+fn links(page_contents: &str) -> Vec<String> {
+    let html = Html::parse_document(page_contents);
+
+    let Ok(a_selector) = Selector::parse("a") else {
+        return vec![];
+    };
+
+    html.select(&a_selector)
+        .map(|element| {
+            element
+                .value()
+                .attr("href")
+                .map(str::to_string)
+                .unwrap_or_else(|| element.inner_html())
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMDROP: &str = include_str!("alliedmods/fixtures/smdrop.html");
+    const SMDROP_FOLDER: &str = include_str!("alliedmods/fixtures/smdrop-1.12.html");
+    const MMSDROP: &str = include_str!("alliedmods/fixtures/mmsdrop.html");
+
+    fn folder(major: u32, minor: u32) -> Folder {
+        Folder { major, minor }
+    }
+
+    #[test]
+    fn folders_are_listed_by_engine() {
+        assert_eq!(
+            folders(SMDROP, &SourceEngineVersion::Source1),
+            [
+                folder(1, 9),
+                folder(1, 10),
+                folder(1, 11),
+                folder(1, 12),
+                folder(1, 13)
+            ]
+        );
+        assert_eq!(folders(SMDROP, &SourceEngineVersion::Source2), []);
+    }
+
+    #[test]
+    fn stable_is_the_line_before_dev() {
+        assert_eq!(
+            latest_folders(SMDROP, &SourceEngineVersion::Source1),
+            Some(LatestFolders {
+                stable: folder(1, 12),
+                dev: folder(1, 13),
+            })
+        );
+        assert_eq!(
+            latest_folders(MMSDROP, &SourceEngineVersion::Source1),
+            Some(LatestFolders {
+                stable: folder(1, 11),
+                dev: folder(1, 12),
+            })
+        );
+        assert_eq!(
+            latest_folders(MMSDROP, &SourceEngineVersion::Source2),
+            Some(LatestFolders {
+                stable: folder(2, 0),
+                dev: folder(2, 0),
+            })
+        );
+        assert_eq!(latest_folders(SMDROP, &SourceEngineVersion::Source2), None);
+        assert_eq!(latest_folders("", &SourceEngineVersion::Source1), None);
+    }
+
+    #[test]
+    fn archives_are_read_from_hrefs() {
+        let version = |build| Version {
+            major: 1,
+            minor: 12,
+            patch: 0,
+            build,
+        };
+
+        assert_eq!(
+            archives(SMDROP_FOLDER, "sourcemod", "linux"),
+            [
+                (
+                    version(7110),
+                    "sourcemod-1.12.0-git7110-linux.tar.gz".to_string()
+                ),
+                (
+                    version(7163),
+                    "sourcemod-1.12.0-git7163-linux.tar.gz".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            archives(SMDROP_FOLDER, "sourcemod", "windows"),
+            [(
+                version(7163),
+                "sourcemod-1.12.0-git7163-windows.zip".to_string()
+            )]
+        );
+        assert_eq!(archives(SMDROP_FOLDER, "mmsource", "linux"), []);
+    }
+
+    #[test]
+    fn latest_archive_names_are_trimmed() {
+        assert_eq!(
+            latest_archive_name("sourcemod-1.12.0-git7163-linux.tar.gz\n"),
+            Some("sourcemod-1.12.0-git7163-linux.tar.gz".to_string())
+        );
+        assert_eq!(latest_archive_name("  \n"), None);
+        assert_eq!(latest_archive_name("Not Found"), None);
+        assert_eq!(latest_archive_name("<html>\n<body>Not Found</body>"), None);
+    }
+}
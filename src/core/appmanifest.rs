@@ -0,0 +1,221 @@
+//! The `steamapps/appmanifest_<app>.acf` file SteamCMD keeps about an install, e.g.
+//!
+//! ```text
+//! "AppState"
+//! {
+//!     "appid"        "232250"
+//!     "buildid"      "14783012"
+//!     "InstalledDepots"
+//!     {
+//!         "232256"
+//!         {
+//!             "manifest"     "3459476342547125403"
+//!             "size"         "9836587216"
+//!         }
+//!     }
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AppManifest {
+    pub build_id: u32,
+    /// The manifest installed for every depot.
+    pub depots: Vec<(u32, u64)>,
+}
+
+pub fn path(install_path: &Path, app: u32) -> PathBuf {
+    install_path
+        .join("steamapps")
+        .join(format!("appmanifest_{app}.acf"))
+}
+
+/// The manifest of `app` installed in `install_path`, if SteamCMD installed it.
+pub async fn read(install_path: &Path, app: u32) -> Option<AppManifest> {
+    let contents = tokio::fs::read_to_string(path(install_path, app))
+        .await
+        .ok()?;
+
+    parse(&contents)
+}
+
+pub fn parse(contents: &str) -> Option<AppManifest> {
+    let tokens = tokenize(contents);
+
+    let [Token::String(_), Token::Open, body @ ..] = tokens.as_slice() else {
+        return None;
+    };
+
+    let mut manifest = AppManifest::default();
+    let mut build_id = None;
+    let mut tokens = body.iter();
+
+    loop {
+        let key = match tokens.next()? {
+            Token::String(key) => key,
+            Token::Close => break,
+            Token::Open => return None,
+        };
+
+        match tokens.next()? {
+            Token::String(value) if key.eq_ignore_ascii_case("buildid") => {
+                build_id = value.parse().ok();
+            }
+            Token::String(_) => {}
+            Token::Open if key.eq_ignore_ascii_case("InstalledDepots") => {
+                manifest.depots = installed_depots(&mut tokens)?;
+            }
+            Token::Open => skip_section(&mut tokens)?,
+            Token::Close => return None,
+        }
+    }
+
+    manifest.build_id = build_id?;
+
+    Some(manifest)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    String(&'a str),
+    Open,
+    Close,
+}
+
+fn tokenize(contents: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = contents;
+
+    while let Some(start) = rest.find(['"', '{', '}']) {
+        match rest.as_bytes()[start] {
+            b'{' => {
+                tokens.push(Token::Open);
+                rest = &rest[start + 1..];
+            }
+            b'}' => {
+                tokens.push(Token::Close);
+                rest = &rest[start + 1..];
+            }
+            _ => {
+                let string = &rest[start + 1..];
+
+                let Some(end) = string.find('"') else {
+                    break;
+                };
+
+                tokens.push(Token::String(&string[..end]));
+                rest = &string[end + 1..];
+            }
+        }
+    }
+
+    tokens
+}
+
+fn installed_depots<'a>(
+    tokens: &mut impl Iterator<Item = &'a Token<'a>>,
+) -> Option<Vec<(u32, u64)>> {
+    let mut depots = vec![];
+
+    loop {
+        let depot = match tokens.next()? {
+            Token::String(depot) => depot.parse().ok(),
+            Token::Close => return Some(depots),
+            Token::Open => return None,
+        };
+
+        let Token::Open = tokens.next()? else {
+            return None;
+        };
+
+        let mut manifest = None;
+
+        loop {
+            match tokens.next()? {
+                Token::String(key) => {
+                    let Token::String(value) = tokens.next()? else {
+                        return None;
+                    };
+
+                    if key.eq_ignore_ascii_case("manifest") {
+                        manifest = value.parse().ok();
+                    }
+                }
+                Token::Close => break,
+                Token::Open => skip_section(tokens)?,
+            }
+        }
+
+        if let Some(pair) = depot.zip(manifest) {
+            depots.push(pair);
+        }
+    }
+}
+
+/// Skips the rest of a section whose `{` was just read.
+fn skip_section<'a>(tokens: &mut impl Iterator<Item = &'a Token<'a>>) -> Option<()> {
+    let mut depth = 1;
+
+    while depth > 0 {
+        match tokens.next()? {
+            Token::Open => depth += 1,
+            Token::Close => depth -= 1,
+            Token::String(_) => {}
+        }
+    }
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+"AppState"
+{
+	"appid"		"232250"
+	"name"		"Team Fortress 2 Dedicated Server"
+	"buildid"		"14783012"
+	"UserConfig"
+	{
+		"BetaKey"		"prerelease"
+	}
+	"InstalledDepots"
+	{
+		"232256"
+		{
+			"manifest"		"3459476342547125403"
+			"size"		"9836587216"
+		}
+		"232253"
+		{
+			"manifest"		"1184346394298839912"
+			"size"		"431890516"
+		}
+	}
+	"MountedConfig"
+	{
+	}
+}
+"#;
+
+    #[test]
+    fn app_manifests_are_parsed() {
+        assert_eq!(
+            parse(MANIFEST),
+            Some(AppManifest {
+                build_id: 14783012,
+                depots: vec![(232256, 3459476342547125403), (232253, 1184346394298839912)],
+            })
+        );
+    }
+
+    #[test]
+    fn broken_app_manifests_are_rejected() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("\"AppState\"\n{\n\t\"appid\"\t\t\"232250\"\n}"), None);
+        assert_eq!(parse("\"AppState\"\n{\n\t\"buildid\"\t\t\"1\"\n"), None);
+    }
+}
@@ -0,0 +1,516 @@
+//! Downloading, verifying and extracting the `.tar.gz` and `.zip` archives the app installs:
+//! SourceMod and Metamod:Source releases, and the SteamCMD and DepotDownloader bootstraps.
+
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use super::{
+    ArchiveExtractionSnafu, DirectoryCreationSnafu, DownloadRequestSnafu, Error, ExtractError,
+    HttpStatusSnafu, MissingChecksumSnafu, TarSnafu, UnknownFormatSnafu, UnsafeEntrySnafu,
+    WriteSnafu, ZipSnafu, addons::InstallProgress,
+};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+/// The biggest archive that's downloaded. Releases are around 10 MB.
+pub const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The most an archive may unpack to, so a decompression bomb can't fill the disk.
+pub const MAX_EXTRACTED_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Whether an archive nobody published or pinned a hash of may be installed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Verification {
+    /// Archives without a hash are refused.
+    #[default]
+    Required,
+    /// Archives without a hash are installed unchecked, e.g. the AlliedMods builds that only
+    /// some platforms get a `.sha1` of.
+    Optional,
+}
+
+impl Verification {
+    pub const ALL: [Verification; 2] = [Verification::Required, Verification::Optional];
+
+    pub fn encode(&self) -> &'static str {
+        match self {
+            Verification::Required => "required",
+            Verification::Optional => "optional",
+        }
+    }
+}
+
+impl std::str::FromStr for Verification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "required" => Ok(Verification::Required),
+            "optional" => Ok(Verification::Optional),
+            verification => Err(format!("'{verification}' is not a valid checksum policy")),
+        }
+    }
+}
+
+impl std::fmt::Display for Verification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Verification::Required => "Required",
+            Verification::Optional => "Optional",
+        })
+    }
+}
+
+/// A hash an archive is checked against before it's extracted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Checksum {
+    Sha1(String),
+    Sha256(String),
+}
+
+impl Checksum {
+    /// Reads a published hash file, e.g. `sourcemod-1.12.0-git7110-linux.tar.gz.sha256`, whose
+    /// extension tells the algorithm. Its contents are the hex digest, optionally followed by
+    /// the file name like `sha256sum` writes it.
+    pub fn parse(hash_file_name: &str, contents: &str) -> Option<Self> {
+        let digest = contents.split_whitespace().next()?.to_ascii_lowercase();
+
+        if !digest.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        match hash_file_name.rsplit('.').next()? {
+            "sha1" if digest.len() == 40 => Some(Self::Sha1(digest)),
+            "sha256" if digest.len() == 64 => Some(Self::Sha256(digest)),
+            _ => None,
+        }
+    }
+
+    pub fn verify(&self, name: &str, contents: &[u8]) -> Result<(), Error> {
+        let (expected, actual) = match self {
+            Self::Sha1(expected) => (expected, hex(&Sha1::digest(contents))),
+            Self::Sha256(expected) => (expected, hex(&Sha256::digest(contents))),
+        };
+
+        if !expected.eq_ignore_ascii_case(&actual) {
+            return Err(Error::ChecksumMismatchError {
+                name: name.to_string(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends a GET request to `url`, failing on anything but a success status.
+pub async fn request(url: &str) -> Result<reqwest::Response, Error> {
+    let response = reqwest::get(url).await.context(DownloadRequestSnafu)?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        return HttpStatusSnafu {
+            url,
+            status: status.as_u16(),
+        }
+        .fail();
+    }
+
+    Ok(response)
+}
+
+pub async fn fetch_text(url: &str) -> Result<String, Error> {
+    request(url)
+        .await?
+        .text()
+        .await
+        .context(DownloadRequestSnafu)
+}
+
+/// Downloads the archive at `url` and checks it against `pinned_sha256`, or else against the
+/// `.sha256` or `.sha1` file published next to it. An archive there's no hash of is refused,
+/// unless `verification` is [`Verification::Optional`].
+pub async fn fetch(
+    url: &str,
+    pinned_sha256: Option<&str>,
+    verification: Verification,
+    progress: &impl Fn(InstallProgress),
+) -> Result<Vec<u8>, Error> {
+    let name = archive_name(url);
+
+    let contents = download(url, progress).await?;
+
+    progress(InstallProgress::Verifying);
+
+    let checksum = match pinned_sha256 {
+        Some(sha256) => Some(Checksum::Sha256(sha256.trim().to_ascii_lowercase())),
+        None => published_checksum(url).await,
+    };
+
+    if let Some(checksum) = required_checksum(name, checksum, verification)? {
+        checksum.verify(name, &contents)?;
+    }
+
+    Ok(contents)
+}
+
+/// The hash `name` is checked against, or `None` when it's allowed to go unchecked.
+fn required_checksum(
+    name: &str,
+    checksum: Option<Checksum>,
+    verification: Verification,
+) -> Result<Option<Checksum>, Error> {
+    match (checksum, verification) {
+        (Some(checksum), _) => Ok(Some(checksum)),
+        (None, Verification::Optional) => Ok(None),
+        (None, Verification::Required) => MissingChecksumSnafu { name }.fail(),
+    }
+}
+
+/// Downloads the archive at `url` without checking it, for the ones nobody publishes a hash
+/// of. Only meant for archives served over HTTPS by their own vendor.
+pub async fn fetch_unverified(
+    url: &str,
+    progress: &impl Fn(InstallProgress),
+) -> Result<Vec<u8>, Error> {
+    download(url, progress).await
+}
+
+fn archive_name(url: &str) -> &str {
+    url.rsplit('/').next().unwrap_or(url)
+}
+
+async fn download(url: &str, progress: &impl Fn(InstallProgress)) -> Result<Vec<u8>, Error> {
+    let name = archive_name(url);
+
+    let mut response = request(url).await?;
+
+    let too_large = |size| Error::ArchiveTooLargeError {
+        name: name.to_string(),
+        size,
+        limit: MAX_ARCHIVE_SIZE,
+    };
+
+    if let Some(size) = response.content_length()
+        && size > MAX_ARCHIVE_SIZE
+    {
+        return Err(too_large(size));
+    }
+
+    let total = response.content_length();
+    let mut contents = vec![];
+
+    progress(InstallProgress::Downloading {
+        downloaded: 0,
+        total,
+    });
+
+    // The advertised length can't be trusted, so the limit is checked as it comes in too.
+    while let Some(chunk) = response.chunk().await.context(DownloadRequestSnafu)? {
+        contents.extend_from_slice(&chunk);
+
+        if contents.len() as u64 > MAX_ARCHIVE_SIZE {
+            return Err(too_large(contents.len() as u64));
+        }
+
+        progress(InstallProgress::Downloading {
+            downloaded: contents.len() as u64,
+            total,
+        });
+    }
+
+    Ok(contents)
+}
+
+/// The first of the `.sha256` and `.sha1` files next to `url` that exists and holds a hash.
+async fn published_checksum(url: &str) -> Option<Checksum> {
+    for extension in ["sha256", "sha1"] {
+        let hash_url = format!("{url}.{extension}");
+
+        let Ok(contents) = fetch_text(&hash_url).await else {
+            continue;
+        };
+
+        if let Some(checksum) = Checksum::parse(&hash_url, &contents) {
+            return Some(checksum);
+        }
+    }
+
+    None
+}
+
+/// The hex SHA-256 of `contents`.
+pub fn sha256(contents: &[u8]) -> String {
+    hex(&Sha256::digest(contents))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Extracts `contents` into `destination`, leaving out the entries `skip` returns true for.
+/// `skip` is given the path of each entry, relative to `destination`.
+///
+/// Archives with absolute paths, `..` components or links pointing out of `destination` are
+/// refused as a whole, as are archives unpacking to more than [`MAX_EXTRACTED_SIZE`].
+pub fn extract(
+    contents: &[u8],
+    destination: &Path,
+    skip: impl Fn(&Path) -> bool,
+) -> Result<(), Error> {
+    fs::create_dir_all(destination).context(DirectoryCreationSnafu)?;
+
+    if contents.starts_with(GZIP_MAGIC) {
+        check_tar(contents).and_then(|()| extract_tar(contents, destination, skip))
+    } else if contents.starts_with(ZIP_MAGIC) {
+        extract_zip(contents, destination, skip)
+    } else {
+        UnknownFormatSnafu.fail()
+    }
+    .context(ArchiveExtractionSnafu)
+}
+
+/// The path of an entry, if it stays inside the folder the archive is extracted into.
+fn enclosed_path(path: &Path) -> Result<PathBuf, ExtractError> {
+    let is_enclosed = path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+    if !is_enclosed {
+        return UnsafeEntrySnafu {
+            path: path.to_string_lossy(),
+        }
+        .fail();
+    }
+
+    Ok(path.to_path_buf())
+}
+
+fn tar_archive(contents: &[u8]) -> tar::Archive<flate2::read::GzDecoder<&[u8]>> {
+    tar::Archive::new(flate2::read::GzDecoder::new(contents))
+}
+
+/// Goes through the whole archive before anything is written, so a refused archive doesn't
+/// leave a half extracted install behind.
+fn check_tar(contents: &[u8]) -> Result<(), ExtractError> {
+    let mut archive = tar_archive(contents);
+    let mut size = 0u64;
+
+    for entry in archive.entries().context(TarSnafu)? {
+        let entry = entry.context(TarSnafu)?;
+        let path = entry.path().context(TarSnafu)?;
+
+        enclosed_path(&path)?;
+
+        if let Some(target) = entry.link_name().context(TarSnafu)? {
+            // Hard links are relative to the archive's root, symbolic ones to their folder.
+            let parent = if entry.header().entry_type().is_hard_link() {
+                Path::new("")
+            } else {
+                path.parent().unwrap_or(Path::new(""))
+            };
+
+            check_link_target(parent, &target)?;
+        }
+
+        size += entry.size();
+
+        if size > MAX_EXTRACTED_SIZE {
+            return Err(ExtractError::ExtractedSizeError {
+                limit: MAX_EXTRACTED_SIZE,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Links may point at a sibling folder through `..`, but never above the archive's root.
+fn check_link_target(parent: &Path, target: &Path) -> Result<(), ExtractError> {
+    let mut depth = parent.components().count();
+
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => {
+                return UnsafeEntrySnafu {
+                    path: target.to_string_lossy(),
+                }
+                .fail();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_tar(
+    contents: &[u8],
+    destination: &Path,
+    skip: impl Fn(&Path) -> bool,
+) -> Result<(), ExtractError> {
+    let mut archive = tar_archive(contents);
+
+    for entry in archive.entries().context(TarSnafu)? {
+        let mut entry = entry.context(TarSnafu)?;
+
+        if skip(&entry.path().context(TarSnafu)?) {
+            continue;
+        }
+
+        entry.unpack_in(destination).context(TarSnafu)?;
+    }
+
+    Ok(())
+}
+
+fn extract_zip(
+    contents: &[u8],
+    destination: &Path,
+    skip: impl Fn(&Path) -> bool,
+) -> Result<(), ExtractError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(contents)).context(ZipSnafu)?;
+
+    let mut size = 0u64;
+
+    // Checked before anything is written, like tarballs are.
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).context(ZipSnafu)?;
+
+        enclosed_path(Path::new(entry.name()))?;
+
+        size += entry.size();
+
+        if size > MAX_EXTRACTED_SIZE {
+            return Err(ExtractError::ExtractedSizeError {
+                limit: MAX_EXTRACTED_SIZE,
+            });
+        }
+    }
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).context(ZipSnafu)?;
+
+        let path = enclosed_path(Path::new(entry.name()))?;
+
+        if skip(&path) {
+            continue;
+        }
+
+        let path = destination.join(path);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path).context(WriteSnafu)?;
+
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context(WriteSnafu)?;
+        }
+
+        let mut file = fs::File::create(&path).context(WriteSnafu)?;
+
+        // The sizes in the header could be lying.
+        io::copy(&mut (&mut entry).take(MAX_EXTRACTED_SIZE), &mut file).context(WriteSnafu)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::alliedmods;
+
+    #[test]
+    fn published_hashes_are_parsed() {
+        let sha256 = "a".repeat(64);
+
+        assert_eq!(
+            Checksum::parse(
+                "sourcemod-1.12.0-git7110-linux.tar.gz.sha256",
+                &format!("{sha256}  sourcemod-1.12.0-git7110-linux.tar.gz\n")
+            ),
+            Some(Checksum::Sha256(sha256))
+        );
+        assert_eq!(
+            Checksum::parse("mmsource.zip.sha1", &"B".repeat(40)),
+            Some(Checksum::Sha1("b".repeat(40)))
+        );
+        assert_eq!(Checksum::parse("a.sha1", "not a hash"), None);
+        assert_eq!(Checksum::parse("a.md5", &"a".repeat(32)), None);
+    }
+
+    #[test]
+    fn checksums_are_verified() {
+        let checksum = Checksum::Sha256(
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string(),
+        );
+
+        assert!(checksum.verify("hello", b"hello").is_ok());
+        assert!(matches!(
+            checksum.verify("hello", b"hello!"),
+            Err(Error::ChecksumMismatchError { .. })
+        ));
+    }
+
+    #[test]
+    fn entries_outside_of_the_destination_are_refused() {
+        assert!(enclosed_path(Path::new("addons/sourcemod/bin/sourcemod.logic.so")).is_ok());
+        assert!(enclosed_path(Path::new("./addons/metamod.vdf")).is_ok());
+        assert!(enclosed_path(Path::new("../../.bashrc")).is_err());
+        assert!(enclosed_path(Path::new("addons/../../escape")).is_err());
+        assert!(enclosed_path(Path::new("/etc/passwd")).is_err());
+
+        assert!(check_link_target(Path::new("addons/bin"), Path::new("../lib/a.so")).is_ok());
+        assert!(check_link_target(Path::new("addons"), Path::new("../../a.so")).is_err());
+        assert!(check_link_target(Path::new("addons"), Path::new("/usr/lib/a.so")).is_err());
+    }
+
+    #[test]
+    fn builds_without_a_published_hash_need_opting_in() {
+        let listing = include_str!("alliedmods/fixtures/smdrop-1.12.html");
+        let is_published = |name: &str| {
+            listing.contains(&format!("\"{name}.sha1\""))
+                || listing.contains(&format!("\"{name}.sha256\""))
+        };
+
+        let (_, latest) = alliedmods::archives(listing, "sourcemod", "linux")
+            .into_iter()
+            .max()
+            .unwrap();
+
+        assert_eq!(latest, "sourcemod-1.12.0-git7163-linux.tar.gz");
+        assert!(is_published("sourcemod-1.12.0-git7110-linux.tar.gz"));
+        assert!(!is_published(&latest));
+
+        assert!(matches!(
+            required_checksum(&latest, None, Verification::Required),
+            Err(Error::MissingChecksumError { .. })
+        ));
+        assert_eq!(
+            required_checksum(&latest, None, Verification::Optional).unwrap(),
+            None
+        );
+
+        let sha1 = Checksum::Sha1("a".repeat(40));
+
+        assert_eq!(
+            required_checksum(&latest, Some(sha1.clone()), Verification::Optional).unwrap(),
+            Some(sha1)
+        );
+    }
+}
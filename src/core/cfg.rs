@@ -0,0 +1,607 @@
+//! Reading and writing Source `.cfg` files, such as `server.cfg`.
+//!
+//! Only the commands that are changed get rewritten, so comments, blank lines and
+//! anything that isn't understood are written back untouched.
+
+use std::{io, path::Path};
+
+use snafu::ResultExt;
+
+use super::{Error, Game, IoSnafu};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    lines: Vec<Line>,
+    line_ending: LineEnding,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    Command(Command),
+    Other(String),
+}
+
+/// A `name value` line, remembering how it was written so it can be rebuilt as is.
+#[derive(Debug, Clone, PartialEq)]
+struct Command {
+    indent: String,
+    name: String,
+    separator: String,
+    value: String,
+    is_quoted: bool,
+    /// Whatever follows the value, usually a comment.
+    suffix: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Self {
+        let line_ending = if text.contains("\r\n") {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        };
+
+        let lines = text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .map(|line| match Command::parse(line) {
+                Some(command) => Line::Command(command),
+                None => Line::Other(line.to_string()),
+            })
+            .collect();
+
+        Self { lines, line_ending }
+    }
+
+    /// Reads the file at `path`. A missing file is an empty config.
+    pub async fn load(path: &Path) -> Result<Self, Error> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).context(IoSnafu),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.context(IoSnafu)?;
+        }
+
+        tokio::fs::write(path, self.to_string())
+            .await
+            .context(IoSnafu)
+    }
+
+    /// The value of `name`. When it's set more than once, the last one wins, like in game.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.commands()
+            .rev()
+            .find(|command| command.name.eq_ignore_ascii_case(name))
+            .map(|command| command.value.as_str())
+    }
+
+    /// Changes the last occurrence of `name`, or adds it at the end of the file.
+    pub fn set(&mut self, name: &str, value: &str) {
+        let existing = self.lines.iter_mut().rev().find_map(|line| match line {
+            Line::Command(command) if command.name.eq_ignore_ascii_case(name) => Some(command),
+            _ => None,
+        });
+
+        if let Some(command) = existing {
+            command.value = value.to_string();
+
+            // A bare `name` prints the value instead of clearing it.
+            if value.is_empty() {
+                command.is_quoted = true;
+            }
+
+            if command.separator.is_empty() {
+                command.separator = " ".to_string();
+            }
+
+            return;
+        }
+
+        let command = Line::Command(Command {
+            indent: String::new(),
+            name: name.to_string(),
+            separator: " ".to_string(),
+            value: value.to_string(),
+            is_quoted: true,
+            suffix: String::new(),
+        });
+
+        // Keep the final newline at the end of the file.
+        match self.lines.last() {
+            Some(Line::Other(last)) if last.is_empty() => {
+                self.lines.insert(self.lines.len() - 1, command)
+            }
+            Some(_) => self.lines.push(command),
+            None => self.lines.extend([command, Line::Other(String::new())]),
+        }
+    }
+
+    /// Removes every occurrence of `name`.
+    pub fn remove(&mut self, name: &str) {
+        self.lines.retain(|line| match line {
+            Line::Command(command) => !command.name.eq_ignore_ascii_case(name),
+            Line::Other(_) => true,
+        });
+    }
+
+    /// The names and values of every command, in file order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.commands()
+            .map(|command| (command.name.as_str(), command.value.as_str()))
+    }
+
+    fn commands(&self) -> impl DoubleEndedIterator<Item = &Command> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Command(command) => Some(command),
+            Line::Other(_) => None,
+        })
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line_ending = match self.line_ending {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        };
+
+        for (index, line) in self.lines.iter().enumerate() {
+            if index > 0 {
+                f.write_str(line_ending)?;
+            }
+
+            match line {
+                Line::Command(command) => write!(f, "{command}")?,
+                Line::Other(text) => f.write_str(text)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Command {
+    /// Only simple `name value` lines are parsed. Anything else, like several commands
+    /// separated by `;`, is left alone.
+    fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+
+        if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.contains(';') {
+            return None;
+        }
+
+        let name_end = trimmed
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .unwrap_or(trimmed.len());
+        let (name, rest) = trimmed.split_at(name_end);
+
+        let value_start = rest.len() - rest.trim_start().len();
+        let (separator, rest) = rest.split_at(value_start);
+
+        let (value, is_quoted, suffix) = if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+
+            (&quoted[..end], true, &quoted[end + 1..])
+        } else {
+            let end = rest
+                .find(|c: char| c.is_whitespace())
+                .unwrap_or(rest.len())
+                .min(rest.find("//").unwrap_or(rest.len()));
+
+            (&rest[..end], false, &rest[end..])
+        };
+
+        let trailing = suffix.trim();
+
+        if name.is_empty() || !(trailing.is_empty() || trailing.starts_with("//")) {
+            return None;
+        }
+
+        Some(Self {
+            indent: indent.to_string(),
+            name: name.to_string(),
+            separator: separator.to_string(),
+            value: value.to_string(),
+            is_quoted,
+            suffix: suffix.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for Command {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let needs_quotes = self.is_quoted
+            || self.value.contains(|c: char| c.is_whitespace())
+            || self.value.contains("//");
+
+        write!(f, "{}{}{}", self.indent, self.name, self.separator)?;
+
+        if needs_quotes {
+            write!(f, "\"{}\"", self.value)?;
+        } else {
+            f.write_str(&self.value)?;
+        }
+
+        f.write_str(&self.suffix)
+    }
+}
+
+/// What a convar accepts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvarKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    Float { min: f32, max: f32 },
+    Text,
+}
+
+impl ConvarKind {
+    pub fn is_valid(&self, value: &str) -> bool {
+        match self {
+            ConvarKind::Bool => matches!(value, "0" | "1"),
+            ConvarKind::Integer { min, max } => value
+                .parse::<i64>()
+                .is_ok_and(|value| (*min..=*max).contains(&value)),
+            ConvarKind::Float { min, max } => value
+                .parse::<f32>()
+                .is_ok_and(|value| (*min..=*max).contains(&value)),
+            ConvarKind::Text => true,
+        }
+    }
+}
+
+/// A convar the config editor knows about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Convar {
+    pub name: &'static str,
+    pub kind: ConvarKind,
+    pub description: &'static str,
+}
+
+const fn convar(name: &'static str, kind: ConvarKind, description: &'static str) -> Convar {
+    Convar {
+        name,
+        kind,
+        description,
+    }
+}
+
+const fn int(min: i64, max: i64) -> ConvarKind {
+    ConvarKind::Integer { min, max }
+}
+
+const fn float(min: f32, max: f32) -> ConvarKind {
+    ConvarKind::Float { min, max }
+}
+
+const COMMON_CONVARS: &[Convar] = &[
+    convar(
+        "sv_contact",
+        ConvarKind::Text,
+        "Contact email for the server operator",
+    ),
+    convar(
+        "sv_lan",
+        ConvarKind::Bool,
+        "Only accept players from the local network",
+    ),
+    convar("sv_cheats", ConvarKind::Bool, "Allow cheat commands"),
+    convar(
+        "sv_pure",
+        int(-1, 2),
+        "How strictly client files must match the server's",
+    ),
+    convar(
+        "sv_alltalk",
+        ConvarKind::Bool,
+        "Players can hear the other team's voice chat",
+    ),
+    convar(
+        "sv_allow_upload",
+        ConvarKind::Bool,
+        "Allow clients to upload sprays",
+    ),
+    convar(
+        "sv_allow_download",
+        ConvarKind::Bool,
+        "Allow clients to download files",
+    ),
+    convar(
+        "sv_downloadurl",
+        ConvarKind::Text,
+        "Fast download URL for custom content",
+    ),
+    convar(
+        "sv_minrate",
+        int(0, 1048576),
+        "Minimum bandwidth rate per client, in bytes/s",
+    ),
+    convar(
+        "sv_maxrate",
+        int(0, 1048576),
+        "Maximum bandwidth rate per client, in bytes/s",
+    ),
+    convar(
+        "sv_minupdaterate",
+        int(10, 128),
+        "Minimum updates per second sent to clients",
+    ),
+    convar(
+        "sv_maxupdaterate",
+        int(10, 128),
+        "Maximum updates per second sent to clients",
+    ),
+    convar(
+        "sv_timeout",
+        int(10, 300),
+        "Seconds before a silent client is dropped",
+    ),
+    convar(
+        "mp_timelimit",
+        int(0, 1440),
+        "Minutes per map, 0 for no limit",
+    ),
+    convar(
+        "mp_friendlyfire",
+        ConvarKind::Bool,
+        "Players can hurt their teammates",
+    ),
+    convar(
+        "sv_rcon_banpenalty",
+        int(0, 1440),
+        "Minutes to ban after failed RCON attempts",
+    ),
+    convar(
+        "sv_rcon_maxfailures",
+        int(1, 20),
+        "Failed RCON attempts before a ban",
+    ),
+    convar("log", ConvarKind::Text, "Server logging, on or off"),
+];
+
+const TEAM_FORTRESS_2_CONVARS: &[Convar] = &[
+    convar(
+        "mp_winlimit",
+        int(0, 100),
+        "Rounds to win before the map changes",
+    ),
+    convar("mp_maxrounds", int(0, 100), "Rounds before the map changes"),
+    convar(
+        "mp_autoteambalance",
+        int(0, 2),
+        "Automatically balance the teams",
+    ),
+    convar(
+        "mp_teams_unbalance_limit",
+        int(0, 32),
+        "Allowed difference in team sizes",
+    ),
+    convar(
+        "mp_disable_respawn_times",
+        ConvarKind::Bool,
+        "Respawn instantly",
+    ),
+    convar(
+        "tf_weapon_criticals",
+        ConvarKind::Bool,
+        "Random critical hits",
+    ),
+    convar(
+        "tf_use_fixed_weaponspreads",
+        ConvarKind::Bool,
+        "Fixed shotgun spread",
+    ),
+    convar(
+        "tf_bot_quota",
+        int(0, 100),
+        "Number of bots to keep on the server",
+    ),
+    convar(
+        "tf_allow_server_hibernation",
+        ConvarKind::Bool,
+        "Sleep while the server is empty",
+    ),
+];
+
+const COUNTER_STRIKE_CONVARS: &[Convar] = &[
+    convar("mp_maxrounds", int(0, 100), "Rounds before the map changes"),
+    convar("mp_roundtime", float(1.0, 60.0), "Minutes per round"),
+    convar(
+        "mp_freezetime",
+        int(0, 60),
+        "Seconds players are frozen at round start",
+    ),
+    convar(
+        "mp_buytime",
+        int(0, 600),
+        "Seconds players can buy after round start",
+    ),
+    convar("mp_startmoney", int(0, 65535), "Money players start with"),
+    convar(
+        "mp_c4timer",
+        int(10, 90),
+        "Seconds before the bomb explodes",
+    ),
+    convar(
+        "mp_autoteambalance",
+        ConvarKind::Bool,
+        "Automatically balance the teams",
+    ),
+    convar(
+        "mp_limitteams",
+        int(0, 30),
+        "Allowed difference in team sizes",
+    ),
+    convar(
+        "bot_quota",
+        int(0, 64),
+        "Number of bots to keep on the server",
+    ),
+];
+
+const LEFT_4_DEAD_CONVARS: &[Convar] = &[
+    convar(
+        "z_difficulty",
+        ConvarKind::Text,
+        "Easy, Normal, Hard or Impossible",
+    ),
+    convar(
+        "sv_allow_lobby_connect_only",
+        ConvarKind::Bool,
+        "Only accept players from lobbies",
+    ),
+    convar(
+        "sv_gametypes",
+        ConvarKind::Text,
+        "Game modes the server accepts",
+    ),
+    convar(
+        "sv_steamgroup",
+        ConvarKind::Text,
+        "Steam group ID the server belongs to",
+    ),
+    convar(
+        "sv_steamgroup_exclusive",
+        ConvarKind::Bool,
+        "Only members of the group can join",
+    ),
+];
+
+const DEATHMATCH_CONVARS: &[Convar] = &[
+    convar(
+        "mp_fraglimit",
+        int(0, 1000),
+        "Frags to win before the map changes",
+    ),
+    convar("mp_teamplay", ConvarKind::Bool, "Team deathmatch"),
+    convar(
+        "mp_forcerespawn",
+        ConvarKind::Bool,
+        "Respawn dead players automatically",
+    ),
+    convar(
+        "mp_weaponstay",
+        ConvarKind::Bool,
+        "Weapons stay after being picked up",
+    ),
+];
+
+const DAY_OF_DEFEAT_CONVARS: &[Convar] = &[
+    convar(
+        "mp_winlimit",
+        int(0, 100),
+        "Rounds to win before the map changes",
+    ),
+    convar(
+        "mp_limitteams",
+        int(0, 30),
+        "Allowed difference in team sizes",
+    ),
+    convar(
+        "mp_autoteambalance",
+        ConvarKind::Bool,
+        "Automatically balance the teams",
+    ),
+];
+
+/// The convars the config editor offers for `game`.
+pub fn convars(game: Game) -> Vec<Convar> {
+    let game_convars = match game {
+        Game::TeamFortress2 | Game::TeamFortress2Classified => TEAM_FORTRESS_2_CONVARS,
+        Game::CounterStrikeSource | Game::CounterStrikeGlobalOffensive | Game::CounterStrike2 => {
+            COUNTER_STRIKE_CONVARS
+        }
+        Game::LeftForDead1 | Game::LeftForDead2 => LEFT_4_DEAD_CONVARS,
+        Game::HalfLife2DM => DEATHMATCH_CONVARS,
+        Game::DayOfDefeatSource => DAY_OF_DEFEAT_CONVARS,
+        Game::NoMoreRoomInHell | Game::Deadlock => &[],
+    };
+
+    COMMON_CONVARS.iter().chain(game_convars).copied().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER_CFG: &str = "// General\r\n\
+        hostname \"My Server\" // shown in the browser\r\n\
+        sv_cheats 0\r\n\
+        \r\n\
+        exec banned_user.cfg; exec banned_ip.cfg\r\n\
+        \tmp_timelimit\t30\r\n";
+
+    #[test]
+    fn unchanged_files_are_written_back_as_is() {
+        assert_eq!(Config::parse(SERVER_CFG).to_string(), SERVER_CFG);
+    }
+
+    #[test]
+    fn values_are_read() {
+        let config = Config::parse(SERVER_CFG);
+
+        assert_eq!(config.get("hostname"), Some("My Server"));
+        assert_eq!(config.get("SV_CHEATS"), Some("0"));
+        assert_eq!(config.get("mp_timelimit"), Some("30"));
+        assert_eq!(config.get("exec"), None);
+    }
+
+    #[test]
+    fn setting_keeps_comments_and_formatting() {
+        let mut config = Config::parse(SERVER_CFG);
+
+        config.set("hostname", "Other Server");
+        config.set("mp_timelimit", "45");
+        config.set("sv_pure", "1");
+
+        assert_eq!(
+            config.to_string(),
+            "// General\r\n\
+            hostname \"Other Server\" // shown in the browser\r\n\
+            sv_cheats 0\r\n\
+            \r\n\
+            exec banned_user.cfg; exec banned_ip.cfg\r\n\
+            \tmp_timelimit\t45\r\n\
+            sv_pure \"1\"\r\n"
+        );
+    }
+
+    #[test]
+    fn removing_drops_every_occurrence() {
+        let mut config = Config::parse("sv_cheats 1\nsv_cheats 0\nsv_lan 0\n");
+
+        config.remove("sv_cheats");
+
+        assert_eq!(config.to_string(), "sv_lan 0\n");
+    }
+
+    #[test]
+    fn the_last_occurrence_wins() {
+        let config = Config::parse("sv_cheats 1\nsv_cheats 0");
+
+        assert_eq!(config.get("sv_cheats"), Some("0"));
+    }
+
+    #[test]
+    fn values_are_validated() {
+        assert!(ConvarKind::Bool.is_valid("1"));
+        assert!(!ConvarKind::Bool.is_valid("yes"));
+        assert!(int(-1, 2).is_valid("-1"));
+        assert!(!int(-1, 2).is_valid("3"));
+        assert!(float(1.0, 60.0).is_valid("2.5"));
+        assert!(!float(1.0, 60.0).is_valid("abc"));
+    }
+}
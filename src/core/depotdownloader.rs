@@ -1,58 +1,124 @@
-use reqwest::{self};
-use snafu::ResultExt;
+use serde_json::Value;
+use snafu::{OptionExt, ResultExt};
 use std::{
+    collections::HashSet,
     fs,
     io::Cursor,
     path::{Path, PathBuf},
     process::Stdio,
+    time::SystemTime,
+};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::{Child, ChildStdout, Command},
 };
-use tokio::process::{Child, ChildStdout, Command};
 
 use super::{
-    ArchiveExtractionSnafu, DirectoryCreationSnafu, DownloadRequestSnafu, Error, SpawnProcessSnafu,
-    ZipSnafu,
+    ArchiveExtractionSnafu, DirectoryCreationSnafu, DownloadRequestSnafu, Error, HttpStatusSnafu,
+    IoSnafu, ListingParseSnafu, MissingChecksumSnafu, SpawnProcessSnafu, ZipSnafu,
+    archive::{self, Verification},
+    installer::{DownloadPhase, FileProblem, InstallEvent, InstallOptions, Installer},
 };
 
 pub struct DepotDownloader {
     pub depotdownloader_path: PathBuf,
+    pub options: InstallOptions,
+    /// Checks every installed file, downloading again the ones that differ.
+    pub validate: bool,
+    /// Killed when dropped, so cancelling the download stops it.
     process: Option<Child>,
 }
 
+/// The release downloaded when there's no DepotDownloader yet, so every machine runs the
+/// same one. Newer ones are installed with [`download`].
+pub const PINNED_VERSION: &str = "3.4.0";
+
+const RELEASES_URL: &str = "https://api.github.com/repos/SteamRE/DepotDownloader/releases";
+
+/// A DepotDownloader executable to use instead of downloading one, e.g. on machines without
+/// access to GitHub.
+pub const EXECUTABLE_VARIABLE: &str = "MANNAGER_DEPOTDOWNLOADER";
+
 #[cfg(target_os = "linux")]
-const DEPOTDOWNLOADER_URL: &str = "https://github.com/SteamRE/DepotDownloader/releases/latest/download/DepotDownloader-linux-x64.zip";
+const ASSET_NAME: &str = "DepotDownloader-linux-x64.zip";
 
 #[cfg(target_os = "windows")]
-const DEPOTDOWNLOADER_URL: &str = "https://github.com/SteamRE/DepotDownloader/releases/latest/download/DepotDownloader-windows-x64.zip";
+const ASSET_NAME: &str = "DepotDownloader-windows-x64.zip";
+
+#[cfg(target_os = "linux")]
+const EXECUTABLE: &str = "DepotDownloader";
+
+#[cfg(target_os = "windows")]
+const EXECUTABLE: &str = "DepotDownloader.exe";
+
+/// Where the version of a downloaded DepotDownloader is kept, next to it.
+const VERSION_FILE: &str = "version";
+
+/// Where the SHA-256 of a downloaded DepotDownloader's executable is kept, to notice it
+/// changing afterwards.
+const HASH_FILE: &str = "sha256";
 
 impl DepotDownloader {
+    /// Uses the DepotDownloader in `path`, downloading the pinned version first if it isn't
+    /// there, is older than it, or changed since it was downloaded.
     pub async fn new(path: impl AsRef<Path>) -> Result<Self, Error> {
-        let path = path.as_ref().to_path_buf();
+        let path = path.as_ref();
+
+        if !is_intact(path).await {
+            download(path, Some(PINNED_VERSION), None).await?;
+        }
+
+        Ok(Self::with_executable(path.join(EXECUTABLE)))
+    }
 
-        let executable_path = path.join("DepotDownloader");
+    /// The DepotDownloader set through [`EXECUTABLE_VARIABLE`], or else the one in MANNager's
+    /// data folder.
+    pub async fn locate() -> Result<Self, Error> {
+        let Some(executable) = local_executable() else {
+            return Self::new(Self::default_path()?).await;
+        };
 
-        if !executable_path.try_exists().unwrap_or(false) {
-            download_file(&path).await?;
+        if !executable.is_file() {
+            return Err(Error::ExecutableNotFoundError {
+                path: executable.display().to_string(),
+            });
         }
 
-        Ok(Self {
-            depotdownloader_path: executable_path,
+        Ok(Self::with_executable(executable))
+    }
+
+    /// The DepotDownloader in MANNager's data folder.
+    pub fn default_path() -> Result<PathBuf, Error> {
+        super::data_dir()
+            .map(|data_dir| data_dir.join("depotdownloader"))
+            .ok_or(Error::NoDataDirectoryError)
+    }
+
+    fn with_executable(executable: PathBuf) -> Self {
+        Self {
+            depotdownloader_path: executable,
+            options: InstallOptions::default(),
+            validate: false,
             process: None,
-        })
+        }
     }
 
     pub async fn download_app(
         &mut self,
-        path: &str,
+        path: &Path,
         appid: u32,
     ) -> Result<Option<ChildStdout>, Error> {
         let mut process = Command::new(&self.depotdownloader_path);
 
         process
             .args(["-app", &appid.to_string()])
-            .args(["-dir", path])
+            .arg("-dir")
+            .arg(path)
+            .args(self.args())
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
+            // Nothing reads it, a full pipe would stall the download.
+            .stderr(Stdio::null())
             .kill_on_drop(true);
 
         #[cfg(target_os = "windows")]
@@ -66,33 +132,632 @@ impl DepotDownloader {
 
         Ok(stdout)
     }
+
+    /// The arguments for the branch, the pinned manifests and validation.
+    fn args(&self) -> Vec<String> {
+        let options = &self.options;
+        let mut args = vec![];
+
+        if let Some(beta) = &options.beta {
+            args.extend(["-beta".to_string(), beta.clone()]);
+
+            if let Some(password) = &options.beta_password {
+                args.extend(["-betapassword".to_string(), password.clone()]);
+            }
+        }
+
+        if !options.manifests.is_empty() {
+            args.push("-depot".to_string());
+            args.extend(options.manifests.iter().map(|pin| pin.depot.to_string()));
+            args.push("-manifest".to_string());
+            args.extend(options.manifests.iter().map(|pin| pin.manifest.to_string()));
+        }
+
+        if self.validate {
+            args.push("-validate".to_string());
+        }
+
+        args
+    }
+}
+
+impl Installer for DepotDownloader {
+    async fn install(
+        &mut self,
+        app: u32,
+        path: &Path,
+        events: &impl Fn(InstallEvent),
+    ) -> Result<(), Error> {
+        let started = SystemTime::now();
+        let stdout = self.download_app(path, app).await?;
+
+        let mut output = Output {
+            validating_since: self.validate.then_some(started),
+            ..Output::default()
+        };
+
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).lines();
+
+            while let Some(line) = lines.next_line().await.context(IoSnafu)? {
+                output.read(&line).into_iter().for_each(events);
+            }
+        }
+
+        let Some(process) = self.process.as_mut() else {
+            return Ok(());
+        };
+
+        let status = process.wait().await.context(IoSnafu)?;
+
+        if !status.success() {
+            return Err(Error::InstallFailedError {
+                message: format!("DepotDownloader exited with {status}"),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the events out of DepotDownloader's output, line by line.
+#[derive(Debug, Default)]
+struct Output {
+    phase: DownloadPhase,
+    /// The depot the progress lines are about.
+    current_depot: Option<u32>,
+    /// When validating, the time the install started. Files written are repairs then, rather
+    /// than a fresh install or an update.
+    validating_since: Option<SystemTime>,
+    /// The files checked against their checksums, that haven't been reported as done yet.
+    validated: HashSet<String>,
 }
 
-async fn download_file(path: &PathBuf) -> Result<(), Error> {
-    fs::create_dir_all(path).context(DirectoryCreationSnafu)?;
+impl Output {
+    fn read(&mut self, line: &str) -> Vec<InstallEvent> {
+        let trimmed = line.trim();
+        let mut events = vec![];
 
-    let steamcmd_contents = reqwest::get(DEPOTDOWNLOADER_URL)
+        let phase = self.phase.clone().advance(trimmed);
+
+        if phase != self.phase {
+            self.phase = phase.clone();
+            events.push(InstallEvent::Phase(phase));
+        }
+
+        if let Some(id) = trimmed
+            .strip_prefix("Processing depot ")
+            .and_then(|rest| rest.trim().parse().ok())
+        {
+            self.current_depot = Some(id);
+            events.push(InstallEvent::Depot { id });
+        } else if let Some(id) = trimmed
+            .strip_prefix("Downloading depot ")
+            .and_then(|rest| rest.split_whitespace().next())
+            .and_then(|id| id.parse().ok())
+        {
+            self.current_depot = Some(id);
+        } else if let Some(id) = trimmed
+            .strip_prefix("Depot ")
+            .and_then(|rest| rest.split_once(" - Downloaded "))
+            .and_then(|(id, _)| id.trim().parse().ok())
+        {
+            events.push(InstallEvent::DepotProgress {
+                id,
+                progress: 100.0,
+            });
+        } else if let Some(path) = trimmed
+            .strip_prefix("Validating ")
+            .filter(|_| self.validating_since.is_some())
+        {
+            self.validated.insert(path.to_string());
+        } else if let Some(path) = trimmed
+            .strip_prefix("Pre-allocating ")
+            .filter(|_| self.validating_since.is_some())
+        {
+            events.push(InstallEvent::Repair {
+                path: path.to_string(),
+                problem: FileProblem::Missing,
+            });
+        } else if let Some(progress) = percentage(trimmed)
+            && let Some(id) = self.current_depot
+        {
+            events.push(InstallEvent::DepotProgress { id, progress });
+
+            // Every validated file is reported, once it matched or once all of its chunks were
+            // written. Only the ones written to were repaired.
+            if let Some((_, path)) = trimmed.split_once("% ")
+                && self.validated.remove(path.trim())
+                && self
+                    .validating_since
+                    .is_some_and(|started| written_since(Path::new(path.trim()), started))
+            {
+                events.push(InstallEvent::Repair {
+                    path: path.trim().to_string(),
+                    problem: FileProblem::Mismatched,
+                });
+            }
+        }
+
+        // Every file gets one of these, they'd bury the rest of the log.
+        if !trimmed.starts_with("Pre-allocating") && !trimmed.starts_with("Validating ") {
+            events.push(InstallEvent::Log(line.to_string()));
+        }
+
+        events
+    }
+}
+
+impl DownloadPhase {
+    fn advance(self, trimmed: &str) -> Self {
+        let next = if trimmed.starts_with("Connecting to Steam")
+            || trimmed.starts_with("Logging")
+            || trimmed.starts_with("Got AppInfo")
+            || trimmed.starts_with("Got depot key")
+        {
+            DownloadPhase::Connecting
+        } else if trimmed.starts_with("Processing depot")
+            || trimmed.starts_with("Downloading depot")
+            || trimmed.starts_with("Got manifest")
+            || trimmed.starts_with("Manifest ")
+            || trimmed.starts_with("Pre-allocating")
+        {
+            DownloadPhase::ResolvingDepots
+        } else if trimmed.starts_with("Validating ") {
+            DownloadPhase::Verifying
+        } else if percentage(trimmed).is_some()
+            || (trimmed.starts_with("Depot ") && trimmed.contains("Downloaded"))
+        {
+            DownloadPhase::Downloading
+        } else if trimmed.starts_with("Total downloaded") {
+            DownloadPhase::Validating
+        } else if trimmed == "Disconnected from Steam" {
+            DownloadPhase::Done
+        } else {
+            return self;
+        };
+
+        next.max(self)
+    }
+}
+
+/// Whether the file at `path` was modified at or after `time`.
+fn written_since(path: &Path, time: SystemTime) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|modified| modified >= time)
+}
+
+/// The percentage a progress line starts with, e.g. ` 42.17% depots/232256/...`.
+fn percentage(trimmed: &str) -> Option<f32> {
+    let (percentage, _) = trimmed.split_once('%')?;
+
+    percentage.trim().parse().ok()
+}
+
+/// The executable set through [`EXECUTABLE_VARIABLE`], if any.
+pub fn local_executable() -> Option<PathBuf> {
+    std::env::var_os(EXECUTABLE_VARIABLE)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The version of the DepotDownloader downloaded into `path`, if it's known.
+pub async fn installed_version(path: &Path) -> Option<String> {
+    let version = tokio::fs::read_to_string(path.join(VERSION_FILE))
         .await
-        .context(DownloadRequestSnafu)?
-        .bytes()
+        .ok()?;
+
+    Some(version.trim().to_string()).filter(|version| !version.is_empty())
+}
+
+/// Whether the DepotDownloader in `path` is at least [`PINNED_VERSION`], and its executable is
+/// the one that was downloaded.
+async fn is_intact(path: &Path) -> bool {
+    let Some(version) = installed_version(path).await else {
+        return false;
+    };
+
+    if is_older(&version, PINNED_VERSION) {
+        return false;
+    }
+
+    let Ok(expected) = tokio::fs::read_to_string(path.join(HASH_FILE)).await else {
+        return false;
+    };
+
+    let executable = path.join(EXECUTABLE);
+
+    tokio::task::spawn_blocking(move || fs::read(executable))
+        .await
+        .unwrap()
+        .is_ok_and(|contents| archive::sha256(&contents) == expected.trim())
+}
+
+/// Whether the dotted `version` comes before `other`. Versions that don't parse are older.
+fn is_older(version: &str, other: &str) -> bool {
+    let parse = |version: &str| {
+        version
+            .split('.')
+            .map(str::parse)
+            .collect::<Result<Vec<u32>, _>>()
+            .ok()
+    };
+
+    match (parse(version), parse(other)) {
+        (Some(version), Some(other)) => version < other,
+        _ => true,
+    }
+}
+
+/// Downloads `version` of DepotDownloader into `path`, or the latest one when `None`,
+/// replacing the one there. Returns the version downloaded.
+///
+/// The archive is checked against `sha256`, or else against the digest GitHub lists for it.
+pub async fn download(
+    path: &Path,
+    version: Option<&str>,
+    sha256: Option<&str>,
+) -> Result<String, Error> {
+    let url = match version {
+        Some(version) => format!("{RELEASES_URL}/tags/DepotDownloader_{version}"),
+        None => format!("{RELEASES_URL}/latest"),
+    };
+
+    let contents = github_request(&url)
+        .await?
+        .text()
         .await
         .context(DownloadRequestSnafu)?;
 
-    let cursor = Cursor::new(steamcmd_contents);
+    let release = parse_release(&contents, ASSET_NAME).context(ListingParseSnafu { url })?;
+
+    let sha256 = sha256
+        .map(str::to_string)
+        .or(release.sha256)
+        .context(MissingChecksumSnafu { name: ASSET_NAME })?;
+
+    let contents =
+        archive::fetch(&release.url, Some(&sha256), Verification::Required, &|_| {}).await?;
 
-    let mut zip = zip::ZipArchive::new(cursor)
-        .context(ZipSnafu)
-        .context(ArchiveExtractionSnafu)?;
+    let path = path.to_path_buf();
+    let version = release.version.clone();
 
-    zip.extract(path)
-        .context(ZipSnafu)
-        .context(ArchiveExtractionSnafu)?;
+    tokio::task::spawn_blocking(move || {
+        fs::create_dir_all(&path).context(DirectoryCreationSnafu)?;
 
-    Ok(())
+        // Unlike `archive::extract`, this keeps the executable bit of the binary.
+        zip::ZipArchive::new(Cursor::new(contents))
+            .and_then(|mut zip| zip.extract(&path))
+            .context(ZipSnafu)
+            .context(ArchiveExtractionSnafu)?;
+
+        let executable = fs::read(path.join(EXECUTABLE)).context(IoSnafu)?;
+
+        fs::write(path.join(HASH_FILE), archive::sha256(&executable)).context(IoSnafu)?;
+        fs::write(path.join(VERSION_FILE), version).context(IoSnafu)
+    })
+    .await
+    .unwrap()?;
+
+    Ok(release.version)
 }
 
-impl Drop for DepotDownloader {
-    fn drop(&mut self) {
-        let _ = self.process.as_mut().unwrap().start_kill();
+/// The GitHub API refuses requests without a user agent.
+async fn github_request(url: &str) -> Result<reqwest::Response, Error> {
+    let response = reqwest::Client::new()
+        .get(url)
+        .header(reqwest::header::USER_AGENT, "MANNager")
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .context(DownloadRequestSnafu)?;
+
+    let status = response.status();
+
+    if !status.is_success() {
+        return HttpStatusSnafu {
+            url,
+            status: status.as_u16(),
+        }
+        .fail();
+    }
+
+    Ok(response)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    pub url: String,
+    /// The SHA-256 of the archive, for releases GitHub computed it for.
+    pub sha256: Option<String>,
+}
+
+/// Reads the archive named `asset_name` out of a GitHub release, as the API returns it.
+pub fn parse_release(contents: &str, asset_name: &str) -> Option<Release> {
+    let release: Value = serde_json::from_str(contents).ok()?;
+
+    let tag = release.get("tag_name")?.as_str()?;
+
+    let asset = release
+        .get("assets")?
+        .as_array()?
+        .iter()
+        .find(|asset| asset.get("name").and_then(Value::as_str) == Some(asset_name))?;
+
+    Some(Release {
+        version: tag
+            .strip_prefix("DepotDownloader_")
+            .unwrap_or(tag)
+            .to_string(),
+        url: asset.get("browser_download_url")?.as_str()?.to_string(),
+        sha256: asset
+            .get("digest")
+            .and_then(Value::as_str)
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_is_read_into_events() {
+        let mut output = Output::default();
+
+        let mut read = |line: &str| {
+            output
+                .read(line)
+                .into_iter()
+                .filter(|event| !matches!(event, InstallEvent::Log(_)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(read("Connecting to Steam3...Done!"), []);
+        assert_eq!(
+            read("Processing depot 232256"),
+            [
+                InstallEvent::Phase(DownloadPhase::ResolvingDepots),
+                InstallEvent::Depot { id: 232256 },
+            ]
+        );
+        assert_eq!(
+            read(" 42.50% tf/tf2_misc_dir.vpk"),
+            [
+                InstallEvent::Phase(DownloadPhase::Downloading),
+                InstallEvent::DepotProgress {
+                    id: 232256,
+                    progress: 42.5
+                },
+            ]
+        );
+        assert_eq!(
+            read("Depot 232256 - Downloaded 1024 bytes (2048 bytes uncompressed)"),
+            [InstallEvent::DepotProgress {
+                id: 232256,
+                progress: 100.0
+            }]
+        );
+        assert_eq!(
+            read("Total downloaded: 1024 bytes (2048 bytes uncompressed) from 1 depots"),
+            [InstallEvent::Phase(DownloadPhase::Validating)]
+        );
+        assert_eq!(
+            read("Disconnected from Steam"),
+            [InstallEvent::Phase(DownloadPhase::Done)]
+        );
+    }
+
+    #[test]
+    fn pinned_manifests_are_passed_in_order() {
+        let depot_downloader = DepotDownloader {
+            depotdownloader_path: PathBuf::from("DepotDownloader"),
+            options: InstallOptions {
+                beta: Some("prerelease".to_string()),
+                beta_password: Some("hunter2".to_string()),
+                manifests: vec![
+                    "232256:3459476342547125403".parse().unwrap(),
+                    "232253:1184346394298839912".parse().unwrap(),
+                ],
+                ..Default::default()
+            },
+            validate: false,
+            process: None,
+        };
+
+        assert_eq!(
+            depot_downloader.args(),
+            [
+                "-beta",
+                "prerelease",
+                "-betapassword",
+                "hunter2",
+                "-depot",
+                "232256",
+                "232253",
+                "-manifest",
+                "3459476342547125403",
+                "1184346394298839912",
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_reports_the_repaired_files() {
+        let dir = std::env::temp_dir().join(format!("mannager-validate-{}", std::process::id()));
+        let started = SystemTime::now();
+
+        // Left alone by the validation, so older than the install.
+        let unchanged = dir.join("tf/tf2_misc_dir.vpk");
+        // Written again, chunk by chunk.
+        let repaired = dir.join("tf/bin/server.so");
+        let missing = dir.join("tf/maps/ctf_2fort.bsp");
+
+        let write = |path: &Path, modified: SystemTime| {
+            fs::write(path, "").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(modified))
+                .unwrap();
+        };
+
+        let hour = std::time::Duration::from_secs(3600);
+
+        fs::create_dir_all(dir.join("tf/bin")).unwrap();
+        write(&unchanged, started - hour);
+        write(&repaired, started + hour);
+
+        let mut output = Output {
+            validating_since: Some(started),
+            ..Output::default()
+        };
+
+        let (unchanged, repaired, missing) = (
+            unchanged.display().to_string(),
+            repaired.display().to_string(),
+            missing.display().to_string(),
+        );
+
+        let repairs = [
+            "Connecting to Steam3... Done!".to_string(),
+            "Got AppInfo for 232250".to_string(),
+            "Processing depot 232256".to_string(),
+            "Downloading depot 232256 - Team Fortress 2 Dedicated Server".to_string(),
+            format!("Validating {unchanged}"),
+            format!("Validating {repaired}"),
+            format!("Pre-allocating {missing}"),
+            format!(" 41.87% {unchanged}"),
+            format!(" 72.03% {missing}"),
+            format!("100.00% {repaired}"),
+            "Depot 232256 - Downloaded 1048576 bytes (4194304 bytes uncompressed)".to_string(),
+            "Total downloaded: 1048576 bytes (4194304 bytes uncompressed) from 1 depots"
+                .to_string(),
+            "Disconnected from Steam".to_string(),
+        ]
+        .iter()
+        .flat_map(|line| output.read(line))
+        .filter(|event| matches!(event, InstallEvent::Repair { .. }))
+        .collect::<Vec<_>>();
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            repairs,
+            [
+                InstallEvent::Repair {
+                    path: missing,
+                    problem: FileProblem::Missing,
+                },
+                InstallEvent::Repair {
+                    path: repaired,
+                    problem: FileProblem::Mismatched,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_dont_report_repairs() {
+        let mut output = Output::default();
+
+        assert!(
+            !output
+                .read("Pre-allocating srv/tf/maps/ctf_2fort.bsp")
+                .iter()
+                .any(|event| matches!(event, InstallEvent::Repair { .. }))
+        );
+    }
+
+    #[test]
+    fn release_assets_are_found_by_name() {
+        let release = r#"{
+            "tag_name": "DepotDownloader_3.4.0",
+            "assets": [
+                {
+                    "name": "DepotDownloader-linux-x64.zip",
+                    "digest": "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08",
+                    "browser_download_url": "https://github.com/SteamRE/DepotDownloader/releases/download/DepotDownloader_3.4.0/DepotDownloader-linux-x64.zip"
+                },
+                {
+                    "name": "DepotDownloader-windows-x64.zip",
+                    "digest": null,
+                    "browser_download_url": "https://github.com/SteamRE/DepotDownloader/releases/download/DepotDownloader_3.4.0/DepotDownloader-windows-x64.zip"
+                }
+            ]
+        }"#;
+
+        assert_eq!(
+            parse_release(release, "DepotDownloader-linux-x64.zip"),
+            Some(Release {
+                version: "3.4.0".to_string(),
+                url: "https://github.com/SteamRE/DepotDownloader/releases/download/DepotDownloader_3.4.0/DepotDownloader-linux-x64.zip".to_string(),
+                sha256: Some(
+                    "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08".to_string()
+                ),
+            })
+        );
+        assert_eq!(
+            parse_release(release, "DepotDownloader-windows-x64.zip").map(|release| release.sha256),
+            Some(None)
+        );
+        assert_eq!(
+            parse_release(release, "DepotDownloader-macos-arm64.zip"),
+            None
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn failed_downloads_are_errors() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("mannager-depotdownloader-{}", std::process::id()));
+        let executable = dir.join(EXECUTABLE);
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &executable,
+            "#!/bin/sh\necho 'Connecting to Steam3...'\nexit 3\n",
+        )
+        .unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let result = DepotDownloader::with_executable(executable)
+            .install(232250, &dir.join("server"), &|_| {})
+            .await;
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(result, Err(Error::InstallFailedError { .. })));
+    }
+
+    #[test]
+    fn older_versions_are_replaced() {
+        assert!(is_older("3.3.0", "3.4.0"));
+        assert!(is_older("2.10.1", "3.4.0"));
+        assert!(is_older("unknown", "3.4.0"));
+        assert!(!is_older("3.4.0", "3.4.0"));
+        assert!(!is_older("3.10.0", "3.4.0"));
+    }
+
+    #[test]
+    fn pre_allocating_lines_are_left_out_of_the_log() {
+        let mut output = Output::default();
+
+        assert!(
+            !output
+                .read("Pre-allocating tf/bin/server.so")
+                .iter()
+                .any(|event| matches!(event, InstallEvent::Log(_)))
+        );
+        assert_eq!(
+            output.read("Got AppInfo for 232250"),
+            [InstallEvent::Log("Got AppInfo for 232250".to_string())]
+        );
     }
 }
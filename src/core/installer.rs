@@ -0,0 +1,296 @@
+//! Installing and updating the files of a dedicated server from Steam.
+//!
+//! Every way of getting them is an [`Installer`], which reports how it goes through
+//! [`InstallEvent`]s instead of output to scrape.
+//!
+//! There's no native backend downloading from the Steam CDN. Anonymous depot downloads need
+//! a Steam client session for the depot keys and manifest request codes, which would take a
+//! port of SteamKit, so installs still go through DepotDownloader or SteamCMD.
+
+use std::path::Path;
+
+use decoder::Value;
+
+use super::{Error, depotdownloader::DepotDownloader, steamcmd::SteamCmd, updates};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum DownloadPhase {
+    #[default]
+    Connecting,
+    ResolvingDepots,
+    /// Checking the installed files, when validating.
+    Verifying,
+    Downloading,
+    Validating,
+    Done,
+}
+
+#[derive(Debug, Clone)]
+pub struct DepotStatus {
+    pub id: u32,
+    /// From 0 to 100.
+    pub progress: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallEvent {
+    /// The install moved on to a later phase.
+    Phase(DownloadPhase),
+    /// A depot of the app is going to be downloaded.
+    Depot {
+        id: u32,
+    },
+    DepotProgress {
+        id: u32,
+        progress: f32,
+    },
+    /// A file that was missing or damaged is being downloaded again, when validating.
+    Repair {
+        path: String,
+        problem: FileProblem,
+    },
+    /// A line of output, for the download log.
+    Log(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileProblem {
+    Missing,
+    /// The file didn't match its checksums.
+    Mismatched,
+}
+
+/// The files a validation found wrong, which it repaired.
+///
+/// SteamCMD doesn't say which files it repairs, so its reports are always empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    pub missing: Vec<String>,
+    pub mismatched: Vec<String>,
+}
+
+impl RepairReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.mismatched.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.missing.len() + self.mismatched.len()
+    }
+}
+
+pub trait Installer {
+    /// Installs `app` into `path`, or updates the install already there.
+    fn install(
+        &mut self,
+        app: u32,
+        path: &Path,
+        events: &impl Fn(InstallEvent),
+    ) -> impl Future<Output = Result<(), Error>>;
+}
+
+/// Which tool downloads a server's files.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Backend {
+    #[default]
+    DepotDownloader,
+    SteamCmd,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::DepotDownloader, Backend::SteamCmd];
+
+    pub fn encode(&self) -> &'static str {
+        match self {
+            Backend::DepotDownloader => "depotdownloader",
+            Backend::SteamCmd => "steamcmd",
+        }
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "depotdownloader" => Ok(Backend::DepotDownloader),
+            "steamcmd" => Ok(Backend::SteamCmd),
+            backend => Err(format!("'{backend}' is not a valid installer")),
+        }
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Backend::DepotDownloader => "DepotDownloader",
+            Backend::SteamCmd => "SteamCMD",
+        })
+    }
+}
+
+/// A depot held at an exact manifest, e.g. to stay on a build from before a bad update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ManifestPin {
+    pub depot: u32,
+    pub manifest: u64,
+}
+
+impl std::str::FromStr for ManifestPin {
+    type Err = String;
+
+    /// Parses `<depot>:<manifest>`, e.g. `232256:3459476342547125403`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .split_once(':')
+            .and_then(|(depot, manifest)| {
+                Some(Self {
+                    depot: depot.trim().parse().ok()?,
+                    manifest: manifest.trim().parse().ok()?,
+                })
+            })
+            .ok_or_else(|| format!("'{s}' is not a valid <depot>:<manifest> pair"))
+    }
+}
+
+impl std::fmt::Display for ManifestPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.depot, self.manifest)
+    }
+}
+
+/// How a server's files are installed, saved with the server.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstallOptions {
+    pub backend: Backend,
+    /// A beta branch, e.g. `prerelease`. The public one when `None`.
+    pub beta: Option<String>,
+    /// The password of a private beta branch.
+    pub beta_password: Option<String>,
+    /// Depots held at a manifest. Only these depots are downloaded when there's any.
+    /// DepotDownloader only.
+    pub manifests: Vec<ManifestPin>,
+    /// The account SteamCMD logs in with, anonymous when `None`.
+    pub login: Option<String>,
+}
+
+impl InstallOptions {
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, sequence, string};
+
+        let mut options = map(value)?;
+
+        Ok(Self {
+            backend: options
+                .optional("backend", string)?
+                .map(|backend| backend.parse().map_err(decoder::Error::Custom))
+                .transpose()?
+                .unwrap_or_default(),
+            beta: options.optional("beta", string)?,
+            beta_password: options.optional("beta_password", string)?,
+            // Stored as strings, manifest IDs don't always fit in a TOML integer.
+            manifests: options
+                .optional("manifests", sequence(decode_manifest))?
+                .unwrap_or_default(),
+            login: options.optional("login", string)?,
+        })
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::{map, optional, sequence, string};
+
+        let manifest = |pin: &ManifestPin| string(&pin.to_string());
+
+        map([
+            ("backend", string(self.backend.encode())),
+            ("beta", optional(string, self.beta.clone())),
+            (
+                "beta_password",
+                optional(string, self.beta_password.clone()),
+            ),
+            ("manifests", sequence(manifest, &self.manifests)),
+            ("login", optional(string, self.login.clone())),
+        ])
+        .into()
+    }
+}
+
+fn decode_manifest(value: Value) -> Result<ManifestPin, decoder::Error> {
+    use decoder::decode::string;
+
+    string(value)?.parse().map_err(decoder::Error::Custom)
+}
+
+/// Installs `app` into `path` with the backend picked in `options`. `validate` checks the
+/// files already there too.
+pub async fn install(
+    options: &InstallOptions,
+    validate: bool,
+    app: u32,
+    path: &Path,
+    events: &impl Fn(InstallEvent),
+) -> Result<(), Error> {
+    match options.backend {
+        Backend::DepotDownloader => {
+            let mut depot_downloader = DepotDownloader::locate().await?;
+
+            depot_downloader.options = options.clone();
+            depot_downloader.validate = validate;
+
+            depot_downloader.install(app, path, events).await?;
+
+            // Unlike SteamCMD, DepotDownloader doesn't keep the build it installed.
+            updates::record_build(path, app, options.beta.as_deref()).await;
+
+            Ok(())
+        }
+        Backend::SteamCmd => {
+            let mut steamcmd = SteamCmd::new(SteamCmd::default_path()?).await?;
+
+            steamcmd.options = options.clone();
+            steamcmd.validate = validate;
+
+            steamcmd.install(app, path, events).await
+        }
+    }
+}
+
+/// Where an install is at, built up from its events.
+#[derive(Debug, Clone, Default)]
+pub struct InstallState {
+    pub phase: DownloadPhase,
+    pub depots: Vec<DepotStatus>,
+    pub repairs: RepairReport,
+}
+
+impl InstallState {
+    pub fn apply(&mut self, event: &InstallEvent) {
+        match event {
+            InstallEvent::Phase(phase) => self.phase = phase.clone().max(self.phase.clone()),
+            InstallEvent::Depot { id } => {
+                if !self.depots.iter().any(|depot| depot.id == *id) {
+                    self.depots.push(DepotStatus {
+                        id: *id,
+                        progress: 0.0,
+                    });
+                }
+            }
+            InstallEvent::DepotProgress { id, progress } => {
+                if let Some(depot) = self.depots.iter_mut().find(|depot| depot.id == *id) {
+                    depot.progress = *progress;
+                }
+            }
+            InstallEvent::Repair { path, problem } => {
+                let files = match problem {
+                    FileProblem::Missing => &mut self.repairs.missing,
+                    FileProblem::Mismatched => &mut self.repairs.mismatched,
+                };
+
+                if !files.contains(path) {
+                    files.push(path.clone());
+                }
+            }
+            InstallEvent::Log(_) => {}
+        }
+    }
+}
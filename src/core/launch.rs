@@ -0,0 +1,469 @@
+//! Command line arguments for launching a dedicated server.
+
+use super::{
+    Game,
+    workshop::{Workshop, WorkshopItems},
+};
+
+/// The file the server description is written to, inside the game folder, and shown as the MOTD.
+pub const MOTD_FILE_NAME: &str = "mannager_motd.txt";
+
+/// A server setting that can be passed on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Hostname,
+    Map,
+    MaxPlayers,
+    Port,
+    Password,
+    Region,
+    Tags,
+    Tickrate,
+    Motd,
+    Gslt,
+    RconPassword,
+    /// A flag without a value, turned on when hosting through Steam Datagram Relay.
+    FakeIp,
+}
+
+/// How a game's dedicated server wants to be launched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchProfile {
+    /// Arguments every server of the game starts with.
+    pub base_args: Vec<&'static str>,
+    /// The argument each supported setting is passed as. Settings that aren't listed are left out.
+    pub settings: Vec<(Setting, &'static str)>,
+    pub max_players: u32,
+    /// The player count above which the server needs an extra flag, and that flag.
+    pub unrestricted_max_players: Option<(u32, &'static str)>,
+    pub default_map: &'static str,
+    /// How the server hosts Steam Workshop maps, if it can.
+    pub workshop: Option<Workshop>,
+}
+
+impl LaunchProfile {
+    /// The common profile of `srcds` based games.
+    pub fn source1(game: Game, default_map: &'static str) -> Self {
+        Self {
+            base_args: vec![
+                "-console",
+                "-game",
+                game.arg_name(),
+                "-nohltv",
+                "+ip",
+                "0.0.0.0",
+                "-strictportbind",
+                // Mirrors the console to a log file, so it can be followed after the app restarts.
+                // It's moved aside on every start, see `Server::rotate_console_log`.
+                "-condebug",
+            ],
+            settings: vec![
+                (Setting::Hostname, "+hostname"),
+                (Setting::Map, "+map"),
+                (Setting::MaxPlayers, "+maxplayers"),
+                (Setting::Port, "-port"),
+                (Setting::Password, "+sv_password"),
+                (Setting::Region, "+sv_region"),
+                (Setting::Tags, "+sv_tags"),
+                (Setting::Motd, "+motdfile"),
+                (Setting::Gslt, "+sv_setsteamaccount"),
+                (Setting::RconPassword, "+rcon_password"),
+                (Setting::FakeIp, "-enablefakeip"),
+            ],
+            max_players: 32,
+            unrestricted_max_players: None,
+            default_map,
+            workshop: None,
+        }
+    }
+
+    /// The common profile of Source 2 games. They don't read a MOTD file, nor know `sv_region`.
+    pub fn source2(default_map: &'static str) -> Self {
+        Self {
+            // RCON is off unless asked for, and the console, stop and player list rely on it.
+            base_args: vec!["-dedicated", "-usercon", "-condebug"],
+            settings: vec![
+                (Setting::Hostname, "+hostname"),
+                (Setting::Map, "+map"),
+                (Setting::MaxPlayers, "+maxplayers"),
+                (Setting::Port, "-port"),
+                (Setting::Password, "+sv_password"),
+                (Setting::Tags, "+sv_tags"),
+                (Setting::Gslt, "+sv_setsteamaccount"),
+                (Setting::RconPassword, "+rcon_password"),
+                (Setting::FakeIp, "-enablefakeip"),
+            ],
+            max_players: 64,
+            unrestricted_max_players: None,
+            default_map,
+            workshop: None,
+        }
+    }
+
+    pub fn max_players(mut self, max_players: u32) -> Self {
+        self.max_players = max_players;
+
+        self
+    }
+
+    pub fn unrestricted_above(mut self, max_players: u32, flag: &'static str) -> Self {
+        self.unrestricted_max_players = Some((max_players, flag));
+
+        self
+    }
+
+    pub fn workshop(mut self, workshop: Workshop) -> Self {
+        self.workshop = Some(workshop);
+
+        self
+    }
+
+    pub fn with(mut self, setting: Setting, arg: &'static str) -> Self {
+        self.settings.push((setting, arg));
+
+        self
+    }
+
+    pub fn without(mut self, setting: Setting) -> Self {
+        self.settings.retain(|(supported, _)| *supported != setting);
+
+        self
+    }
+
+    pub fn supports(&self, setting: Setting) -> bool {
+        self.settings
+            .iter()
+            .any(|(supported, _)| *supported == setting)
+    }
+}
+
+/// The values of a server that end up on its command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub hostname: String,
+    pub map: String,
+    pub max_players: u32,
+    pub port: u16,
+    pub password: Option<String>,
+    pub has_motd: bool,
+    pub region: Option<Region>,
+    pub tags: Option<String>,
+    pub tickrate: Option<u32>,
+    pub gslt: Option<String>,
+    pub rcon_password: Option<String>,
+    pub fake_ip: bool,
+    /// Left out for games that can't host Workshop maps.
+    pub workshop: WorkshopItems,
+    /// Appended as is, after everything else.
+    pub extra_args: Vec<String>,
+    /// `+convar value` pairs, replacing the ones set from the other options.
+    pub convars: Vec<(String, String)>,
+}
+
+impl LaunchOptions {
+    /// The arguments, one per element, so values with spaces don't need any quoting.
+    pub fn args(&self, profile: &LaunchProfile) -> Vec<String> {
+        let mut args: Vec<String> = profile.base_args.iter().map(ToString::to_string).collect();
+
+        for (setting, arg) in &profile.settings {
+            let value = match setting {
+                Setting::Hostname => Some(self.hostname.clone()),
+                // A hosted Workshop map is loaded instead.
+                Setting::Map => match profile.workshop {
+                    Some(Workshop::Hosted { .. }) if !self.workshop.is_empty() => None,
+                    _ => Some(self.map.clone()),
+                },
+                Setting::MaxPlayers => Some(self.max_players.min(profile.max_players).to_string()),
+                Setting::Port => Some(self.port.to_string()),
+                Setting::Password => non_empty(&self.password),
+                Setting::Region => self.region.map(|region| region.code().to_string()),
+                Setting::Tags => non_empty(&self.tags),
+                Setting::Tickrate => self.tickrate.map(|tickrate| tickrate.to_string()),
+                Setting::Motd => self.has_motd.then(|| MOTD_FILE_NAME.to_string()),
+                Setting::Gslt => non_empty(&self.gslt),
+                Setting::RconPassword => non_empty(&self.rcon_password),
+                Setting::FakeIp => {
+                    if self.fake_ip {
+                        args.push(arg.to_string());
+                    }
+
+                    continue;
+                }
+            };
+
+            if let Some(value) = value {
+                args.push(arg.to_string());
+                args.push(value);
+            }
+        }
+
+        if let Some((max_players, flag)) = profile.unrestricted_max_players
+            && self.max_players > max_players
+        {
+            args.push(flag.to_string());
+        }
+
+        if let Some(workshop) = profile.workshop {
+            args.extend(self.workshop.args(workshop));
+        }
+
+        args.extend(self.extra_args.iter().cloned());
+
+        for (name, value) in &self.convars {
+            let key = format!("+{}", name.trim_start_matches('+'));
+
+            if let Some(index) = args.iter().position(|arg| *arg == key) {
+                args.drain(index..(index + 2).min(args.len()));
+            }
+
+            args.push(key);
+            args.push(value.clone());
+        }
+
+        args
+    }
+}
+
+/// Splits a command line typed by the user into arguments. Double quotes group words together.
+pub fn split_args(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_arg = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// The regions `sv_region` understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    UsEast,
+    UsWest,
+    SouthAmerica,
+    Europe,
+    Asia,
+    Australia,
+    MiddleEast,
+    Africa,
+    World,
+}
+
+impl Region {
+    pub const ALL: [Region; 9] = [
+        Region::UsEast,
+        Region::UsWest,
+        Region::SouthAmerica,
+        Region::Europe,
+        Region::Asia,
+        Region::Australia,
+        Region::MiddleEast,
+        Region::Africa,
+        Region::World,
+    ];
+
+    pub fn code(&self) -> u32 {
+        match self {
+            Region::UsEast => 0,
+            Region::UsWest => 1,
+            Region::SouthAmerica => 2,
+            Region::Europe => 3,
+            Region::Asia => 4,
+            Region::Australia => 5,
+            Region::MiddleEast => 6,
+            Region::Africa => 7,
+            Region::World => 255,
+        }
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|region| region.code() == code)
+    }
+}
+
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Region::UsEast => "US East",
+            Region::UsWest => "US West",
+            Region::SouthAmerica => "South America",
+            Region::Europe => "Europe",
+            Region::Asia => "Asia",
+            Region::Australia => "Australia",
+            Region::MiddleEast => "Middle East",
+            Region::Africa => "Africa",
+            Region::World => "World",
+        })
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .filter(|value| !value.trim().is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn options() -> LaunchOptions {
+        LaunchOptions {
+            hostname: "My Server".to_string(),
+            map: "some_map".to_string(),
+            max_players: 24,
+            port: 27015,
+            password: Some("secret".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// The value following `key` in `args`, shared with the game profile tests.
+    pub(crate) fn value<'a>(args: &'a [String], key: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|arg| arg == key)
+            .and_then(|index| args.get(index + 1))
+            .map(String::as_str)
+    }
+
+    pub(crate) fn has(args: &[String], flag: &str) -> bool {
+        args.iter().any(|arg| arg == flag)
+    }
+
+    #[test]
+    fn values_with_spaces_stay_whole() {
+        let args = options().args(&LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort"));
+
+        assert_eq!(value(&args, "+hostname"), Some("My Server"));
+    }
+
+    #[test]
+    fn empty_values_are_left_out() {
+        let args = LaunchOptions {
+            password: Some(String::new()),
+            tags: Some("  ".to_string()),
+            ..options()
+        }
+        .args(&LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort"));
+
+        assert!(!has(&args, "+sv_password"));
+        assert!(!has(&args, "+sv_tags"));
+        assert!(!has(&args, "+motdfile"));
+        assert!(!has(&args, "-enablefakeip"));
+    }
+
+    #[test]
+    fn fake_ip() {
+        let args = LaunchOptions {
+            fake_ip: true,
+            ..options()
+        }
+        .args(&LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort"));
+
+        assert!(has(&args, "-enablefakeip"));
+    }
+
+    #[test]
+    fn unsupported_settings_are_left_out() {
+        let profile =
+            LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort").without(Setting::Password);
+
+        let args = options().args(&profile);
+
+        assert!(!has(&args, "+sv_password"));
+    }
+
+    #[test]
+    fn max_players_are_capped() {
+        let profile = LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort").max_players(32);
+
+        let args = LaunchOptions {
+            max_players: 100,
+            ..options()
+        }
+        .args(&profile);
+
+        assert_eq!(value(&args, "+maxplayers"), Some("32"));
+    }
+
+    #[test]
+    fn convars_replace_the_generated_ones() {
+        let args = LaunchOptions {
+            convars: vec![
+                ("sv_password".to_string(), "other".to_string()),
+                ("+sv_cheats".to_string(), "1".to_string()),
+            ],
+            ..options()
+        }
+        .args(&LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort"));
+
+        assert_eq!(value(&args, "+sv_password"), Some("other"));
+        assert_eq!(args.iter().filter(|arg| *arg == "+sv_password").count(), 1);
+        assert_eq!(value(&args, "+sv_cheats"), Some("1"));
+    }
+
+    #[test]
+    fn hosted_workshop_maps_replace_the_map() {
+        let profile = LaunchProfile::source2("de_dust2").workshop(Workshop::Hosted {
+            start_map_arg: "+host_workshop_map",
+        });
+
+        let args = LaunchOptions {
+            workshop: WorkshopItems {
+                collection: None,
+                maps: vec![123],
+            },
+            ..options()
+        }
+        .args(&profile);
+
+        assert!(!has(&args, "+map"));
+        assert_eq!(value(&args, "+host_workshop_map"), Some("123"));
+
+        let args = options().args(&profile);
+
+        assert_eq!(value(&args, "+map"), Some("some_map"));
+    }
+
+    #[test]
+    fn extra_args_come_last() {
+        let args = LaunchOptions {
+            extra_args: split_args("-insecure +exec \"my config.cfg\""),
+            ..options()
+        }
+        .args(&LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort"));
+
+        assert_eq!(
+            &args[args.len() - 3..],
+            ["-insecure", "+exec", "my config.cfg"]
+        );
+    }
+
+    #[test]
+    fn split_args_handles_quotes() {
+        assert_eq!(split_args(r#"  -a "b c" d""  "" "#), ["-a", "b c", "d", ""]);
+    }
+}
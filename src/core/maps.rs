@@ -0,0 +1,403 @@
+//! Finding the maps a server can run, and its map cycle.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use snafu::ResultExt;
+
+use super::{Error, Game, IoSnafu};
+
+const VPK_SIGNATURE: u32 = 0x55aa_1234;
+const MAPCYCLE_FILE_NAME: &str = "mapcycle.txt";
+const DEFAULT_MAPCYCLE_FILE_NAME: &str = "mapcycle_default.txt";
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Map {
+    /// What the server is told to load, e.g. `ctf_2fort` or `workshop/cp_foo.ugc123`.
+    pub name: String,
+    pub source: MapSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MapSource {
+    /// A `.bsp` file, or a Source 2 map `.vpk`, in the `maps` folder.
+    File,
+    Workshop,
+    /// Packed inside one of the game's `_dir.vpk` archives.
+    Vpk,
+}
+
+impl std::fmt::Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl std::fmt::Display for MapSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MapSource::File => "File",
+            MapSource::Workshop => "Workshop",
+            MapSource::Vpk => "VPK",
+        })
+    }
+}
+
+/// Every map found in `game_directory`, sorted by name. A map found in several places is
+/// listed once, preferring loose files.
+pub async fn scan(game_directory: PathBuf) -> Result<Vec<Map>, Error> {
+    tokio::task::spawn_blocking(move || scan_blocking(&game_directory))
+        .await
+        .unwrap()
+        .context(IoSnafu)
+}
+
+/// Whether `map` can be loaded by the server in `game_directory`.
+pub async fn exists(game_directory: PathBuf, map: String) -> bool {
+    let maps_directory = game_directory.join("maps");
+
+    let is_file = ["bsp", "vpk"]
+        .iter()
+        .any(|extension| maps_directory.join(format!("{map}.{extension}")).is_file());
+
+    if is_file {
+        return true;
+    }
+
+    scan(game_directory)
+        .await
+        .is_ok_and(|maps| maps.iter().any(|found| found.name == map))
+}
+
+fn scan_blocking(game_directory: &Path) -> io::Result<Vec<Map>> {
+    let maps_directory = game_directory.join("maps");
+    let mut maps = vec![];
+
+    for path in read_dir(&maps_directory)? {
+        if let Some(name) = map_name(&path) {
+            maps.push(Map {
+                name,
+                source: MapSource::File,
+            });
+        }
+    }
+
+    let workshop_directory = maps_directory.join("workshop");
+
+    for path in walk(&workshop_directory)? {
+        let Some(name) = map_name(&path) else {
+            continue;
+        };
+
+        let path = path.with_file_name(name);
+
+        let Ok(relative) = path.strip_prefix(&maps_directory) else {
+            continue;
+        };
+
+        maps.push(Map {
+            name: relative.to_string_lossy().replace('\\', "/"),
+            source: MapSource::Workshop,
+        });
+    }
+
+    for path in read_dir(game_directory)? {
+        let is_vpk_directory = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with("_dir.vpk"));
+
+        if !is_vpk_directory {
+            continue;
+        }
+
+        // A broken archive shouldn't hide every other map.
+        let Ok(names) = fs::read(&path).and_then(|bytes| vpk_maps(&bytes)) else {
+            continue;
+        };
+
+        maps.extend(names.into_iter().map(|name| Map {
+            name,
+            source: MapSource::Vpk,
+        }));
+    }
+
+    maps.sort_by(|a, b| a.name.cmp(&b.name).then(a.source.cmp(&b.source)));
+    maps.dedup_by(|a, b| a.name == b.name);
+
+    Ok(maps)
+}
+
+/// The name of a map file, without its extension.
+fn map_name(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?;
+
+    if !matches!(extension, "bsp" | "vpk") || !path.is_file() {
+        return None;
+    }
+
+    path.file_stem()?.to_str().map(str::to_string)
+}
+
+/// The entries of `directory`, or nothing if it doesn't exist.
+fn read_dir(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
+
+/// Every file below `directory`.
+fn walk(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for path in read_dir(directory)? {
+        if path.is_dir() {
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// The maps listed in the directory tree of a `_dir.vpk` archive.
+///
+/// See https://developer.valvesoftware.com/wiki/VPK_(file_format)
+fn vpk_maps(bytes: &[u8]) -> io::Result<Vec<String>> {
+    let mut reader = VpkReader { bytes, position: 0 };
+
+    if reader.u32()? != VPK_SIGNATURE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "not a VPK file"));
+    }
+
+    let header_size = match reader.u32()? {
+        1 => 12,
+        2 => 28,
+        version => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported VPK version {version}"),
+            ));
+        }
+    };
+
+    let tree_size = reader.u32()? as usize;
+
+    let tree = bytes
+        .get(header_size..header_size + tree_size)
+        .ok_or(io::ErrorKind::UnexpectedEof)?;
+
+    let mut reader = VpkReader {
+        bytes: tree,
+        position: 0,
+    };
+
+    let mut maps = vec![];
+
+    loop {
+        let extension = reader.string()?;
+
+        if extension.is_empty() {
+            break;
+        }
+
+        loop {
+            let directory = reader.string()?;
+
+            if directory.is_empty() {
+                break;
+            }
+
+            loop {
+                let file_name = reader.string()?;
+
+                if file_name.is_empty() {
+                    break;
+                }
+
+                // CRC, preload size, archive index, offset, length and terminator.
+                reader.skip(4)?;
+                let preload_size = reader.u16()?;
+                reader.skip(12)?;
+                reader.skip(preload_size.into())?;
+
+                if !matches!(extension, "bsp" | "vpk") {
+                    continue;
+                }
+
+                if directory == "maps" {
+                    maps.push(file_name.to_string());
+                } else if let Some(subdirectory) = directory.strip_prefix("maps/") {
+                    maps.push(format!("{subdirectory}/{file_name}"));
+                }
+            }
+        }
+    }
+
+    Ok(maps)
+}
+
+struct VpkReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> VpkReader<'a> {
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + length)
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> io::Result<()> {
+        self.take(length).map(|_| ())
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> io::Result<&'a str> {
+        let remaining = self.bytes.get(self.position..).unwrap_or_default();
+
+        let end = remaining
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+
+        let string = std::str::from_utf8(&remaining[..end])
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        self.position += end + 1;
+
+        Ok(string)
+    }
+}
+
+/// Where the game reads its map cycle from.
+pub fn mapcycle_path(game_directory: &Path, game: Game) -> PathBuf {
+    match game {
+        Game::CounterStrikeGlobalOffensive => game_directory.join(MAPCYCLE_FILE_NAME),
+        _ => game_directory.join("cfg").join(MAPCYCLE_FILE_NAME),
+    }
+}
+
+/// Reads the map cycle at `path`, falling back to the game's default cycle beside it.
+pub async fn load_mapcycle(path: &Path) -> Result<Vec<String>, Error> {
+    let default_path = path.with_file_name(DEFAULT_MAPCYCLE_FILE_NAME);
+
+    for path in [path, &default_path] {
+        match tokio::fs::read_to_string(path).await {
+            Ok(text) => return Ok(parse_mapcycle(&text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).context(IoSnafu),
+        }
+    }
+
+    Ok(vec![])
+}
+
+pub async fn save_mapcycle(path: &Path, maps: &[String]) -> Result<(), Error> {
+    let text = maps
+        .iter()
+        .fold(String::new(), |text, map| text + map + "\n");
+
+    tokio::fs::write(path, text).await.context(IoSnafu)
+}
+
+/// One map per line. Comments and blank lines are skipped.
+pub fn parse_mapcycle(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.split("//").next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vpk(entries: &[(&str, &str, &str)]) -> Vec<u8> {
+        let mut tree = vec![];
+
+        for (extension, directory, file_name) in entries {
+            for string in [extension, directory, file_name] {
+                tree.extend_from_slice(string.as_bytes());
+                tree.push(0);
+            }
+
+            tree.extend_from_slice(&[0; 4]);
+            tree.extend_from_slice(&0u16.to_le_bytes());
+            tree.extend_from_slice(&[0; 10]);
+            tree.extend_from_slice(&0xffffu16.to_le_bytes());
+            tree.extend_from_slice(&[0, 0]);
+        }
+
+        tree.push(0);
+
+        let mut bytes = vec![];
+
+        bytes.extend_from_slice(&VPK_SIGNATURE.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&(tree.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&tree);
+
+        bytes
+    }
+
+    #[test]
+    fn maps_are_found_in_vpks() {
+        let bytes = vpk(&[
+            ("bsp", "maps", "ctf_2fort"),
+            ("bsp", "maps/workshop", "cp_foo.ugc1"),
+            ("vmt", "materials", "wall"),
+            ("bsp", "other", "not_a_map"),
+        ]);
+
+        assert_eq!(
+            vpk_maps(&bytes).unwrap(),
+            ["ctf_2fort", "workshop/cp_foo.ugc1"]
+        );
+    }
+
+    #[test]
+    fn broken_vpks_are_rejected() {
+        let mut bytes = vpk(&[("bsp", "maps", "ctf_2fort")]);
+
+        bytes.truncate(bytes.len() - 10);
+
+        assert!(vpk_maps(&bytes).is_err());
+        assert!(vpk_maps(b"not a vpk").is_err());
+    }
+
+    #[test]
+    fn mapcycle_skips_comments() {
+        let text = "// Official maps\nctf_2fort\n\n  cp_badlands // push\r\npl_upward\n";
+
+        assert_eq!(
+            parse_mapcycle(text),
+            ["ctf_2fort", "cp_badlands", "pl_upward"]
+        );
+    }
+}
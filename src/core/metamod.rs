@@ -1,146 +1,215 @@
-use std::{fs, io::Cursor, path::Path};
+use std::{fs, path::Path};
 
-use scraper::{Html, Selector};
 use snafu::ResultExt;
 
-use crate::core::ArchiveExtractionSnafu;
-
-use super::{DirectoryCreationSnafu, Error, Game, SourceEngineVersion};
+use super::{
+    DirectoryCreationSnafu, Error, Game, SourceEngineVersion,
+    addons::{InstallProgress, Version},
+    alliedmods::{self, TARGET_OS},
+    archive::{self, Verification},
+};
 
 pub struct MetamodDownloader;
 
-const METAMOD_VERSIONS_URL: &str = "https://mms.alliedmods.net/mmsdrop";
-
-#[cfg(target_os = "windows")]
-const TARGET_OS: &str = "windows";
+pub const DEFAULT_DROP_URL: &str = "https://mms.alliedmods.net/mmsdrop";
 
-#[cfg(target_os = "linux")]
-const TARGET_OS: &str = "linux";
+/// Overrides [`DEFAULT_DROP_URL`], e.g. with a mirror or a local test server.
+const DROP_URL_VARIABLE: &str = "MANNAGER_METAMOD_DROP_URL";
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum MetamodBranch {
     Stable,
     Dev,
 }
 
+/// The Metamod:Source build to install.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetamodRelease {
+    /// The newest build of a branch, whichever it is at install time.
+    Latest(MetamodBranch),
+    /// An exact build, so every install of the server gets the same one.
+    Pinned(Version),
+}
+
+impl Default for MetamodRelease {
+    fn default() -> Self {
+        Self::Latest(MetamodBranch::Stable)
+    }
+}
+
+impl std::str::FromStr for MetamodRelease {
+    type Err = String;
+
+    /// Parses `stable`, `dev` or a version, e.g. `1.12.0.1219`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stable" => Ok(Self::Latest(MetamodBranch::Stable)),
+            "dev" => Ok(Self::Latest(MetamodBranch::Dev)),
+            version => version.parse().map(Self::Pinned),
+        }
+    }
+}
+
+impl std::fmt::Display for MetamodRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Latest(MetamodBranch::Stable) => f.write_str("Latest stable"),
+            Self::Latest(MetamodBranch::Dev) => f.write_str("Latest dev"),
+            Self::Pinned(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+impl MetamodRelease {
+    /// The form [`FromStr`](std::str::FromStr) reads back.
+    pub fn encode(&self) -> String {
+        match self {
+            Self::Latest(MetamodBranch::Stable) => "stable".to_string(),
+            Self::Latest(MetamodBranch::Dev) => "dev".to_string(),
+            Self::Pinned(version) => version.to_string(),
+        }
+    }
+}
+
 impl MetamodDownloader {
+    /// Installs Metamod:Source into the game folder. Upgrading an install keeps its list of
+    /// plugins, `addons/metamod/metaplugins.ini`.
+    ///
+    /// The archive is checked against `pinned_sha256` when given, see [`archive::fetch`].
+    /// Builds there's no hash of are only installed when `verification` allows it.
     pub async fn download(
         path: impl AsRef<Path>,
         game: &Game,
-        branch: &MetamodBranch,
+        release: &MetamodRelease,
         source_version: &SourceEngineVersion,
+        pinned_sha256: Option<&str>,
+        verification: Verification,
+        progress: &impl Fn(InstallProgress),
     ) -> Result<(), Error> {
-        let version = get_latest_metamod_version(branch, source_version).await?;
+        progress(InstallProgress::Resolving);
+
+        let (version, metamod_version_name) = match release {
+            MetamodRelease::Latest(branch) => latest_archive(branch, source_version).await?,
+            MetamodRelease::Pinned(version) => pinned_archive(version).await?,
+        };
 
         let path = path.as_ref();
 
         fs::create_dir_all(path).context(DirectoryCreationSnafu)?;
 
-        let latest_metamod_archive_name_url =
-            format!("{METAMOD_VERSIONS_URL}/{version}/mmsource-latest-{TARGET_OS}");
+        let metamod_download_url = format!("{}/{version}/{metamod_version_name}", drop_url());
 
-        let metamod_version_name = reqwest::get(latest_metamod_archive_name_url)
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?
-            .text()
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?;
+        let metamod_archive_contents =
+            archive::fetch(&metamod_download_url, pinned_sha256, verification, progress).await?;
 
-        let metamod_download_url =
-            format!("{}/{version}/{metamod_version_name}", METAMOD_VERSIONS_URL);
+        let game_directory = game.directory(path, source_version);
+        let metaplugins = Path::new("addons").join("metamod").join("metaplugins.ini");
 
-        let metamod_archive_contents = reqwest::get(metamod_download_url)
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?
-            .bytes()
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?;
+        progress(InstallProgress::Extracting);
 
-        let cursor = Cursor::new(metamod_archive_contents);
+        tokio::task::spawn_blocking(move || {
+            archive::extract(&metamod_archive_contents, &game_directory, |entry| {
+                entry == metaplugins && game_directory.join(entry).is_file()
+            })
+        })
+        .await
+        .unwrap()
+    }
+}
 
-        #[cfg(target_os = "linux")]
-        {
-            use crate::core::TarSnafu;
-            use flate2::read::GzDecoder;
+/// The version the latest archive of `branch` is for.
+pub async fn latest_version(
+    branch: &MetamodBranch,
+    source_version: &SourceEngineVersion,
+) -> Result<Version, Error> {
+    let (_, archive_name) = latest_archive(branch, source_version).await?;
 
-            let tar = GzDecoder::new(cursor);
+    Version::from_archive_name(&archive_name)
+        .ok_or_else(|| Error::ListingParseError { url: drop_url() })
+}
 
-            let mut archive = tar::Archive::new(tar);
+/// The drop listing set through `MANNAGER_METAMOD_DROP_URL`, or [`DEFAULT_DROP_URL`].
+pub fn drop_url() -> String {
+    alliedmods::base_url(DROP_URL_VARIABLE, DEFAULT_DROP_URL)
+}
 
-            archive
-                .unpack(path.to_path_buf().join(format!("{}/", game.arg_name())))
-                .context(TarSnafu)
-                .context(ArchiveExtractionSnafu)?;
-        }
+/// Every build for `source_version` available for this OS, newest first.
+pub async fn builds(source_version: &SourceEngineVersion) -> Result<Vec<Version>, Error> {
+    let drop_url = drop_url();
 
-        #[cfg(target_os = "windows")]
-        {
-            use crate::core::ZipSnafu;
+    let page_contents = archive::fetch_text(&drop_url).await?;
 
-            let mut zip = zip::ZipArchive::new(cursor)
-                .context(ZipSnafu)
-                .context(ArchiveExtractionSnafu)?;
+    let mut listings = tokio::task::JoinSet::new();
 
-            zip.extract(path.to_path_buf().join(format!("{}/", game.arg_name())))
-                .context(ZipSnafu)
-                .context(ArchiveExtractionSnafu)?;
-        }
+    for folder in alliedmods::folders(&page_contents, source_version) {
+        let url = format!("{drop_url}/{folder}/");
 
-        Ok(())
+        listings.spawn(async move { archive::fetch_text(&url).await });
     }
-}
 
-/// Oh God, this is so annoying.
-async fn get_latest_metamod_version(
-    branch: &MetamodBranch,
-    source_version: &SourceEngineVersion,
-) -> Result<String, Error> {
-    let page_contents = reqwest::get(METAMOD_VERSIONS_URL)
-        .await
-        .map_err(|_| Error::UnableToFindLatestVersionError)?
-        .text()
-        .await
-        .map_err(|_| Error::UnableToFindLatestVersionError)?;
+    let mut builds = vec![];
 
-    let html = Html::parse_fragment(&page_contents);
+    // Folders that fail to load are left out, rather than failing the whole list.
+    while let Some(listing) = listings.join_next().await {
+        if let Ok(Ok(page_contents)) = listing {
+            builds.extend(
+                alliedmods::archives(&page_contents, "mmsource", TARGET_OS)
+                    .into_iter()
+                    .map(|(version, _)| version),
+            );
+        }
+    }
 
-    let a_selector = Selector::parse("a").map_err(|_| Error::UnableToFindLatestVersionError)?;
+    builds.sort_unstable_by(|a, b| b.cmp(a));
+    builds.dedup();
 
-    let mut stable = 0u32;
-    let mut dev = 0u32;
+    Ok(builds)
+}
 
-    for element in html.select(&a_selector).skip(5) {
-        let string = element.inner_html();
+/// The folder of a pinned release, and the name of its archive for this OS.
+async fn pinned_archive(version: &Version) -> Result<(String, String), Error> {
+    let folder = format!("{}.{}", version.major, version.minor);
 
-        let mut split = string.trim_end_matches("/").trim().split(".");
+    let page_contents = archive::fetch_text(&format!("{}/{folder}/", drop_url())).await?;
 
-        let major = match split.next().and_then(|next| next.parse::<u32>().ok()) {
-            Some(1) => SourceEngineVersion::Source1,
-            Some(2) => SourceEngineVersion::Source2,
-            _ => continue,
-        };
+    let (_, archive_name) = alliedmods::archives(&page_contents, "mmsource", TARGET_OS)
+        .into_iter()
+        .find(|(build, _)| build == version)
+        .ok_or_else(|| Error::UnableToFindVersionError {
+            version: version.to_string(),
+        })?;
 
-        let Some(Ok(minor)) = split.next().map(|s| s.parse::<u32>()) else {
-            continue;
-        };
+    Ok((folder, archive_name))
+}
 
-        if &major != source_version {
-            continue;
-        }
+/// The folder of the latest `branch` release, and the name of its archive for this OS.
+async fn latest_archive(
+    branch: &MetamodBranch,
+    source_version: &SourceEngineVersion,
+) -> Result<(String, String), Error> {
+    let drop_url = drop_url();
 
-        if minor > stable {
-            stable = dev;
-        }
+    let page_contents = archive::fetch_text(&drop_url).await?;
 
-        if minor > dev {
-            dev = minor;
+    let latest = alliedmods::latest_folders(&page_contents, source_version).ok_or_else(|| {
+        Error::ListingParseError {
+            url: drop_url.clone(),
         }
-    }
+    })?;
 
-    let version: u32 = source_version.clone().into();
+    let folder = match branch {
+        MetamodBranch::Stable => latest.stable,
+        MetamodBranch::Dev => latest.dev,
+    };
 
-    match branch {
-        MetamodBranch::Stable => Ok(format!("{version}.{stable}")),
-        MetamodBranch::Dev => Ok(format!("{version}.{dev}")),
-    }
+    let latest_archive_name_url = format!("{drop_url}/{folder}/mmsource-latest-{TARGET_OS}");
+
+    let contents = archive::fetch_text(&latest_archive_name_url).await?;
+
+    let archive_name =
+        alliedmods::latest_archive_name(&contents).ok_or(Error::ListingParseError {
+            url: latest_archive_name_url,
+        })?;
+
+    Ok((folder.to_string(), archive_name))
 }
@@ -0,0 +1,345 @@
+//! Managing the SourceMod plugins of a server.
+
+pub mod index;
+
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Component, Path, PathBuf},
+};
+
+use snafu::ResultExt;
+
+use super::{
+    ArchiveExtractionSnafu, EmptyPluginArchiveSnafu, Error, IoSnafu, TarSnafu,
+    UnsupportedPluginFileSnafu, ZipSnafu,
+};
+
+/// The folders of a SourceMod install that plugin archives ship files in.
+const PLUGIN_FOLDERS: [&str; 4] = ["plugins", "translations", "configs", "gamedata"];
+
+const DISABLED_FOLDER: &str = "disabled";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plugin {
+    /// The file name, e.g. `funcommands.smx`.
+    pub file_name: String,
+    /// Where the plugin is, relative to the `plugins` folder.
+    pub path: PathBuf,
+    pub is_enabled: bool,
+}
+
+impl Plugin {
+    /// The file name without the `.smx` extension.
+    pub fn name(&self) -> &str {
+        self.file_name.trim_end_matches(".smx")
+    }
+}
+
+/// `addons/sourcemod`, inside the game folder.
+pub fn sourcemod_directory(game_directory: &Path) -> PathBuf {
+    game_directory.join("addons").join("sourcemod")
+}
+
+/// Whether SourceMod is installed in the game folder.
+pub fn is_sourcemod_installed(game_directory: &Path) -> bool {
+    sourcemod_directory(game_directory).join("plugins").is_dir()
+}
+
+/// Every plugin of the server, enabled ones first, each sorted by name.
+///
+/// SourceMod loads plugins from subfolders of `plugins` too, except from `plugins/disabled`.
+pub async fn list(game_directory: PathBuf) -> Result<Vec<Plugin>, Error> {
+    tokio::task::spawn_blocking(move || {
+        let plugins_directory = sourcemod_directory(&game_directory).join("plugins");
+
+        let mut plugins = vec![];
+
+        for path in walk(&plugins_directory, &plugins_directory.join(DISABLED_FOLDER))? {
+            if let Some(plugin) = plugin(&plugins_directory, &path, true) {
+                plugins.push(plugin);
+            }
+        }
+
+        for path in read_dir(&plugins_directory.join(DISABLED_FOLDER))? {
+            if let Some(plugin) = plugin(&plugins_directory, &path, false) {
+                plugins.push(plugin);
+            }
+        }
+
+        plugins.sort_by(|a, b| {
+            b.is_enabled
+                .cmp(&a.is_enabled)
+                .then_with(|| a.file_name.cmp(&b.file_name))
+        });
+
+        Ok(plugins)
+    })
+    .await
+    .unwrap()
+    .context(IoSnafu)
+}
+
+/// Moves a plugin in or out of `plugins/disabled`. Disabled plugins aren't loaded on the
+/// next map change.
+pub async fn set_enabled(
+    game_directory: PathBuf,
+    plugin: Plugin,
+    is_enabled: bool,
+) -> Result<(), Error> {
+    let plugins_directory = sourcemod_directory(&game_directory).join("plugins");
+
+    let destination = if is_enabled {
+        plugins_directory.join(&plugin.file_name)
+    } else {
+        plugins_directory
+            .join(DISABLED_FOLDER)
+            .join(&plugin.file_name)
+    };
+
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await.context(IoSnafu)?;
+    }
+
+    tokio::fs::rename(plugins_directory.join(&plugin.path), destination)
+        .await
+        .context(IoSnafu)
+}
+
+pub async fn remove(game_directory: PathBuf, plugin: Plugin) -> Result<(), Error> {
+    let path = sourcemod_directory(&game_directory)
+        .join("plugins")
+        .join(&plugin.path);
+
+    tokio::fs::remove_file(path).await.context(IoSnafu)
+}
+
+/// Installs a `.smx` plugin, or the SourceMod files of a `.zip` or `.tar.gz` archive.
+///
+/// Config files that already exist are kept, so settings aren't lost when reinstalling a
+/// plugin. Returns the file names of the installed plugins.
+pub async fn install(game_directory: PathBuf, file: PathBuf) -> Result<Vec<String>, Error> {
+    let file_name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let contents = tokio::fs::read(&file).await.context(IoSnafu)?;
+
+    install_contents(game_directory, file_name, contents).await
+}
+
+/// Like [`install`], for a plugin that's already in memory. `file_name` tells its format.
+pub async fn install_contents(
+    game_directory: PathBuf,
+    file_name: String,
+    contents: Vec<u8>,
+) -> Result<Vec<String>, Error> {
+    tokio::task::spawn_blocking(move || install_blocking(&game_directory, &file_name, contents))
+        .await
+        .unwrap()
+}
+
+fn install_blocking(
+    game_directory: &Path,
+    file_name: &str,
+    contents: Vec<u8>,
+) -> Result<Vec<String>, Error> {
+    let sourcemod_directory = sourcemod_directory(game_directory);
+
+    let extension = file_name.to_lowercase();
+
+    let mut installed = vec![];
+
+    let mut place = |path: &Path, contents: &mut dyn Read| -> io::Result<()> {
+        let Some(relative) = destination(path) else {
+            return Ok(());
+        };
+
+        let destination = sourcemod_directory.join(&relative);
+
+        if relative.starts_with("configs") && destination.exists() {
+            return Ok(());
+        }
+
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        io::copy(contents, &mut fs::File::create(&destination)?)?;
+
+        if relative.starts_with("plugins")
+            && let Some(name) = plugin_file_name(&relative)
+        {
+            installed.push(name);
+        }
+
+        Ok(())
+    };
+
+    if extension.ends_with(".smx") {
+        place(
+            &Path::new("plugins").join(file_name),
+            &mut Cursor::new(contents),
+        )
+        .context(IoSnafu)?;
+    } else if extension.ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(Cursor::new(contents))
+            .context(ZipSnafu)
+            .context(ArchiveExtractionSnafu)?;
+
+        for index in 0..zip.len() {
+            let mut entry = zip
+                .by_index(index)
+                .context(ZipSnafu)
+                .context(ArchiveExtractionSnafu)?;
+
+            // Entries escaping the archive's root are skipped.
+            let Some(path) = entry.enclosed_name() else {
+                continue;
+            };
+
+            if entry.is_file() {
+                place(&path, &mut entry).context(IoSnafu)?;
+            }
+        }
+    } else if extension.ends_with(".tar.gz") || extension.ends_with(".tgz") {
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(Cursor::new(contents)));
+
+        let entries = tar
+            .entries()
+            .context(TarSnafu)
+            .context(ArchiveExtractionSnafu)?;
+
+        for entry in entries {
+            let mut entry = entry.context(TarSnafu).context(ArchiveExtractionSnafu)?;
+
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = entry
+                .path()
+                .context(TarSnafu)
+                .context(ArchiveExtractionSnafu)?
+                .into_owned();
+
+            place(&path, &mut entry).context(IoSnafu)?;
+        }
+    } else {
+        return UnsupportedPluginFileSnafu { name: file_name }.fail();
+    }
+
+    if installed.is_empty() {
+        return EmptyPluginArchiveSnafu.fail();
+    }
+
+    Ok(installed)
+}
+
+/// Where a file of a plugin archive goes, relative to `addons/sourcemod`.
+///
+/// Archives lay their files out in different ways, e.g. `plugins/foo.smx`,
+/// `addons/sourcemod/plugins/foo.smx` or `foo-1.0/addons/sourcemod/plugins/foo.smx`, so
+/// everything from the first SourceMod folder on is kept.
+fn destination(path: &Path) -> Option<PathBuf> {
+    let mut components = path.components();
+
+    for component in components.by_ref() {
+        match component {
+            Component::Normal(name)
+                if name
+                    .to_str()
+                    .is_some_and(|name| PLUGIN_FOLDERS.contains(&name)) =>
+            {
+                let rest = components.as_path();
+
+                // Anything but plain names could end up outside the SourceMod folder.
+                let is_safe = rest
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+
+                return (is_safe && rest.components().next().is_some())
+                    .then(|| Path::new(name).join(rest));
+            }
+            Component::Normal(_) | Component::CurDir => continue,
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+fn plugin_file_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+
+    name.ends_with(".smx").then(|| name.to_string())
+}
+
+fn plugin(plugins_directory: &Path, path: &Path, is_enabled: bool) -> Option<Plugin> {
+    Some(Plugin {
+        file_name: plugin_file_name(path)?,
+        path: path.strip_prefix(plugins_directory).ok()?.to_path_buf(),
+        is_enabled,
+    })
+}
+
+/// The entries of `directory`, or nothing if it doesn't exist.
+fn read_dir(directory: &Path) -> io::Result<Vec<PathBuf>> {
+    match fs::read_dir(directory) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect(),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(err) => Err(err),
+    }
+}
+
+/// Every file below `directory`, except the ones in `excluded`.
+fn walk(directory: &Path, excluded: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for path in read_dir(directory)? {
+        if path == excluded {
+            continue;
+        }
+
+        if path.is_dir() {
+            files.extend(walk(&path, excluded)?);
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_layouts_are_flattened() {
+        for (path, expected) in [
+            ("plugins/foo.smx", Some("plugins/foo.smx")),
+            ("addons/sourcemod/plugins/foo.smx", Some("plugins/foo.smx")),
+            (
+                "foo-1.0/addons/sourcemod/translations/fr/foo.phrases.txt",
+                Some("translations/fr/foo.phrases.txt"),
+            ),
+            ("./gamedata/foo.txt", Some("gamedata/foo.txt")),
+            ("configs/foo/", Some("configs/foo")),
+            ("scripting/foo.sp", None),
+            ("README.md", None),
+            ("plugins", None),
+            ("plugins/../../evil.smx", None),
+            ("/plugins/foo.smx", None),
+        ] {
+            assert_eq!(
+                destination(Path::new(path)),
+                expected.map(PathBuf::from),
+                "{path}"
+            );
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! A list of plugins that can be downloaded and installed into a server.
+//!
+//! The index is a TOML file, e.g.
+//!
+//! ```toml
+//! [[plugins]]
+//! name = "Advertisements"
+//! version = "2.1.1"
+//! description = "Displays advertisements in chat"
+//! games = ["Team Fortress 2", "Counter-Strike: Source"] # Any game when left out.
+//! engine = 1 # Any engine when left out.
+//! url = "https://example.com/advertisements.zip"
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use decoder::Value;
+use snafu::ResultExt;
+
+use super::install_contents;
+use crate::core::{DownloadRequestSnafu, Error, Game, IoSnafu, SourceEngineVersion};
+
+pub const DEFAULT_INDEX_URL: &str =
+    "https://raw.githubusercontent.com/tsuza/mannager-source/main/plugins.toml";
+
+/// Overrides [`DEFAULT_INDEX_URL`], e.g. to test against a local file.
+const INDEX_URL_VARIABLE: &str = "MANNAGER_PLUGIN_INDEX";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedPlugin {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    /// The games the plugin works with. Empty when it works with any.
+    pub games: Vec<Game>,
+    pub engine: Option<SourceEngineVersion>,
+    /// Where the `.smx`, `.zip` or `.tar.gz` is downloaded from.
+    pub url: String,
+}
+
+impl IndexedPlugin {
+    pub fn supports(&self, game: Game, engine: &SourceEngineVersion) -> bool {
+        (self.games.is_empty() || self.games.contains(&game))
+            && self
+                .engine
+                .as_ref()
+                .is_none_or(|supported| supported == engine)
+    }
+
+    /// The name of the downloaded file, which tells its format.
+    pub fn file_name(&self) -> String {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+
+        path.rsplit(['/', '\\'])
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.name)
+            .to_string()
+    }
+
+    fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, sequence, string, u32};
+
+        let mut plugin = map(value)?;
+
+        Ok(Self {
+            name: plugin.required("name", string)?,
+            version: plugin.required("version", string)?,
+            description: plugin.optional("description", string)?,
+            games: plugin
+                .optional("games", sequence(Game::decode))?
+                .unwrap_or_default(),
+            engine: plugin
+                .optional("engine", u32)?
+                .map(|engine| match engine {
+                    1 => Ok(SourceEngineVersion::Source1),
+                    2 => Ok(SourceEngineVersion::Source2),
+                    engine => Err(decoder::Error::Custom(format!(
+                        "'{engine}' is not a valid engine version"
+                    ))),
+                })
+                .transpose()?,
+            url: plugin.required("url", string)?,
+        })
+    }
+}
+
+/// The index URL set through the environment, or the default one.
+pub fn index_url() -> String {
+    std::env::var(INDEX_URL_VARIABLE).unwrap_or_else(|_| DEFAULT_INDEX_URL.to_string())
+}
+
+/// Fetches the index at `url`, sorted by name. Local paths and `file://` URLs work too.
+pub async fn fetch(url: String) -> Result<Vec<IndexedPlugin>, Error> {
+    let contents = fetch_bytes(&url).await?;
+
+    let text = String::from_utf8(contents).map_err(|_| Error::InvalidPluginIndexError)?;
+
+    let mut plugins = parse(&text)?;
+
+    plugins.sort_by_key(|plugin| plugin.name.to_lowercase());
+
+    Ok(plugins)
+}
+
+pub fn parse(text: &str) -> Result<Vec<IndexedPlugin>, Error> {
+    decoder::run(toml::from_str, decode_index, text).map_err(|_| Error::InvalidPluginIndexError)
+}
+
+fn decode_index(value: Value) -> Result<Vec<IndexedPlugin>, decoder::Error> {
+    use decoder::decode::{map, sequence};
+
+    Ok(map(value)?
+        .optional("plugins", sequence(IndexedPlugin::decode))?
+        .unwrap_or_default())
+}
+
+/// Downloads `plugin` and installs it into the server in `game_directory`.
+pub async fn install(game_directory: PathBuf, plugin: IndexedPlugin) -> Result<Vec<String>, Error> {
+    let contents = fetch_bytes(&plugin.url).await?;
+
+    install_contents(game_directory, plugin.file_name(), contents).await
+}
+
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, Error> {
+    let path = url.strip_prefix("file://").map(Path::new);
+
+    if let Some(path) = path.or_else(|| {
+        let path = Path::new(url);
+
+        (!url.contains("://") && path.exists()).then_some(path)
+    }) {
+        return tokio::fs::read(path).await.context(IoSnafu);
+    }
+
+    let response = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .context(DownloadRequestSnafu)?;
+
+    let bytes = response.bytes().await.context(DownloadRequestSnafu)?;
+
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = r#"
+        [[plugins]]
+        name = "Advertisements"
+        version = "2.1.1"
+        games = ["Team Fortress 2"]
+        engine = 1
+        url = "https://example.com/releases/advertisements.zip?raw=true"
+
+        [[plugins]]
+        name = "Anything"
+        version = "1.0"
+        description = "Works everywhere"
+        url = "https://example.com/anything.smx"
+    "#;
+
+    #[test]
+    fn index_is_parsed() {
+        let plugins = parse(INDEX).unwrap();
+
+        assert_eq!(plugins.len(), 2);
+        assert_eq!(plugins[0].name, "Advertisements");
+        assert_eq!(plugins[0].games, [Game::TeamFortress2]);
+        assert_eq!(plugins[0].engine, Some(SourceEngineVersion::Source1));
+        assert_eq!(plugins[0].file_name(), "advertisements.zip");
+        assert_eq!(plugins[1].description.as_deref(), Some("Works everywhere"));
+        assert_eq!(plugins[1].file_name(), "anything.smx");
+    }
+
+    #[test]
+    fn plugins_are_filtered_by_game_and_engine() {
+        let plugins = parse(INDEX).unwrap();
+
+        assert!(plugins[0].supports(Game::TeamFortress2, &SourceEngineVersion::Source1));
+        assert!(!plugins[0].supports(Game::CounterStrikeSource, &SourceEngineVersion::Source1));
+        assert!(!plugins[0].supports(Game::TeamFortress2, &SourceEngineVersion::Source2));
+        assert!(plugins[1].supports(Game::CounterStrike2, &SourceEngineVersion::Source2));
+    }
+
+    #[test]
+    fn broken_indexes_are_rejected() {
+        assert!(parse("[[plugins]]\nname = \"No URL\"\nversion = \"1\"").is_err());
+        assert!(parse("not toml").is_err());
+        assert!(parse("").unwrap().is_empty());
+    }
+}
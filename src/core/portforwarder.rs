@@ -25,20 +25,7 @@ impl PortForwarder {
         proto: PortMappingProtocol,
         name: &str,
     ) -> Result<Self, Error> {
-        let mut forwarder = match ip {
-            PortForwarderIP::Any => {
-                let interfaces = portforwarder_rs::query_interfaces::get_network_interfaces()
-                    .context(NoInterfacesSnafu)?;
-
-                portforwarder_rs::port_forwarder::create_forwarder_from_any(
-                    interfaces.into_iter().map(|interface| interface.addr),
-                )
-                .map_err(|errs| errs.into_iter().next().unwrap())
-                .context(NoGatewayFoundSnafu)?
-            }
-            PortForwarderIP::Ip(_ip) => portforwarder_rs::port_forwarder::create_forwarder(_ip)
-                .context(NoGatewayFoundSnafu)?,
-        };
+        let mut forwarder = gateway(ip)?;
 
         forwarder
             .forward_port(local_port, remote_port, proto, name)
@@ -58,6 +45,39 @@ impl PortForwarder {
             .context(RemovePortSnafu)
             .context(PortForwardingFailedSnafu)
     }
+
+    /// Removes a mapping whose forwarder is gone, like the one of a server started before
+    /// the app was last closed.
+    pub fn release(
+        ip: PortForwarderIP,
+        remote_port: u16,
+        proto: PortMappingProtocol,
+    ) -> Result<(), Error> {
+        Self {
+            forwarder: gateway(ip)?,
+            remote_port,
+            proto,
+        }
+        .close()
+    }
+}
+
+fn gateway(ip: PortForwarderIP) -> Result<Forwarder, Error> {
+    match ip {
+        PortForwarderIP::Any => {
+            let interfaces = portforwarder_rs::query_interfaces::get_network_interfaces()
+                .context(NoInterfacesSnafu)?;
+
+            portforwarder_rs::port_forwarder::create_forwarder_from_any(
+                interfaces.into_iter().map(|interface| interface.addr),
+            )
+            .map_err(|errs| errs.into_iter().next().unwrap())
+            .context(NoGatewayFoundSnafu)
+        }
+        PortForwarderIP::Ip(_ip) => {
+            portforwarder_rs::port_forwarder::create_forwarder(_ip).context(NoGatewayFoundSnafu)
+        }
+    }
 }
 // TODO improve display errors
 #[derive(Snafu, Debug, Clone)]
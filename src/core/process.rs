@@ -0,0 +1,247 @@
+use std::{
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use snafu::ResultExt;
+
+use super::{Error, SpawnProcessSnafu};
+
+/// How long a process that was asked to terminate gets before it's killed outright.
+pub const KILL_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// When the process was started, to tell it apart from a later one given the same PID.
+///
+/// The value is only meant to be compared with another one from this function.
+pub fn start_time(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+        parse_start_time(&stat)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows::creation_time(pid)
+    }
+}
+
+/// Checks whether the process with the given PID is still the one started at `started`.
+pub fn is_same(pid: u32, started: u64) -> bool {
+    start_time(pid) == Some(started)
+}
+
+/// Reads the `starttime` field out of `/proc/<pid>/stat`, in clock ticks since boot.
+#[cfg(any(target_os = "linux", test))]
+fn parse_start_time(stat: &str) -> Option<u64> {
+    // The name in parentheses can contain spaces and parentheses itself, so the fields
+    // are counted from the last closing one. `starttime` is the 22nd, the state the 3rd.
+    let (_, fields) = stat.rsplit_once(')')?;
+
+    fields.split_whitespace().nth(22 - 3)?.parse().ok()
+}
+
+/// Waits up to `timeout` for the process to exit, and returns whether it did.
+pub async fn wait_for_exit(pid: u32, started: u64, timeout: Duration) -> bool {
+    let start = Instant::now();
+
+    while is_same(pid, started) {
+        if start.elapsed() >= timeout {
+            return false;
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    true
+}
+
+/// Kills the process and everything it spawned.
+///
+/// On Linux the servers are started as session leaders through the pty, so the
+/// whole process group gets the signal and `srcds_run` doesn't leave `srcds_linux` behind.
+/// On Windows `taskkill /T` goes through the tree instead.
+///
+/// They're asked to terminate first, and killed outright if they're still there after
+/// [`KILL_GRACE_PERIOD`].
+pub fn kill(pid: u32) -> Result<(), Error> {
+    #[cfg(target_os = "linux")]
+    {
+        let signal = |signal: &str, target: &str| {
+            Command::new("kill")
+                .args([signal, "--", target])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .context(SpawnProcessSnafu)
+        };
+
+        let group = format!("-{pid}");
+        let process = pid.to_string();
+
+        let target = if signal("-TERM", &group)?.success() {
+            group
+        } else if signal("-TERM", &process)?.success() {
+            process
+        } else {
+            return Err(Error::ProcessNotFoundError { pid });
+        };
+
+        after_grace_period(pid, || signal("-KILL", &target).map(|_| ()))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+        let taskkill = |force: bool| {
+            let pid = pid.to_string();
+            let mut args = vec!["/PID", pid.as_str(), "/T"];
+
+            if force {
+                args.push("/F");
+            }
+
+            Command::new("taskkill")
+                .args(args)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .creation_flags(CREATE_NO_WINDOW)
+                .status()
+                .context(SpawnProcessSnafu)
+        };
+
+        if !is_running(pid) {
+            return Err(Error::ProcessNotFoundError { pid });
+        }
+
+        // Processes without a window can't be asked to close, they're killed right away.
+        if !taskkill(false)?.success() {
+            return taskkill(true).map(|_| ());
+        }
+
+        after_grace_period(pid, || taskkill(true).map(|_| ()))
+    }
+}
+
+/// Waits up to [`KILL_GRACE_PERIOD`] for the process to exit, and calls `force` if it doesn't.
+fn after_grace_period(pid: u32, force: impl FnOnce() -> Result<(), Error>) -> Result<(), Error> {
+    let start = Instant::now();
+
+    while is_running(pid) {
+        if start.elapsed() >= KILL_GRACE_PERIOD {
+            return force();
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn is_running(pid: u32) -> bool {
+    windows::creation_time(pid).is_some()
+}
+
+/// Whether the process still runs, as opposed to being gone or a zombie waiting to be reaped.
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{pid}/stat"))
+        .ok()
+        .and_then(|stat| parse_state(&stat))
+        .is_some_and(|state| state != 'Z' && state != 'X')
+}
+
+/// Reads the state field out of `/proc/<pid>/stat`, e.g. `R` or `Z`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_state(stat: &str) -> Option<char> {
+    let (_, fields) = stat.rsplit_once(')')?;
+
+    fields.split_whitespace().next()?.chars().next()
+}
+
+/// Asks the kernel directly, instead of going through a shell for every poll.
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::ffi::c_void;
+
+    const PROCESS_QUERY_LIMITED_INFORMATION: u32 = 0x1000;
+    const STILL_ACTIVE: u32 = 259;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct FileTime {
+        low: u32,
+        high: u32,
+    }
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn OpenProcess(access: u32, inherit_handle: i32, pid: u32) -> *mut c_void;
+        fn GetExitCodeProcess(process: *mut c_void, exit_code: *mut u32) -> i32;
+        fn GetProcessTimes(
+            process: *mut c_void,
+            creation: *mut FileTime,
+            exit: *mut FileTime,
+            kernel: *mut FileTime,
+            user: *mut FileTime,
+        ) -> i32;
+        fn CloseHandle(handle: *mut c_void) -> i32;
+    }
+
+    /// When the process was created, as a `FILETIME`, if it's still running.
+    pub fn creation_time(pid: u32) -> Option<u64> {
+        // SAFETY: The handle is checked before it's used, and closed before returning.
+        unsafe {
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+
+            if process.is_null() {
+                return None;
+            }
+
+            let mut exit_code = 0;
+            let [mut creation, mut exit, mut kernel, mut user] = Default::default();
+
+            let is_running =
+                GetExitCodeProcess(process, &mut exit_code) != 0 && exit_code == STILL_ACTIVE;
+
+            let has_times =
+                GetProcessTimes(process, &mut creation, &mut exit, &mut kernel, &mut user) != 0;
+
+            CloseHandle(process);
+
+            (is_running && has_times)
+                .then(|| (u64::from(creation.high) << 32) | u64::from(creation.low))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_time_is_read_past_the_name() {
+        let stat = "4242 (srcds (main) x) S 1 4242 4242 0 -1 4194560 1061 0 0 0 12 3 0 0 20 0 \
+                    9 0 73219 1181696 620 18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 3";
+
+        assert_eq!(parse_start_time(stat), Some(73219));
+    }
+
+    #[test]
+    fn zombies_are_told_apart() {
+        assert_eq!(parse_state("4242 (srcds_linux) Z 1 4242"), Some('Z'));
+        assert_eq!(parse_state("4242 (a) b) R 1 4242"), Some('R'));
+    }
+
+    #[test]
+    fn truncated_stats_have_no_start_time() {
+        assert_eq!(parse_start_time("4242 (srcds) S 1 4242"), None);
+    }
+}
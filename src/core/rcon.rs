@@ -0,0 +1,325 @@
+//! Source RCON client over TCP.
+//!
+//! See https://developer.valvesoftware.com/wiki/Source_RCON_Protocol
+
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
+
+use snafu::{ResultExt, Snafu};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+
+const SERVERDATA_AUTH: i32 = 3;
+const SERVERDATA_AUTH_RESPONSE: i32 = 2;
+const SERVERDATA_EXECCOMMAND: i32 = 2;
+const SERVERDATA_RESPONSE_VALUE: i32 = 0;
+
+/// Id, type and the two null terminators.
+const MIN_PACKET_SIZE: i32 = 10;
+const MAX_PACKET_SIZE: i32 = 4096 + MIN_PACKET_SIZE;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct RconClient {
+    stream: TcpStream,
+    /// What was read past the last whole packet, so a read that times out loses nothing.
+    buffer: Vec<u8>,
+    next_id: i32,
+}
+
+struct Packet {
+    id: i32,
+    kind: i32,
+    body: String,
+}
+
+impl RconClient {
+    pub async fn connect(address: SocketAddr, password: &str) -> Result<Self, Error> {
+        let stream = timeout(READ_TIMEOUT, TcpStream::connect(address))
+            .await
+            .map_err(|_| Error::TimedOut)?
+            .context(ConnectionSnafu)?;
+
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+            next_id: 1,
+        };
+
+        client.authenticate(password).await?;
+
+        Ok(client)
+    }
+
+    async fn authenticate(&mut self, password: &str) -> Result<(), Error> {
+        let id = self.id();
+
+        self.send(id, SERVERDATA_AUTH, password).await?;
+
+        // The server sends an empty SERVERDATA_RESPONSE_VALUE before the actual auth response.
+        loop {
+            let packet = self.receive(READ_TIMEOUT).await?;
+
+            if packet.kind != SERVERDATA_AUTH_RESPONSE {
+                continue;
+            }
+
+            return match packet.id {
+                -1 => Err(Error::AuthenticationFailed),
+                packet_id if packet_id == id => Ok(()),
+                _ => Err(Error::UnexpectedResponse),
+            };
+        }
+    }
+
+    /// Runs `command` and returns the whole output, even if it was split across several packets.
+    pub async fn exec(&mut self, command: &str) -> Result<String, Error> {
+        let id = self.id();
+        let mirror_id = self.id();
+
+        self.send(id, SERVERDATA_EXECCOMMAND, command).await?;
+
+        // The server answers requests in order, so the reply to this empty packet marks the end
+        // of the command's output.
+        self.send(mirror_id, SERVERDATA_RESPONSE_VALUE, "").await?;
+
+        let mut output = String::new();
+
+        // SRCDS follows the mirrored packet with a second one. It comes in before the output of
+        // the next command and carries the old id, so it's skipped like any other stray packet.
+        loop {
+            let packet = self.receive(READ_TIMEOUT).await?;
+
+            if packet.id == id {
+                output.push_str(&packet.body);
+            } else if packet.id == mirror_id {
+                break;
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn id(&mut self) -> i32 {
+        let id = self.next_id;
+
+        self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+
+        id
+    }
+
+    async fn send(&mut self, id: i32, kind: i32, body: &str) -> Result<(), Error> {
+        let size = i32::try_from(body.len())
+            .ok()
+            .map(|len| len + MIN_PACKET_SIZE)
+            .filter(|size| *size <= MAX_PACKET_SIZE)
+            .ok_or(Error::CommandTooLong)?;
+
+        let mut packet = Vec::with_capacity(size as usize + 4);
+
+        packet.extend_from_slice(&size.to_le_bytes());
+        packet.extend_from_slice(&id.to_le_bytes());
+        packet.extend_from_slice(&kind.to_le_bytes());
+        packet.extend_from_slice(body.as_bytes());
+        packet.extend_from_slice(&[0, 0]);
+
+        self.stream
+            .write_all(&packet)
+            .await
+            .context(ConnectionSnafu)
+    }
+
+    async fn receive(&mut self, duration: Duration) -> Result<Packet, Error> {
+        loop {
+            if let Some(packet) = parse_packet(&mut self.buffer)? {
+                return Ok(packet);
+            }
+
+            // Reading into the buffer is cancel safe, a timeout keeps the bytes already read.
+            let read = timeout(duration, self.stream.read_buf(&mut self.buffer))
+                .await
+                .map_err(|_| Error::TimedOut)?
+                .context(ConnectionSnafu)?;
+
+            if read == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof)).context(ConnectionSnafu);
+            }
+        }
+    }
+}
+
+/// Takes the first packet out of `buffer`, once all of it was read.
+fn parse_packet(buffer: &mut Vec<u8>) -> Result<Option<Packet>, Error> {
+    let Some(size) = buffer.first_chunk().copied().map(i32::from_le_bytes) else {
+        return Ok(None);
+    };
+
+    if !(MIN_PACKET_SIZE..=MAX_PACKET_SIZE).contains(&size) {
+        return Err(Error::UnexpectedResponse);
+    }
+
+    let length = 4 + size as usize;
+
+    if buffer.len() < length {
+        return Ok(None);
+    }
+
+    let packet: Vec<u8> = buffer.drain(..length).skip(4).collect();
+
+    let id = i32::from_le_bytes([packet[0], packet[1], packet[2], packet[3]]);
+    let kind = i32::from_le_bytes([packet[4], packet[5], packet[6], packet[7]]);
+
+    let body = &packet[8..];
+
+    let end = body
+        .iter()
+        .position(|byte| *byte == 0)
+        .unwrap_or(body.len());
+
+    Ok(Some(Packet {
+        id,
+        kind,
+        body: String::from_utf8_lossy(&body[..end]).into_owned(),
+    }))
+}
+
+#[derive(Snafu, Debug, Clone)]
+pub enum Error {
+    #[snafu(display("RCON connection failed: {source}"))]
+    ConnectionError {
+        #[snafu(source(from(io::Error, Arc::new)))]
+        source: Arc<io::Error>,
+    },
+
+    #[snafu(display("Wrong RCON password"))]
+    AuthenticationFailed,
+
+    #[snafu(display("The server didn't answer in time"))]
+    TimedOut,
+
+    #[snafu(display("The command is too long"))]
+    CommandTooLong,
+
+    #[snafu(display("The server answered with an unexpected packet"))]
+    UnexpectedResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tokio::net::TcpListener;
+
+    async fn read_packet(stream: &mut TcpStream) -> (i32, i32, String) {
+        let size = stream.read_i32_le().await.unwrap();
+        let id = stream.read_i32_le().await.unwrap();
+        let kind = stream.read_i32_le().await.unwrap();
+
+        let mut body = vec![0u8; (size - 8) as usize];
+        stream.read_exact(&mut body).await.unwrap();
+
+        let end = body.iter().position(|byte| *byte == 0).unwrap();
+
+        (id, kind, String::from_utf8(body[..end].to_vec()).unwrap())
+    }
+
+    fn packet(id: i32, kind: i32, body: &[u8]) -> Vec<u8> {
+        let mut packet = (body.len() as i32 + MIN_PACKET_SIZE).to_le_bytes().to_vec();
+        packet.extend(id.to_le_bytes());
+        packet.extend(kind.to_le_bytes());
+        packet.extend(body);
+        packet.extend([0, 0]);
+        packet
+    }
+
+    /// Starts a fake server that answers the login with `auth_id`, then runs `session`.
+    async fn serve<F>(
+        auth_id: Option<i32>,
+        session: impl FnOnce(TcpStream) -> F + Send + 'static,
+    ) -> SocketAddr
+    where
+        F: Future<Output = ()> + Send,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let (id, kind, password) = read_packet(&mut stream).await;
+
+            assert_eq!(kind, SERVERDATA_AUTH);
+            assert_eq!(password, "secret");
+
+            let mut reply = packet(id, SERVERDATA_RESPONSE_VALUE, b"");
+            reply.extend(packet(auth_id.unwrap_or(id), SERVERDATA_AUTH_RESPONSE, b""));
+
+            stream.write_all(&reply).await.unwrap();
+
+            session(stream).await;
+        });
+
+        address
+    }
+
+    #[tokio::test]
+    async fn wrong_passwords_are_refused() {
+        let address = serve(Some(-1), |_| async {}).await;
+
+        let result = RconClient::connect(address, "secret").await;
+
+        assert!(matches!(result, Err(Error::AuthenticationFailed)));
+    }
+
+    #[tokio::test]
+    async fn split_output_is_joined_up_to_the_mirror() {
+        let address = serve(None, |mut stream| async move {
+            for output in [["first ", "half"], ["second", ""]] {
+                let (id, kind, _) = read_packet(&mut stream).await;
+                let (mirror_id, mirror_kind, _) = read_packet(&mut stream).await;
+
+                assert_eq!(kind, SERVERDATA_EXECCOMMAND);
+                assert_eq!(mirror_kind, SERVERDATA_RESPONSE_VALUE);
+
+                let mut reply = Vec::new();
+
+                for part in output {
+                    reply.extend(packet(id, SERVERDATA_RESPONSE_VALUE, part.as_bytes()));
+                }
+
+                reply.extend(packet(mirror_id, SERVERDATA_RESPONSE_VALUE, b""));
+                reply.extend(packet(mirror_id, SERVERDATA_RESPONSE_VALUE, &[0, 1, 0, 0]));
+
+                // Sent a few bytes at a time, so packets are split across reads.
+                for chunk in reply.chunks(5) {
+                    stream.write_all(chunk).await.unwrap();
+                    stream.flush().await.unwrap();
+                }
+            }
+        })
+        .await;
+
+        let mut client = RconClient::connect(address, "secret").await.unwrap();
+
+        assert_eq!(client.exec("status").await.unwrap(), "first half");
+        assert_eq!(client.exec("users").await.unwrap(), "second");
+    }
+
+    #[test]
+    fn packets_are_taken_once_whole() {
+        let mut buffer = packet(7, SERVERDATA_RESPONSE_VALUE, b"hello");
+        let tail = buffer.split_off(10);
+
+        assert!(parse_packet(&mut buffer).unwrap().is_none());
+
+        buffer.extend(tail);
+        buffer.extend([1, 2]);
+
+        let packet = parse_packet(&mut buffer).unwrap().unwrap();
+
+        assert_eq!((packet.id, packet.body.as_str()), (7, "hello"));
+        assert_eq!(buffer, [1, 2]);
+    }
+}
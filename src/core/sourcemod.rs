@@ -1,11 +1,13 @@
-use std::{fs, io::Cursor, path::Path};
+use std::{fs, path::Path};
 
-use scraper::{Html, Selector};
 use snafu::ResultExt;
 
-use crate::core::ArchiveExtractionSnafu;
-
-use super::{DirectoryCreationSnafu, Error, Game, SourceEngineVersion};
+use super::{
+    DirectoryCreationSnafu, Error, Game, SourceEngineVersion,
+    addons::{InstallProgress, Version},
+    alliedmods::{self, TARGET_OS},
+    archive::{self, Verification},
+};
 
 pub struct SourcemodDownloader;
 
@@ -15,135 +17,218 @@ pub enum SourcemodBranch {
     Dev,
 }
 
-const SOURCEMOD_VERSIONS_URL: &str = "https://sm.alliedmods.net/smdrop";
+/// The SourceMod build to install.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourcemodRelease {
+    /// The newest build of a branch, whichever it is at install time.
+    Latest(SourcemodBranch),
+    /// An exact build, so every install of the server gets the same one.
+    Pinned(Version),
+}
+
+impl Default for SourcemodRelease {
+    fn default() -> Self {
+        Self::Latest(SourcemodBranch::Stable)
+    }
+}
+
+impl std::str::FromStr for SourcemodRelease {
+    type Err = String;
+
+    /// Parses `stable`, `dev` or a version, e.g. `1.11.0.6968`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stable" => Ok(Self::Latest(SourcemodBranch::Stable)),
+            "dev" => Ok(Self::Latest(SourcemodBranch::Dev)),
+            version => version.parse().map(Self::Pinned),
+        }
+    }
+}
+
+impl std::fmt::Display for SourcemodRelease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Latest(SourcemodBranch::Stable) => f.write_str("Latest stable"),
+            Self::Latest(SourcemodBranch::Dev) => f.write_str("Latest dev"),
+            Self::Pinned(version) => write!(f, "{version}"),
+        }
+    }
+}
+
+impl SourcemodRelease {
+    /// The form [`FromStr`](std::str::FromStr) reads back.
+    pub fn encode(&self) -> String {
+        match self {
+            Self::Latest(SourcemodBranch::Stable) => "stable".to_string(),
+            Self::Latest(SourcemodBranch::Dev) => "dev".to_string(),
+            Self::Pinned(version) => version.to_string(),
+        }
+    }
+}
 
-#[cfg(target_os = "windows")]
-const TARGET_OS: &str = "windows";
+pub const DEFAULT_DROP_URL: &str = "https://sm.alliedmods.net/smdrop";
 
-#[cfg(target_os = "linux")]
-const TARGET_OS: &str = "linux";
+/// Overrides [`DEFAULT_DROP_URL`], e.g. with a mirror or a local test server.
+const DROP_URL_VARIABLE: &str = "MANNAGER_SOURCEMOD_DROP_URL";
 
 impl SourcemodDownloader {
+    /// Installs SourceMod into the game folder. Upgrading an install keeps its configs and
+    /// its plugins, see [`is_preserved`].
+    ///
+    /// The archive is checked against `pinned_sha256` when given, see [`archive::fetch`].
+    /// Builds there's no hash of are only installed when `verification` allows it.
     pub async fn download(
         path: impl AsRef<Path>,
         game: &Game,
-        branch: &SourcemodBranch,
+        release: &SourcemodRelease,
         source_version: &SourceEngineVersion,
+        pinned_sha256: Option<&str>,
+        verification: Verification,
+        progress: &impl Fn(InstallProgress),
     ) -> Result<(), Error> {
-        let version = get_latest_sourcemod_version(branch, source_version).await?;
+        progress(InstallProgress::Resolving);
+
+        let (version, sourcemod_version_name) = match release {
+            SourcemodRelease::Latest(branch) => latest_archive(branch, source_version).await?,
+            SourcemodRelease::Pinned(version) => pinned_archive(version).await?,
+        };
 
         let path = path.as_ref();
 
         fs::create_dir_all(path).context(DirectoryCreationSnafu)?;
 
-        let latest_sourcemod_archive_name_url =
-            format!("{SOURCEMOD_VERSIONS_URL}/{version}/sourcemod-latest-{TARGET_OS}",);
+        let sourcemod_download_url = format!("{}/{version}/{sourcemod_version_name}", drop_url());
 
-        let sourcemod_version_name = reqwest::get(latest_sourcemod_archive_name_url)
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?
-            .text()
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?;
+        let sourcemod_archive_contents = archive::fetch(
+            &sourcemod_download_url,
+            pinned_sha256,
+            verification,
+            progress,
+        )
+        .await?;
 
-        let sourcemod_download_url = format!(
-            "{}/{version}/{sourcemod_version_name}",
-            SOURCEMOD_VERSIONS_URL
-        );
+        let game_directory = game.directory(path, source_version);
 
-        let sourcemod_archive_contents = reqwest::get(sourcemod_download_url)
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?
-            .bytes()
-            .await
-            .map_err(|_| Error::UnableToFindLatestVersionError)?;
+        progress(InstallProgress::Extracting);
 
-        let cursor = Cursor::new(sourcemod_archive_contents);
+        tokio::task::spawn_blocking(move || {
+            archive::extract(&sourcemod_archive_contents, &game_directory, |entry| {
+                is_preserved(&game_directory, entry)
+            })
+        })
+        .await
+        .unwrap()
+    }
+}
 
-        #[cfg(target_os = "linux")]
-        {
-            use crate::core::TarSnafu;
-            use flate2::read::GzDecoder;
+/// The version the latest archive of `branch` is for.
+pub async fn latest_version(
+    branch: &SourcemodBranch,
+    source_version: &SourceEngineVersion,
+) -> Result<Version, Error> {
+    let (_, archive_name) = latest_archive(branch, source_version).await?;
 
-            let tar = GzDecoder::new(cursor);
+    Version::from_archive_name(&archive_name)
+        .ok_or_else(|| Error::ListingParseError { url: drop_url() })
+}
 
-            let mut archive = tar::Archive::new(tar);
+/// Whether an archive entry, relative to the game folder, is kept as it is.
+///
+/// Existing config files aren't overwritten, and the plugins of an existing install are left
+/// alone, so plugins moved in or out of `plugins/disabled` stay where they are.
+fn is_preserved(game_directory: &Path, entry: &Path) -> bool {
+    let sourcemod = Path::new("addons").join("sourcemod");
 
-            archive
-                .unpack(path.to_path_buf().join(format!("{}/", game.arg_name())))
-                .context(TarSnafu)
-                .context(ArchiveExtractionSnafu)?;
-        }
+    if entry.starts_with(sourcemod.join("plugins")) {
+        return game_directory.join(&sourcemod).join("plugins").is_dir();
+    }
 
-        #[cfg(target_os = "windows")]
-        {
-            use crate::core::ZipSnafu;
+    (entry.starts_with(sourcemod.join("configs")) || entry.starts_with(Path::new("cfg")))
+        && game_directory.join(entry).is_file()
+}
 
-            let mut zip = zip::ZipArchive::new(cursor)
-                .context(ZipSnafu)
-                .context(ArchiveExtractionSnafu)?;
+/// The drop listing set through `MANNAGER_SOURCEMOD_DROP_URL`, or [`DEFAULT_DROP_URL`].
+pub fn drop_url() -> String {
+    alliedmods::base_url(DROP_URL_VARIABLE, DEFAULT_DROP_URL)
+}
 
-            zip.extract(path.to_path_buf().join(format!("{}/", game.arg_name())))
-                .context(ZipSnafu)
-                .context(ArchiveExtractionSnafu)?;
-        }
+/// Every build for `source_version` available for this OS, newest first.
+pub async fn builds(source_version: &SourceEngineVersion) -> Result<Vec<Version>, Error> {
+    let drop_url = drop_url();
+
+    let page_contents = archive::fetch_text(&drop_url).await?;
+
+    let mut listings = tokio::task::JoinSet::new();
+
+    for folder in alliedmods::folders(&page_contents, source_version) {
+        let url = format!("{drop_url}/{folder}/");
 
-        Ok(())
+        listings.spawn(async move { archive::fetch_text(&url).await });
     }
-}
 
-// TODO: Maybe switch to ureq? Less deps
-/// Oh God, this is so annoying.
-async fn get_latest_sourcemod_version(
-    branch: &SourcemodBranch,
-    source_version: &SourceEngineVersion,
-) -> Result<String, Error> {
-    let page_contents = reqwest::get(SOURCEMOD_VERSIONS_URL)
-        .await
-        .map_err(|_| Error::UnableToFindLatestVersionError)?
-        .text()
-        .await
-        .map_err(|_| Error::UnableToFindLatestVersionError)?;
+    let mut builds = vec![];
 
-    let html = Html::parse_fragment(&page_contents);
+    // Folders that fail to load are left out, rather than failing the whole list.
+    while let Some(listing) = listings.join_next().await {
+        if let Ok(Ok(page_contents)) = listing {
+            builds.extend(
+                alliedmods::archives(&page_contents, "sourcemod", TARGET_OS)
+                    .into_iter()
+                    .map(|(version, _)| version),
+            );
+        }
+    }
 
-    let a_selector = Selector::parse("a").map_err(|_| Error::UnableToFindLatestVersionError)?;
+    builds.sort_unstable_by(|a, b| b.cmp(a));
+    builds.dedup();
 
-    let mut stable = 0u32;
-    let mut dev = 0u32;
+    Ok(builds)
+}
 
-    for element in html.select(&a_selector).skip(1) {
-        let string = element.inner_html();
+/// The folder of a pinned release, and the name of its archive for this OS.
+async fn pinned_archive(version: &Version) -> Result<(String, String), Error> {
+    let folder = format!("{}.{}", version.major, version.minor);
 
-        let mut split = string.trim_end_matches("/").trim().split(".");
+    let page_contents = archive::fetch_text(&format!("{}/{folder}/", drop_url())).await?;
 
-        let major = match split.next().and_then(|next| next.parse::<u32>().ok()) {
-            Some(1) => SourceEngineVersion::Source1,
-            Some(2) => SourceEngineVersion::Source2,
-            _ => continue,
-        };
+    let (_, archive_name) = alliedmods::archives(&page_contents, "sourcemod", TARGET_OS)
+        .into_iter()
+        .find(|(build, _)| build == version)
+        .ok_or_else(|| Error::UnableToFindVersionError {
+            version: version.to_string(),
+        })?;
 
-        let Some(Ok(minor)) = split.next().map(|s| s.parse::<u32>()) else {
-            continue;
-        };
+    Ok((folder, archive_name))
+}
 
-        if &major != source_version {
-            continue;
-        }
+/// The folder of the latest `branch` release, and the name of its archive for this OS.
+async fn latest_archive(
+    branch: &SourcemodBranch,
+    source_version: &SourceEngineVersion,
+) -> Result<(String, String), Error> {
+    let drop_url = drop_url();
 
-        if minor > stable {
-            stable = dev;
-        }
+    let page_contents = archive::fetch_text(&drop_url).await?;
 
-        if minor > dev {
-            dev = minor;
+    let latest = alliedmods::latest_folders(&page_contents, source_version).ok_or_else(|| {
+        Error::ListingParseError {
+            url: drop_url.clone(),
         }
-    }
+    })?;
 
-    let version: u32 = source_version.clone().into();
+    let folder = match branch {
+        SourcemodBranch::Stable => latest.stable,
+        SourcemodBranch::Dev => latest.dev,
+    };
 
-    match branch {
-        SourcemodBranch::Stable => Ok(format!("{version}.{stable}")),
-        SourcemodBranch::Dev => Ok(format!("{version}.{dev}")),
-    }
+    let latest_archive_name_url = format!("{drop_url}/{folder}/sourcemod-latest-{TARGET_OS}");
+
+    let contents = archive::fetch_text(&latest_archive_name_url).await?;
+
+    let archive_name =
+        alliedmods::latest_archive_name(&contents).ok_or(Error::ListingParseError {
+            url: latest_archive_name_url,
+        })?;
+
+    Ok((folder.to_string(), archive_name))
 }
@@ -0,0 +1,341 @@
+//! SteamCMD, Valve's own command line client, as an [`Installer`].
+//!
+//! Unlike DepotDownloader it can log into an account, for apps that aren't available
+//! anonymously, using the credentials SteamCMD has cached from an earlier interactive login.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use snafu::ResultExt;
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+use super::{
+    Error, IoSnafu, SpawnProcessSnafu, archive,
+    installer::{Backend, DownloadPhase, InstallEvent, InstallOptions, Installer},
+};
+
+#[cfg(target_os = "linux")]
+const STEAMCMD_URL: &str = "https://steamcdn-a.akamaihd.net/client/installer/steamcmd_linux.tar.gz";
+
+#[cfg(target_os = "windows")]
+const STEAMCMD_URL: &str = "https://steamcdn-a.akamaihd.net/client/installer/steamcmd.zip";
+
+#[cfg(target_os = "linux")]
+const EXECUTABLE: &str = "steamcmd.sh";
+
+#[cfg(target_os = "windows")]
+const EXECUTABLE: &str = "steamcmd.exe";
+
+pub struct SteamCmd {
+    executable: PathBuf,
+    pub options: InstallOptions,
+    /// Checks every installed file, downloading again the ones that differ.
+    pub validate: bool,
+}
+
+impl SteamCmd {
+    /// Uses the SteamCMD in `path`, downloading it first if it isn't there.
+    pub async fn new(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let executable = path.join(EXECUTABLE);
+
+        if !executable.try_exists().unwrap_or(false) {
+            // Valve doesn't publish a hash of the bootstrap, which updates itself on first run.
+            let contents = archive::fetch_unverified(STEAMCMD_URL, &|_| {}).await?;
+
+            let path = path.to_path_buf();
+
+            tokio::task::spawn_blocking(move || archive::extract(&contents, &path, |_| false))
+                .await
+                .unwrap()?;
+        }
+
+        Ok(Self {
+            executable,
+            options: InstallOptions::default(),
+            validate: false,
+        })
+    }
+
+    /// The SteamCMD in MANNager's data folder.
+    pub fn default_path() -> Result<PathBuf, Error> {
+        super::data_dir()
+            .map(|data_dir| data_dir.join("steamcmd"))
+            .ok_or(Error::NoDataDirectoryError)
+    }
+
+    fn args(&self, app: u32, path: &Path) -> Vec<OsString> {
+        let options = &self.options;
+
+        // The install folder has to be set before logging in.
+        let mut args: Vec<OsString> = vec!["+force_install_dir".into(), path.into()];
+
+        args.extend([
+            "+login".into(),
+            options.login.as_deref().unwrap_or("anonymous").into(),
+            "+app_update".into(),
+            app.to_string().into(),
+        ]);
+
+        if let Some(beta) = &options.beta {
+            args.extend(["-beta".into(), beta.into()]);
+
+            if let Some(password) = &options.beta_password {
+                args.extend(["-betapassword".into(), password.into()]);
+            }
+        }
+
+        if self.validate {
+            args.push("validate".into());
+        }
+
+        args.push("+quit".into());
+
+        args
+    }
+}
+
+impl Installer for SteamCmd {
+    async fn install(
+        &mut self,
+        app: u32,
+        path: &Path,
+        events: &impl Fn(InstallEvent),
+    ) -> Result<(), Error> {
+        // `app_update` always installs the latest build of the branch.
+        if !self.options.manifests.is_empty() {
+            return Err(Error::UnsupportedInstallOptionError {
+                installer: Backend::SteamCmd.to_string(),
+                option: "pinning manifests".to_string(),
+            });
+        }
+
+        // SteamCMD wants an absolute install folder, it resolves relative ones against itself.
+        let path = std::path::absolute(path).context(IoSnafu)?;
+
+        let mut process = Command::new(&self.executable);
+
+        process
+            .args(self.args(app, &path))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        #[cfg(target_os = "windows")]
+        process.creation_flags(0x08000000);
+
+        let mut process = process.spawn().context(SpawnProcessSnafu)?;
+
+        events(InstallEvent::Depot { id: app });
+
+        let mut output = Output::new(app);
+
+        if let Some(stdout) = process.stdout.take() {
+            let mut lines = BufReader::new(stdout).lines();
+
+            while let Some(line) = lines.next_line().await.context(IoSnafu)? {
+                output.read(&line).into_iter().for_each(events);
+            }
+        }
+
+        let status = process.wait().await.context(IoSnafu)?;
+
+        match output.failure {
+            Some(message) => Err(Error::InstallFailedError { message }),
+            None if !status.success() => Err(Error::InstallFailedError {
+                message: format!("SteamCMD exited with {status}"),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Reads the events out of SteamCMD's output, line by line.
+///
+/// SteamCMD doesn't tell depots apart, so the whole app is reported as a single one.
+#[derive(Debug)]
+struct Output {
+    app: u32,
+    phase: DownloadPhase,
+    /// Why the install failed, if SteamCMD said so.
+    failure: Option<String>,
+}
+
+impl Output {
+    fn new(app: u32) -> Self {
+        Self {
+            app,
+            phase: DownloadPhase::Connecting,
+            failure: None,
+        }
+    }
+
+    fn read(&mut self, line: &str) -> Vec<InstallEvent> {
+        let trimmed = line.trim();
+        let mut events = vec![];
+
+        let mut advance = |events: &mut Vec<InstallEvent>, phase: DownloadPhase| {
+            if phase > self.phase {
+                self.phase = phase.clone();
+                events.push(InstallEvent::Phase(phase));
+            }
+        };
+
+        if let Some((state, progress)) = update_state(trimmed) {
+            match state {
+                "downloading" => {
+                    advance(&mut events, DownloadPhase::Downloading);
+
+                    events.push(InstallEvent::DepotProgress {
+                        id: self.app,
+                        progress,
+                    });
+                }
+                // Validating checks the installed files before downloading anything.
+                "verifying install" => advance(&mut events, DownloadPhase::Verifying),
+                "verifying update" | "committing" => {
+                    advance(&mut events, DownloadPhase::Validating);
+                }
+                _ => advance(&mut events, DownloadPhase::ResolvingDepots),
+            }
+        } else if trimmed.starts_with("Success! App") {
+            advance(&mut events, DownloadPhase::Done);
+
+            events.push(InstallEvent::DepotProgress {
+                id: self.app,
+                progress: 100.0,
+            });
+        } else if let Some(message) = trimmed.strip_prefix("Error! ") {
+            self.failure = Some(message.to_string());
+        } else if trimmed.starts_with("Logging in") && trimmed.contains("FAILED") {
+            self.failure = Some(trimmed.to_string());
+        }
+
+        if !trimmed.is_empty() {
+            events.push(InstallEvent::Log(line.to_string()));
+        }
+
+        events
+    }
+}
+
+/// The state and percentage of a progress line, e.g.
+/// ` Update state (0x61) downloading, progress: 42.17 (1234 / 5678)`.
+fn update_state(trimmed: &str) -> Option<(&str, f32)> {
+    let rest = trimmed.strip_prefix("Update state (")?;
+    let (_, rest) = rest.split_once(") ")?;
+    let (state, rest) = rest.split_once(", progress: ")?;
+    let progress = rest.split_whitespace().next()?.parse().ok()?;
+
+    Some((state, progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_lines_are_read_into_events() {
+        let mut output = Output::new(232250);
+
+        let mut read = |line: &str| {
+            output
+                .read(line)
+                .into_iter()
+                .filter(|event| !matches!(event, InstallEvent::Log(_)))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(read("Logging in user 'anonymous' to Steam Public...OK"), []);
+        assert_eq!(
+            read(" Update state (0x3) reconfiguring, progress: 0.00 (0 / 0)"),
+            [InstallEvent::Phase(DownloadPhase::ResolvingDepots)]
+        );
+        assert_eq!(
+            read(" Update state (0x61) downloading, progress: 42.17 (4217 / 10000)"),
+            [
+                InstallEvent::Phase(DownloadPhase::Downloading),
+                InstallEvent::DepotProgress {
+                    id: 232250,
+                    progress: 42.17
+                },
+            ]
+        );
+        assert_eq!(
+            read(" Update state (0x81) verifying update, progress: 99.00 (9900 / 10000)"),
+            [InstallEvent::Phase(DownloadPhase::Validating)]
+        );
+        assert_eq!(
+            read("Success! App '232250' fully installed."),
+            [
+                InstallEvent::Phase(DownloadPhase::Done),
+                InstallEvent::DepotProgress {
+                    id: 232250,
+                    progress: 100.0
+                },
+            ]
+        );
+        assert_eq!(output.failure, None);
+    }
+
+    #[test]
+    fn failures_are_kept() {
+        let mut output = Output::new(232250);
+
+        output.read("Error! App '232250' state is 0x202 after update job.");
+
+        assert_eq!(
+            output.failure.as_deref(),
+            Some("App '232250' state is 0x202 after update job.")
+        );
+    }
+
+    #[test]
+    fn install_folder_comes_before_login() {
+        let mut steamcmd = SteamCmd {
+            executable: PathBuf::from(EXECUTABLE),
+            options: InstallOptions {
+                beta: Some("prerelease".to_string()),
+                beta_password: Some("hunter2".to_string()),
+                ..Default::default()
+            },
+            validate: true,
+        };
+
+        assert_eq!(
+            steamcmd.args(232250, Path::new("/srv/tf2")),
+            [
+                "+force_install_dir",
+                "/srv/tf2",
+                "+login",
+                "anonymous",
+                "+app_update",
+                "232250",
+                "-beta",
+                "prerelease",
+                "-betapassword",
+                "hunter2",
+                "validate",
+                "+quit"
+            ]
+        );
+
+        steamcmd.options = InstallOptions {
+            login: Some("someone".to_string()),
+            ..Default::default()
+        };
+        steamcmd.validate = false;
+
+        assert_eq!(
+            steamcmd.args(232250, Path::new("/srv/tf2"))[2..4],
+            ["+login", "someone"]
+        );
+    }
+}
@@ -0,0 +1,482 @@
+//! Telling whether a server's files are behind the latest build of its branch, without
+//! downloading anything.
+//!
+//! The installed manifests come from the `appmanifest_<app>.acf` SteamCMD leaves behind, or
+//! from the `.DepotDownloader/depot.config` DepotDownloader keeps, and the latest ones from
+//! the app info mirrored by <https://api.steamcmd.net>. DepotDownloader doesn't keep the
+//! build it installed, so it's recorded next to its config after every install.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use super::{Error, appmanifest, archive, installer::ManifestPin};
+
+pub const DEFAULT_API_URL: &str = "https://api.steamcmd.net/v1/info";
+
+/// Overrides [`DEFAULT_API_URL`], e.g. to use a mirror.
+const API_URL_VARIABLE: &str = "MANNAGER_APP_INFO_URL";
+
+/// A build of an app, as far as it's known.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Build {
+    pub build_id: Option<u32>,
+    /// The manifest of every depot.
+    pub depots: Vec<(u32, u64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdateCheck {
+    /// The build installed, when it was recorded or the depots match the latest one.
+    pub installed: Option<u32>,
+    pub latest: Option<u32>,
+    pub is_outdated: bool,
+}
+
+/// Compares the build of `app` installed in `path` with the latest one of `branch`, the
+/// public one when `None`. Pinned depots are left out, they're meant to stay behind.
+pub async fn check(
+    path: &Path,
+    app: u32,
+    branch: Option<&str>,
+    pins: &[ManifestPin],
+) -> Result<UpdateCheck, Error> {
+    let installed = installed(path, app).await.unwrap_or_default();
+    let latest = latest(app, branch).await?;
+
+    let is_outdated = is_outdated(&installed, &latest, pins).unwrap_or(false);
+
+    // Installs from before the build was recorded are at the latest one if nothing changed.
+    let installed_build = installed.build_id.or_else(|| {
+        (!installed.depots.is_empty() && pins.is_empty() && !is_outdated)
+            .then_some(latest.build_id)
+            .flatten()
+    });
+
+    Ok(UpdateCheck {
+        installed: installed_build,
+        latest: latest.build_id,
+        is_outdated,
+    })
+}
+
+/// The build installed in `path`, by whichever backend installed it.
+pub async fn installed(path: &Path, app: u32) -> Option<Build> {
+    if let Some(manifest) = appmanifest::read(path, app).await {
+        return Some(Build {
+            build_id: Some(manifest.build_id),
+            depots: manifest.depots,
+        });
+    }
+
+    let contents = tokio::fs::read(depot_config_path(path)).await.ok()?;
+
+    let build_id = tokio::fs::read_to_string(build_record_path(path))
+        .await
+        .ok()
+        .and_then(|contents| contents.trim().parse().ok());
+
+    Some(Build {
+        build_id,
+        depots: depot_config(&contents)?,
+    })
+}
+
+pub fn depot_config_path(path: &Path) -> PathBuf {
+    path.join(".DepotDownloader").join("depot.config")
+}
+
+/// Where the build DepotDownloader installed in `path` is recorded.
+pub fn build_record_path(path: &Path) -> PathBuf {
+    path.join(".DepotDownloader").join("buildid")
+}
+
+/// Records the build DepotDownloader just installed in `path`. It's the latest one of
+/// `branch` when every depot is at its latest manifest, and unknown otherwise, e.g. with
+/// pinned depots.
+pub async fn record_build(path: &Path, app: u32, branch: Option<&str>) {
+    let build_id = async {
+        let installed = installed(path, app).await?;
+        let latest = latest(app, branch).await.ok()?;
+
+        installed_build(&installed, &latest)
+    }
+    .await;
+
+    let record = build_record_path(path);
+
+    let _ = match build_id {
+        Some(build_id) => tokio::fs::write(record, build_id.to_string()).await,
+        None => tokio::fs::remove_file(record).await,
+    };
+}
+
+/// The build of `latest`, if the depots of `installed` are all at it.
+fn installed_build(installed: &Build, latest: &Build) -> Option<u32> {
+    let is_latest = !installed.depots.is_empty()
+        && installed
+            .depots
+            .iter()
+            .all(|depot| latest.depots.contains(depot));
+
+    is_latest.then_some(latest.build_id).flatten()
+}
+
+/// The latest build of `branch`, the public one when `None`.
+pub async fn latest(app: u32, branch: Option<&str>) -> Result<Build, Error> {
+    let url = format!("{}/{app}", api_url());
+
+    let contents = archive::fetch_text(&url).await?;
+
+    parse_app_info(&contents, app, branch.unwrap_or("public"))
+        .ok_or(Error::AppInfoParseError { app })
+}
+
+fn api_url() -> String {
+    std::env::var(API_URL_VARIABLE)
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .map(|url| url.trim().trim_end_matches('/').to_string())
+        .unwrap_or_else(|| DEFAULT_API_URL.to_string())
+}
+
+/// Whether `installed` is behind `latest`, or `None` when there's nothing to compare.
+///
+/// Depots are compared when both sides list them, since a branch may get new depots without
+/// the installed ones changing. The build IDs are compared otherwise.
+pub fn is_outdated(installed: &Build, latest: &Build, pins: &[ManifestPin]) -> Option<bool> {
+    let mut compared = installed
+        .depots
+        .iter()
+        .filter(|(depot, _)| !pins.iter().any(|pin| pin.depot == *depot))
+        .filter_map(|(depot, manifest)| {
+            let (_, latest) = latest.depots.iter().find(|(id, _)| id == depot)?;
+
+            Some(manifest != latest)
+        })
+        .peekable();
+
+    if compared.peek().is_some() {
+        return Some(compared.any(|is_changed| is_changed));
+    }
+
+    if !pins.is_empty() {
+        return None;
+    }
+
+    installed
+        .build_id
+        .zip(latest.build_id)
+        .map(|(installed, latest)| installed != latest)
+}
+
+/// Reads the depots and build of `branch` out of an `api.steamcmd.net` response, e.g.
+///
+/// ```json
+/// { "data": { "232250": { "depots": {
+///     "232256": { "manifests": { "public": { "gid": "3459476342547125403" } } },
+///     "branches": { "public": { "buildid": "14783012" } }
+/// } } }, "status": "success" }
+/// ```
+pub fn parse_app_info(contents: &str, app: u32, branch: &str) -> Option<Build> {
+    let info: Value = serde_json::from_str(contents).ok()?;
+
+    let depots = info
+        .get("data")?
+        .get(app.to_string())?
+        .get("depots")?
+        .as_object()?;
+
+    let build_id = depots
+        .get("branches")
+        .and_then(|branches| branches.get(branch))
+        .and_then(|branch| branch.get("buildid"))
+        .and_then(number);
+
+    let manifests = depots
+        .iter()
+        .filter_map(|(depot, info)| {
+            let manifest = info.get("manifests")?.get(branch)?;
+
+            // Older responses have the manifest ID right there instead of an object.
+            let manifest = manifest.get("gid").unwrap_or(manifest);
+
+            Some((depot.parse().ok()?, number(manifest)?))
+        })
+        .collect::<Vec<_>>();
+
+    (build_id.is_some() || !manifests.is_empty()).then_some(Build {
+        build_id,
+        depots: manifests,
+    })
+}
+
+/// IDs are usually strings in app info, they can be larger than what JSON numbers hold.
+fn number<T: std::str::FromStr>(value: &Value) -> Option<T> {
+    match value {
+        Value::String(string) => string.parse().ok(),
+        Value::Number(number) => number.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Reads the manifests DepotDownloader recorded. The file is a deflated protobuf message
+/// with a single `map<uint32, uint64>` field, from depot to manifest.
+pub fn depot_config(contents: &[u8]) -> Option<Vec<(u32, u64)>> {
+    let mut message = vec![];
+
+    flate2::read::DeflateDecoder::new(contents)
+        .read_to_end(&mut message)
+        .ok()?;
+
+    let mut depots = vec![];
+    let mut rest = message.as_slice();
+
+    while !rest.is_empty() {
+        let (field, wire_type) = key(&mut rest)?;
+
+        match (field, wire_type) {
+            (1, LENGTH_DELIMITED) => {
+                let length = varint(&mut rest)?.try_into().ok()?;
+                let entry = rest.get(..length)?;
+
+                rest = &rest[length..];
+
+                if let Some(depot) = map_entry(entry) {
+                    depots.push(depot);
+                }
+            }
+            (_, wire_type) => skip(&mut rest, wire_type)?,
+        }
+    }
+
+    Some(depots)
+}
+
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const LENGTH_DELIMITED: u64 = 2;
+const FIXED32: u64 = 5;
+
+fn map_entry(mut entry: &[u8]) -> Option<(u32, u64)> {
+    let mut depot = None;
+    let mut manifest = None;
+
+    while !entry.is_empty() {
+        match key(&mut entry)? {
+            (1, VARINT) => depot = varint(&mut entry)?.try_into().ok(),
+            (2, VARINT) => manifest = Some(varint(&mut entry)?),
+            (_, wire_type) => skip(&mut entry, wire_type)?,
+        }
+    }
+
+    depot.zip(manifest)
+}
+
+fn key(bytes: &mut &[u8]) -> Option<(u64, u64)> {
+    let key = varint(bytes)?;
+
+    Some((key >> 3, key & 0b111))
+}
+
+fn varint(bytes: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+
+        *bytes = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+fn skip(bytes: &mut &[u8], wire_type: u64) -> Option<()> {
+    let length = match wire_type {
+        VARINT => return varint(bytes).map(|_| ()),
+        FIXED64 => 8,
+        LENGTH_DELIMITED => varint(bytes)?.try_into().ok()?,
+        FIXED32 => 4,
+        _ => return None,
+    };
+
+    *bytes = bytes.get(length..)?;
+
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_INFO: &str = r#"{
+        "data": {
+            "232250": {
+                "appid": "232250",
+                "depots": {
+                    "232251": {
+                        "config": { "oslist": "windows" },
+                        "manifests": {
+                            "public": { "gid": "5914812390432418474", "size": "12", "download": "6" }
+                        }
+                    },
+                    "232256": {
+                        "config": { "oslist": "linux" },
+                        "manifests": {
+                            "public": { "gid": "3459476342547125403", "size": "12", "download": "6" },
+                            "prerelease": { "gid": "7221083360291728164", "size": "12", "download": "6" }
+                        }
+                    },
+                    "baselanguages": "english",
+                    "branches": {
+                        "public": { "buildid": "14783012", "timeupdated": "1718232123" },
+                        "prerelease": { "buildid": "14790333", "timeupdated": "1718332123" }
+                    }
+                }
+            }
+        },
+        "status": "success"
+    }"#;
+
+    #[test]
+    fn app_info_is_read_per_branch() {
+        assert_eq!(
+            parse_app_info(APP_INFO, 232250, "public"),
+            Some(Build {
+                build_id: Some(14783012),
+                depots: vec![(232251, 5914812390432418474), (232256, 3459476342547125403)],
+            })
+        );
+        assert_eq!(
+            parse_app_info(APP_INFO, 232250, "prerelease"),
+            Some(Build {
+                build_id: Some(14790333),
+                depots: vec![(232256, 7221083360291728164)],
+            })
+        );
+        assert_eq!(parse_app_info(APP_INFO, 232250, "missing"), None);
+        assert_eq!(parse_app_info(APP_INFO, 740, "public"), None);
+        assert_eq!(parse_app_info("not json", 232250, "public"), None);
+    }
+
+    #[test]
+    fn depots_are_compared_before_builds() {
+        let latest = Build {
+            build_id: Some(2),
+            depots: vec![(232256, 20), (232251, 30)],
+        };
+
+        let installed = |build_id, depots: &[(u32, u64)]| Build {
+            build_id,
+            depots: depots.to_vec(),
+        };
+
+        assert_eq!(
+            is_outdated(&installed(None, &[(232256, 20)]), &latest, &[]),
+            Some(false)
+        );
+        assert_eq!(
+            is_outdated(&installed(None, &[(232256, 10)]), &latest, &[]),
+            Some(true)
+        );
+        assert_eq!(
+            is_outdated(&installed(Some(1), &[]), &latest, &[]),
+            Some(true)
+        );
+        assert_eq!(is_outdated(&installed(None, &[]), &latest, &[]), None);
+    }
+
+    #[test]
+    fn pinned_depots_are_never_outdated() {
+        let latest = Build {
+            build_id: Some(2),
+            depots: vec![(232256, 20)],
+        };
+
+        let installed = Build {
+            build_id: None,
+            depots: vec![(232256, 10)],
+        };
+
+        let pins = [ManifestPin {
+            depot: 232256,
+            manifest: 10,
+        }];
+
+        assert_eq!(is_outdated(&installed, &latest, &pins), None);
+    }
+
+    #[test]
+    fn builds_are_recorded_only_when_every_depot_is_at_it() {
+        let latest = Build {
+            build_id: Some(2),
+            depots: vec![(232256, 20), (232251, 30)],
+        };
+
+        let installed = |depots: &[(u32, u64)]| Build {
+            build_id: None,
+            depots: depots.to_vec(),
+        };
+
+        assert_eq!(
+            installed_build(&installed(&[(232256, 20), (232251, 30)]), &latest),
+            Some(2)
+        );
+        assert_eq!(
+            installed_build(&installed(&[(232256, 20)]), &latest),
+            Some(2)
+        );
+        assert_eq!(
+            installed_build(&installed(&[(232256, 10), (232251, 30)]), &latest),
+            None
+        );
+        assert_eq!(installed_build(&installed(&[]), &latest), None);
+    }
+
+    #[test]
+    fn depot_configs_are_read() {
+        use std::io::Write;
+
+        // { 1: { 1: 232256, 2: 3459476342547125403 }, 1: { 1: 232251, 2: 42 } }
+        let mut message = vec![];
+
+        for (depot, manifest) in [(232256u64, 3459476342547125403u64), (232251, 42)] {
+            let mut entry = vec![0x08];
+            encode_varint(&mut entry, depot);
+            entry.push(0x10);
+            encode_varint(&mut entry, manifest);
+
+            message.push(0x0a);
+            encode_varint(&mut message, entry.len() as u64);
+            message.extend(entry);
+        }
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&message).unwrap();
+        let contents = encoder.finish().unwrap();
+
+        assert_eq!(
+            depot_config(&contents),
+            Some(vec![(232256, 3459476342547125403), (232251, 42)])
+        );
+        assert_eq!(depot_config(b"not deflate"), None);
+    }
+
+    fn encode_varint(bytes: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            bytes.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+
+        bytes.push(value as u8);
+    }
+}
@@ -0,0 +1,142 @@
+//! Steam Workshop maps and collections hosted by a server.
+
+/// How a game's dedicated server gets its maps from the Steam Workshop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workshop {
+    /// The server downloads a whole collection with `+host_workshop_collection`, or a single
+    /// map with `+host_workshop_map`. `start_map_arg` picks the collection map it starts on.
+    Hosted { start_map_arg: &'static str },
+    /// Maps are loaded as `workshop/<id>` and kept up to date with `tf_workshop_map_sync`.
+    /// Collections aren't supported.
+    Synced,
+}
+
+impl Workshop {
+    pub fn supports_collections(&self) -> bool {
+        matches!(self, Workshop::Hosted { .. })
+    }
+}
+
+/// The Workshop items configured for a server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WorkshopItems {
+    pub collection: Option<u64>,
+    pub maps: Vec<u64>,
+}
+
+impl WorkshopItems {
+    pub fn is_empty(&self) -> bool {
+        self.collection.is_none() && self.maps.is_empty()
+    }
+
+    /// The launch arguments needed for `workshop`.
+    pub fn args(&self, workshop: Workshop) -> Vec<String> {
+        let mut args = vec![];
+
+        match workshop {
+            Workshop::Hosted { start_map_arg } => {
+                if let Some(collection) = self.collection {
+                    args.extend([
+                        "+host_workshop_collection".to_string(),
+                        collection.to_string(),
+                    ]);
+
+                    if let Some(map) = self.maps.first() {
+                        args.extend([start_map_arg.to_string(), map.to_string()]);
+                    }
+                } else if let Some(map) = self.maps.first() {
+                    // Without a collection the server can only host a single map.
+                    args.extend(["+host_workshop_map".to_string(), map.to_string()]);
+                }
+            }
+            Workshop::Synced => {
+                for map in &self.maps {
+                    args.extend(["+tf_workshop_map_sync".to_string(), map.to_string()]);
+                }
+            }
+        }
+
+        args
+    }
+}
+
+/// Reads a Workshop ID, either as is or from a link to its Workshop page.
+pub fn parse_id(input: &str) -> Option<u64> {
+    let input = input.trim();
+
+    if let Ok(id) = input.parse() {
+        return Some(id);
+    }
+
+    let (_, query) = input.split_once('?')?;
+
+    query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("id="))
+        .and_then(|id| id.parse().ok())
+}
+
+/// The Workshop page of an item.
+pub fn item_url(id: u64) -> String {
+    format!("https://steamcommunity.com/sharedfiles/filedetails/?id={id}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_are_read_from_links() {
+        assert_eq!(parse_id(" 454811406 "), Some(454811406));
+        assert_eq!(parse_id(&item_url(454811406)), Some(454811406));
+        assert_eq!(
+            parse_id("https://steamcommunity.com/workshop/filedetails/?l=english&id=123"),
+            Some(123)
+        );
+        assert_eq!(parse_id("ctf_2fort"), None);
+        assert_eq!(parse_id("https://steamcommunity.com/?id=abc"), None);
+    }
+
+    #[test]
+    fn collections_start_on_the_first_map() {
+        let items = WorkshopItems {
+            collection: Some(1),
+            maps: vec![2, 3],
+        };
+
+        assert_eq!(
+            items.args(Workshop::Hosted {
+                start_map_arg: "+workshop_start_map"
+            }),
+            ["+host_workshop_collection", "1", "+workshop_start_map", "2"]
+        );
+    }
+
+    #[test]
+    fn a_single_map_is_hosted_without_a_collection() {
+        let items = WorkshopItems {
+            collection: None,
+            maps: vec![2, 3],
+        };
+
+        assert_eq!(
+            items.args(Workshop::Hosted {
+                start_map_arg: "+host_workshop_map"
+            }),
+            ["+host_workshop_map", "2"]
+        );
+    }
+
+    #[test]
+    fn every_map_is_synced() {
+        let items = WorkshopItems {
+            collection: Some(1),
+            maps: vec![2, 3],
+        };
+
+        assert_eq!(
+            items.args(Workshop::Synced),
+            ["+tf_workshop_map_sync", "2", "+tf_workshop_map_sync", "3"]
+        );
+    }
+}
@@ -8,6 +8,7 @@ use iced::window;
 use ui::State;
 use velopack::VelopackApp;
 
+pub mod cli;
 pub mod core;
 pub mod icon;
 pub mod ui;
@@ -26,6 +27,12 @@ const ROBOMONO_FONT_BYTES: &[u8] = include_bytes!("../fonts/robomono.ttf");
 fn main() -> iced::Result {
     VelopackApp::build().run();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if !args.is_empty() {
+        std::process::exit(cli::run(args));
+    }
+
     let window_settings = iced::window::Settings {
         #[cfg(target_os = "linux")]
         platform_specific: iced::window::settings::PlatformSpecific {
@@ -39,6 +46,7 @@ fn main() -> iced::Result {
 
     iced::application(State::new, State::update, State::view)
         .title(State::title)
+        .subscription(State::subscription)
         .window(window_settings)
         .window_size(Size::new(900.0, 900.0))
         .centered()
@@ -1,27 +1,34 @@
-use std::{net::Ipv4Addr, sync::Arc, time::Duration};
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
 use iced::{
-    Function, Task,
-    futures::{self, FutureExt},
+    Function, Subscription, Task,
+    futures::{self, FutureExt, SinkExt},
     widget::{markdown, operation::snap_to_end},
 };
 use screen::{
-    Screen,
-    serverboot::{self, Console, ServerCommunicationTwoWay, ServerTerminal, find_available_port},
-    servercreation,
+    Screen, pluginbrowser,
+    serverboot::{
+        self, Connection, Console, DEFAULT_PORT, ServerCommunicationTwoWay, ServerTerminal,
+        TextType, find_available_port,
+    },
+    serverconfig, servercreation,
     serverlist::{self, ServerList},
+    servermaps, serverplugins,
 };
 
 use crate::{
-    core::{Game, SourceEngineVersion, portforwarder},
+    core::{self, a2s, maps, portforwarder, process},
     ui::{
         components::notification::notification,
-        games::SOURCE_GAMES,
         screen::{
-            servercreation::{DownloadUpdate, download_server},
+            servercreation::{DownloadPhase, DownloadUpdate, RepairReport, download_server},
             serverlist::{create_config_file_path, get_config_path},
         },
-        server::{Server, Servers},
+        server::{InstallStatus, RestartPolicy, RuntimeState, Server, Servers, runtime_state_path},
         themes::{Theme, tf2},
     },
     update::{check_for_updates, update_app, update_dialog},
@@ -37,6 +44,11 @@ pub mod themes;
 
 pub type Element<'a, Message> = iced::Element<'a, Message, Theme>;
 
+const QUERY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the servers are compared with the latest builds of their branches.
+const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+
 pub struct State {
     screen: Screen,
     servers: Servers,
@@ -58,8 +70,19 @@ pub enum Message {
         usize,
         Result<Arc<portforwarder::PortForwarder>, portforwarder::Error>,
     ),
+    QueryServers,
+    CheckServerUpdates,
+    ServerQueried(usize, Result<a2s::Status, a2s::Error>),
+    ServerKilled(usize, Result<(), core::Error>),
+    RestartServer(usize),
+    ForceStopServer(usize),
     ServerList(serverlist::Message),
     ServerCreation(servercreation::Message),
+    ServerConfig(serverconfig::Message),
+    ServerMaps(servermaps::Message),
+    ServerPlugins(serverplugins::Message),
+    PluginBrowser(pluginbrowser::Message),
+    MapChecked(usize, bool),
     ServerTerminal(usize, serverboot::Message),
     CheckForUpdate(
         Arc<
@@ -95,7 +118,15 @@ impl State {
                             Err(_) => create_config_file_path().await,
                         }
                     })
-                    .and_then(|path| async move { Servers::fetch(path.as_path()).await })
+                    .and_then(|path| async move {
+                        let mut servers = Servers::fetch(path.as_path()).await?;
+
+                        let runtime_state = RuntimeState::load(&runtime_state_path(&path)).await;
+
+                        servers.restore(&runtime_state);
+
+                        Ok(servers)
+                    })
                     .await
             },
             Message::ServersLoaded,
@@ -122,6 +153,10 @@ impl State {
         match self.screen {
             Screen::Loading | Screen::ServerList => "MANNager".into(),
             Screen::ServerCreation(_) => "MANNager - Creating a server".into(),
+            Screen::ServerConfig(_) => "MANNager - Editing server.cfg".into(),
+            Screen::ServerMaps(_) => "MANNager - Maps".into(),
+            Screen::ServerPlugins(_) => "MANNager - Plugins".into(),
+            Screen::PluginBrowser(_) => "MANNager - Browsing plugins".into(),
             Screen::ServerTerminal(id) => self
                 .servers
                 .get(id)
@@ -137,34 +172,113 @@ impl State {
 
                 self.screen = Screen::ServerList;
 
-                Task::none()
+                Task::batch(self.servers.iter().enumerate().flat_map(|(id, server)| {
+                    [
+                        serverlist::check_addons(id, server),
+                        serverlist::check_build(id, server),
+                        serverlist::check_updates(id, server),
+                    ]
+                }))
+                .map(Message::ServerList)
             }
             Message::UpdateServer(id, update) => {
-                let Some(Server {
-                    update_depot_status,
-                    update_phase,
-                    ..
-                }) = self.servers.get_mut(id)
-                else {
+                let Some(server) = self.servers.get_mut(id) else {
                     return Task::none();
                 };
 
                 match update {
                     Update::Downloading(update) => {
-                        *update_depot_status = update.depots;
-                        *update_phase = Some(update.phase);
-                    }
-                    Update::Finished(_) => {
-                        update_depot_status.clear();
-                        *update_phase = None;
+                        server.update_depot_status = update.depots;
+                        server.update_phase = Some(update.phase);
+
+                        if server.is_validating {
+                            server.repair_report = Some(update.repairs);
+                        }
+
+                        Task::none()
                     }
-                };
+                    Update::Finished(result) => {
+                        server.update_depot_status.clear();
+                        server.update_phase = None;
+                        server.update_handle = None;
 
-                Task::none()
+                        let was_validating = std::mem::take(&mut server.is_validating);
+
+                        // A resumed download that went through leaves the server complete.
+                        let is_resumed = result.is_ok() && server.is_incomplete();
+
+                        if is_resumed {
+                            server.info.install_status = InstallStatus::Installed;
+                        }
+
+                        let failure = match result {
+                            Ok(()) => {
+                                if was_validating && server.repair_report.is_none() {
+                                    server.repair_report = Some(RepairReport::default());
+                                }
+
+                                Task::none()
+                            }
+                            Err(err) => {
+                                // A validation that failed halfway didn't check everything.
+                                if was_validating {
+                                    server.repair_report = None;
+                                }
+
+                                let body =
+                                    format!("'{}' couldn't be updated: {err}", server.info.name);
+
+                                Task::future(notification("MANNager", body, Duration::from_secs(5)))
+                                    .discard()
+                            }
+                        };
+
+                        let checks = Task::batch([
+                            serverlist::check_build(id, server),
+                            serverlist::check_updates(id, server),
+                        ])
+                        .map(Message::ServerList);
+
+                        let save = if is_resumed {
+                            self.save_servers()
+                        } else {
+                            Task::none()
+                        };
+
+                        Task::batch([failure, checks, save])
+                    }
+                }
             }
             Message::ServerCommunication(id, msg) => {
-                let Ok(communication) = msg else {
-                    return Task::none();
+                let communication = match msg {
+                    Ok(communication) => communication,
+                    Err(err) => {
+                        let Some(Server { info, console, .. }) = self.servers.get_mut(id) else {
+                            return Task::none();
+                        };
+
+                        // An RCON session or a log that failed has nothing left to show.
+                        if console
+                            .as_ref()
+                            .is_some_and(|console| console.connection != Connection::Process)
+                        {
+                            *console = None;
+
+                            if matches!(self.screen, Screen::ServerTerminal(terminal_id) if terminal_id == id)
+                            {
+                                self.screen = Screen::ServerList;
+                            }
+                        }
+
+                        let body = format!("'{}': {err}", info.name);
+
+                        return Task::future(notification(
+                            "MANNager",
+                            body,
+                            Duration::from_secs(5),
+                        ))
+                        .discard();
+                    }
                 };
 
                 let Some(Server {
@@ -181,6 +295,25 @@ impl State {
 
                         Task::none()
                     }
+                    ServerCommunicationTwoWay::Started(pid, started) => {
+                        console.pid = Some(pid);
+                        console.started = started;
+
+                        self.save_runtime_state()
+                    }
+                    ServerCommunicationTwoWay::Exited(exit_code) => {
+                        console.exited = Some(exit_code);
+                        console.pid = None;
+                        console.started = None;
+                        console.sender = None;
+
+                        console.output.push(TextType::Output(match exit_code {
+                            Some(code) => format!("The server exited with code {code}"),
+                            None => "The server was killed".to_string(),
+                        }));
+
+                        self.server_exited(id, exit_code)
+                    }
                     ServerCommunicationTwoWay::Output(text) => {
                         console.output.push(text);
 
@@ -220,20 +353,42 @@ impl State {
 
                         Task::none()
                     }
-                    Action::UpdateServer(id) => {
-                        let Some(Server { info, .. }) = self.servers.get_mut(id) else {
+                    action @ (Action::UpdateServer(id) | Action::ValidateServer(id)) => {
+                        let validate = matches!(action, Action::ValidateServer(_));
+
+                        let Some(server) = self.servers.get_mut(id) else {
                             return Task::none();
                         };
 
-                        let server_path = info.path.clone();
-                        let source_game = info.game.clone();
+                        // Automatic updates may come in while one is already going.
+                        if server.update_phase.is_some() {
+                            return Task::none();
+                        }
+
+                        server.update_phase = Some(DownloadPhase::default());
+                        server.is_validating = validate;
+
+                        if validate {
+                            server.repair_report = None;
+                        }
+
+                        let info = &server.info;
 
-                        Task::sip(
-                            download_server(server_path, source_game),
+                        let (update, handle) = Task::sip(
+                            download_server(
+                                info.path.clone(),
+                                info.game,
+                                info.install.clone(),
+                                validate,
+                            ),
                             Update::Downloading,
                             Update::Finished,
                         )
-                        .map(Message::UpdateServer.with(id))
+                        .abortable();
+
+                        server.update_handle = Some(handle.abort_on_drop());
+
+                        update.map(Message::UpdateServer.with(id))
                     }
                     Action::EditServer(id) => {
                         let Some(server) = self.servers.get_mut(id) else {
@@ -242,7 +397,22 @@ impl State {
 
                         server.is_editing = true;
 
-                        Task::none()
+                        if server.sourcemod_builds.is_empty() {
+                            serverlist::fetch_addon_builds(id, server).map(Message::ServerList)
+                        } else {
+                            Task::none()
+                        }
+                    }
+                    Action::EditConfig(id) => {
+                        let Some(server) = self.servers.get(id) else {
+                            return Task::none();
+                        };
+
+                        let (config, task) = serverconfig::State::new(server);
+
+                        self.screen = Screen::ServerConfig(config);
+
+                        task.map(Message::ServerConfig)
                     }
                     Action::StopEditServer(id) => {
                         let Some(server) = self.servers.get_mut(id) else {
@@ -268,104 +438,113 @@ impl State {
                         .discard()
                     }
                     Action::RunServer(id) => {
-                        let Some(Server {
-                            info,
-                            console,
-                            hosting_mode,
-                            ..
-                        }) = self.servers.get_mut(id)
-                        else {
+                        let Some(server) = self.servers.get(id) else {
                             return Task::none();
                         };
 
-                        let Some(game_info) = SOURCE_GAMES
-                            .iter()
-                            .find(|game_info| game_info.game == info.game)
-                        else {
+                        Task::perform(
+                            maps::exists(server.info.game_directory(), server.info.map.clone()),
+                            move |exists| Message::MapChecked(id, exists),
+                        )
+                    }
+                    Action::ManageMaps(id) => {
+                        let Some(server) = self.servers.get(id) else {
                             return Task::none();
                         };
 
-                        let binary_path = {
-                            let server_path = &info.path;
-                            let executable_path = &game_info.executable_path;
+                        let (state, task) = servermaps::State::new(id, server);
 
-                            server_path.join(executable_path)
-                        };
-
-                        let port = info
-                            .port
-                            .unwrap_or_else(|| find_available_port(Ipv4Addr::UNSPECIFIED));
+                        self.screen = Screen::ServerMaps(state);
 
-                        let args = {
-                            let mut args = match game_info.engine {
-                                SourceEngineVersion::Source1 => {
-                                    format!("-console -game {}", &info.game.arg_name())
-                                }
-                                SourceEngineVersion::Source2 => "-dedicated".to_string(),
-                            };
-
-                            args.push_str(&format!(
-                                " +hostname \"{name}\" +map {map} +maxplayers {max} \
-                                  -nohltv +ip 0.0.0.0 -strictportbind -port {port}",
-                                name = info.name,
-                                map = info.map,
-                                max = info.max_players,
-                                port = port,
-                            ));
-
-                            if info.max_players > 32 && info.game == Game::TeamFortress2 {
-                                args.push_str(" -unrestricted_maxplayers");
-                            }
+                        task.map(Message::ServerMaps)
+                    }
+                    Action::ManagePlugins(id) => {
+                        let Some(server) = self.servers.get(id) else {
+                            return Task::none();
+                        };
 
-                            if let Some(token) = &info.gslt {
-                                args.push_str(&format!(" +sv_setsteamaccount {token}"));
-                            }
+                        let (state, task) = serverplugins::State::new(id, server);
 
-                            if matches!(hosting_mode, server::HostingMode::Sdr) {
-                                args.push_str(" -enablefakeip")
-                            }
+                        self.screen = Screen::ServerPlugins(state);
 
-                            args
+                        task.map(Message::ServerPlugins)
+                    }
+                    Action::OpenTerminal(id) => {
+                        let Some(server) = self.servers.get_mut(id) else {
+                            return Task::none();
                         };
 
-                        self.screen = Screen::ServerTerminal(id);
+                        if server.console.is_some() {
+                            self.screen = Screen::ServerTerminal(id);
 
-                        let (server_stream, handle) = Task::run(
-                            Console::start(binary_path, args),
-                            Message::ServerCommunication.with(id),
-                        )
-                        .abortable();
+                            return Task::none();
+                        }
 
-                        let name = info.name.clone();
+                        let Some(running) = &server.detached else {
+                            return Task::none();
+                        };
 
-                        let port_forward_task = match hosting_mode {
-                            server::HostingMode::Upnp => Task::perform(
-                                async move { Console::port_forward(name, port).await },
-                                move |res| Message::PortForward(id, res.map(|pf| Arc::new(pf))),
+                        let port = running.port;
+
+                        // Prefer RCON, since the log can't take commands.
+                        let (stream, connection) = match server.info.rcon_password.clone() {
+                            Some(password) => (
+                                Task::run(
+                                    Console::attach_rcon(
+                                        SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+                                        password,
+                                    ),
+                                    Message::ServerCommunication.with(id),
+                                ),
+                                Connection::Rcon,
+                            ),
+                            None => (
+                                Task::run(
+                                    Console::tail_log(server.console_log_path()),
+                                    Message::ServerCommunication.with(id),
+                                ),
+                                Connection::Log,
                             ),
-                            _ => Task::none(),
                         };
 
-                        *console = Some(Console::from_handle(handle, port));
+                        let (stream, handle) = stream.abortable();
+
+                        server.console =
+                            Some(Console::from_handle(handle, port).with_connection(connection));
 
-                        Task::batch([server_stream, port_forward_task])
-                    }
-                    Action::OpenTerminal(id) => {
                         self.screen = Screen::ServerTerminal(id);
 
-                        Task::none()
+                        stream
                     }
-                    Action::StopServer(id) => {
-                        let Some(Server { console, .. }) = self.servers.get_mut(id) else {
+                    Action::AttachRcon(id) => {
+                        let Some(Server { info, console, .. }) = self.servers.get_mut(id) else {
                             return Task::none();
                         };
 
-                        *console = None;
+                        let Some(password) = info.rcon_password.clone() else {
+                            return Task::none();
+                        };
 
-                        self.screen = Screen::ServerList;
+                        let port = info.port.unwrap_or(DEFAULT_PORT);
 
-                        Task::none()
+                        let (rcon_stream, handle) = Task::run(
+                            Console::attach_rcon(
+                                SocketAddr::from((Ipv4Addr::LOCALHOST, port)),
+                                password,
+                            ),
+                            Message::ServerCommunication.with(id),
+                        )
+                        .abortable();
+
+                        *console = Some(
+                            Console::from_handle(handle, port).with_connection(Connection::Rcon),
+                        );
+
+                        self.screen = Screen::ServerTerminal(id);
+
+                        rcon_stream
                     }
+                    Action::StopServer(id) => self.stop_server(id),
                     Action::Run(task) => task.map(Message::ServerList),
                 }
             }
@@ -379,33 +558,163 @@ impl State {
                 match creation.update(msg) {
                     Action::None => Task::none(),
                     Action::SwitchToServerList => {
+                        // Leaving drops the download, which kills the installer.
                         self.screen = Screen::ServerList;
 
                         Task::none()
                     }
-                    Action::ServerCreated(server) => {
-                        self.servers.push(Server::with_info(server));
+                    Action::ListServer(info, task) => {
+                        match creation.listed().and_then(|id| self.servers.get_mut(id)) {
+                            Some(server) => server.info = info,
+                            None => {
+                                self.servers.push(Server::with_info(info));
 
-                        self.screen = Screen::ServerList;
+                                creation.listed_as(self.servers.len() - 1);
+                            }
+                        }
 
-                        let servers = self.servers.clone();
+                        Task::batch([self.save_servers(), task.map(Message::ServerCreation)])
+                    }
+                    Action::ServerCreated(info) => {
+                        match creation.listed().and_then(|id| self.servers.get_mut(id)) {
+                            Some(server) => server.info = info,
+                            None => self.servers.push(Server::with_info(info)),
+                        }
 
-                        Task::future(async {
-                            futures::future::ready(get_config_path())
-                                .then(|res| async move {
-                                    match res {
-                                        Ok(path) => Ok(path),
-                                        Err(_) => create_config_file_path().await,
-                                    }
-                                })
-                                .and_then(|path| async move { servers.save(&path).await })
-                                .await
-                        })
-                        .discard()
+                        self.screen = Screen::ServerList;
+
+                        self.save_servers()
                     }
                     Action::Run(task) => task.map(Message::ServerCreation),
                 }
             }
+            Message::ServerConfig(msg) => {
+                use serverconfig::Action;
+
+                let Screen::ServerConfig(config) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                match config.update(msg) {
+                    Action::None => Task::none(),
+                    Action::GoBack => {
+                        self.screen = Screen::ServerList;
+
+                        Task::none()
+                    }
+                    Action::Run(task) => task.map(Message::ServerConfig),
+                }
+            }
+            Message::ServerMaps(msg) => {
+                use servermaps::Action;
+
+                let Screen::ServerMaps(maps) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                let id = maps.id;
+
+                match maps.update(msg) {
+                    Action::None => Task::none(),
+                    Action::GoBack => {
+                        self.screen = Screen::ServerList;
+
+                        Task::none()
+                    }
+                    Action::ChangeStartupMap(map) => {
+                        let Some(server) = self.servers.get_mut(id) else {
+                            return Task::none();
+                        };
+
+                        server.info.map = map;
+
+                        self.save_servers()
+                    }
+                    Action::Run(task) => task.map(Message::ServerMaps),
+                }
+            }
+            Message::ServerPlugins(msg) => {
+                use serverplugins::Action;
+
+                let Screen::ServerPlugins(plugins) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                let id = plugins.id;
+
+                match plugins.update(msg) {
+                    Action::None => Task::none(),
+                    Action::GoBack => {
+                        self.screen = Screen::ServerList;
+
+                        Task::none()
+                    }
+                    Action::Browse => {
+                        let Some(server) = self.servers.get(id) else {
+                            return Task::none();
+                        };
+
+                        let (state, task) = pluginbrowser::State::new(id, server);
+
+                        self.screen = Screen::PluginBrowser(state);
+
+                        task.map(Message::PluginBrowser)
+                    }
+                    Action::Run(task) => task.map(Message::ServerPlugins),
+                }
+            }
+            Message::PluginBrowser(msg) => {
+                use pluginbrowser::Action;
+
+                let Screen::PluginBrowser(browser) = &mut self.screen else {
+                    return Task::none();
+                };
+
+                let id = browser.id;
+
+                match browser.update(msg) {
+                    Action::None => Task::none(),
+                    Action::GoBack => {
+                        let Some(server) = self.servers.get(id) else {
+                            self.screen = Screen::ServerList;
+
+                            return Task::none();
+                        };
+
+                        let (state, task) = serverplugins::State::new(id, server);
+
+                        self.screen = Screen::ServerPlugins(state);
+
+                        task.map(Message::ServerPlugins)
+                    }
+                    Action::Run(task) => task.map(Message::PluginBrowser),
+                }
+            }
+            Message::MapChecked(id, exists) => {
+                let Some(server) = self.servers.get_mut(id) else {
+                    return Task::none();
+                };
+
+                if server.is_running() {
+                    return Task::none();
+                }
+
+                if !exists {
+                    let body = format!(
+                        "The map '{}' of '{}' couldn't be found",
+                        server.info.map, server.info.name
+                    );
+
+                    return Task::future(notification("MANNager", body, Duration::from_secs(5)))
+                        .discard();
+                }
+
+                server.restart_attempts = 0;
+
+                self.screen = Screen::ServerTerminal(id);
+
+                self.run_server(id)
+            }
             Message::ServerTerminal(id, message) => {
                 use serverboot::Action;
 
@@ -426,6 +735,7 @@ impl State {
 
                         Task::none()
                     }
+                    Action::StopServer => self.stop_server(id),
                     Action::Run(task) => task.map(Message::ServerTerminal.with(id)),
                 }
             }
@@ -452,6 +762,105 @@ impl State {
 
                 Task::future(notification("MANNager", body, Duration::from_secs(5))).discard()
             }
+            Message::RestartServer(id) => {
+                let Some(server) = self.servers.get(id) else {
+                    return Task::none();
+                };
+
+                // The server could have been started by hand in the meantime.
+                if server.is_running() {
+                    return Task::none();
+                }
+
+                self.run_server(id)
+            }
+            Message::ForceStopServer(id) => {
+                let Some(server) = self.servers.get(id) else {
+                    return Task::none();
+                };
+
+                // Nothing to do if the server quit on its own in time.
+                if !server.is_stopping {
+                    return Task::none();
+                }
+
+                self.kill_server(id)
+            }
+            Message::CheckServerUpdates => Task::batch(
+                self.servers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, server)| server.update_phase.is_none())
+                    .map(|(id, server)| serverlist::check_updates(id, server)),
+            )
+            .map(Message::ServerList),
+            Message::QueryServers => {
+                Task::batch(self.servers.iter().enumerate().filter_map(|(id, server)| {
+                    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, server.running_port()?));
+
+                    Some(Task::perform(
+                        a2s::query(address),
+                        Message::ServerQueried.with(id),
+                    ))
+                }))
+            }
+            Message::ServerQueried(id, res) => {
+                let Some(server) = self.servers.get_mut(id) else {
+                    return Task::none();
+                };
+
+                if server.is_running() {
+                    server.status = res.ok();
+                }
+
+                Task::none()
+            }
+            Message::ServerKilled(id, res) => {
+                let Some(server) = self.servers.get_mut(id) else {
+                    return Task::none();
+                };
+
+                // The server could have been stopped and started again in the meantime.
+                if !server.is_stopping {
+                    return Task::none();
+                }
+
+                server.is_stopping = false;
+
+                // The process is gone either way if it can't be found anymore.
+                let killed = match res {
+                    Ok(()) => Ok(()),
+                    Err(core::Error::ProcessNotFoundError { .. }) => Ok(()),
+                    Err(err) => Err(err),
+                };
+
+                match killed {
+                    Ok(()) => {
+                        let close_port_task = server
+                            .console
+                            .take()
+                            .map_or_else(Task::none, |mut console| {
+                                close_port_forward(&mut console)
+                            });
+
+                        server.detached = None;
+                        server.status = None;
+
+                        if matches!(self.screen, Screen::ServerTerminal(terminal_id) if terminal_id == id)
+                        {
+                            self.screen = Screen::ServerList;
+                        }
+
+                        Task::batch([close_port_task, self.save_runtime_state()])
+                    }
+                    Err(err) => {
+                        let body = format!("Unable to stop '{}'. ERR: {err}", server.info.name);
+
+                        Task::future(notification("MANNager", body, Duration::from_secs(5)))
+                            .discard()
+                    }
+                }
+            }
             Message::CheckForUpdate(res) => {
                 let Ok((um, update_status)) = res.as_ref() else {
                     return Task::none();
@@ -502,16 +911,294 @@ impl State {
         }
     }
 
+    fn run_server(&mut self, id: usize) -> Task<Message> {
+        let Some(server) = self.servers.get_mut(id) else {
+            return Task::none();
+        };
+
+        let port = server
+            .info
+            .port
+            .unwrap_or_else(|| find_available_port(Ipv4Addr::UNSPECIFIED));
+
+        let Some((binary_path, args)) = server.launch_command(port) else {
+            return Task::none();
+        };
+
+        // The server still starts fine without its MOTD.
+        let motd_task = match server.write_motd() {
+            Ok(()) => Task::none(),
+            Err(err) => Task::future(notification(
+                "MANNager",
+                format!(
+                    "Unable to write the MOTD of '{}'. ERR: {err}",
+                    server.info.name
+                ),
+                Duration::from_secs(5),
+            ))
+            .discard(),
+        };
+
+        // Only the log of the run being followed matters, a stale one is no reason not to start.
+        let _ = server.rotate_console_log();
+
+        let Server {
+            info,
+            console,
+            hosting_mode,
+            ..
+        } = server;
+
+        let (server_stream, handle) = Task::run(
+            Console::start(binary_path, args),
+            Message::ServerCommunication.with(id),
+        )
+        .abortable();
+
+        let name = info.name.clone();
+
+        let port_forward_task = match hosting_mode {
+            server::HostingMode::Upnp => Task::perform(
+                async move { Console::port_forward(name, port).await },
+                move |res| Message::PortForward(id, res.map(|pf| Arc::new(pf))),
+            ),
+            _ => Task::none(),
+        };
+
+        *console = Some(Console::from_handle(handle, port));
+
+        Task::batch([server_stream, port_forward_task, motd_task])
+    }
+
+    /// Applies the restart policy of a server whose process just exited.
+    fn server_exited(&mut self, id: usize, exit_code: Option<i32>) -> Task<Message> {
+        let Some(server) = self.servers.get_mut(id) else {
+            return Task::none();
+        };
+
+        server.status = None;
+
+        let close_port_task = server
+            .console
+            .as_mut()
+            .map_or_else(Task::none, close_port_forward);
+
+        // The server was asked to quit, so this isn't a crash.
+        if server.is_stopping {
+            server.is_stopping = false;
+
+            return Task::batch([close_port_task, self.save_runtime_state()]);
+        }
+
+        let exit = match exit_code {
+            Some(code) => format!("exited with code {code}"),
+            None => "was killed".to_string(),
+        };
+
+        let policy = server.info.restart_policy;
+
+        if server
+            .console
+            .as_ref()
+            .is_some_and(|console| console.opened_at.elapsed() >= RestartPolicy::STABLE_UPTIME)
+        {
+            server.restart_attempts = 0;
+        }
+
+        let (body, restart_task) = match policy.restart_delay(exit_code, server.restart_attempts) {
+            Some(delay) => {
+                server.restart_attempts += 1;
+
+                (
+                    format!(
+                        "'{}' {exit}. Restarting in {} seconds ({}/{})",
+                        server.info.name,
+                        delay.as_secs(),
+                        server.restart_attempts,
+                        policy.max_retries,
+                    ),
+                    Task::perform(tokio::time::sleep(delay), move |_| {
+                        Message::RestartServer(id)
+                    }),
+                )
+            }
+            None => (format!("'{}' {exit}", server.info.name), Task::none()),
+        };
+
+        Task::batch([
+            close_port_task,
+            self.save_runtime_state(),
+            restart_task,
+            Task::future(notification("MANNager", body, Duration::from_secs(5))).discard(),
+        ])
+    }
+
+    /// Warns the players, asks the server to quit, and kills it if it doesn't within the timeout.
+    fn stop_server(&mut self, id: usize) -> Task<Message> {
+        let Some(server) = self.servers.get_mut(id) else {
+            return Task::none();
+        };
+
+        // Stopping a server that is already being stopped doesn't wait for it anymore.
+        if server.is_stopping {
+            return self.kill_server(id);
+        }
+
+        let policy = server.info.stop_policy;
+        let commands = policy.commands();
+
+        // A server from a previous session isn't tied to any task, so it can only be
+        // asked to quit through RCON, and killed by PID.
+        if let Some(running) = &server.detached {
+            let stop = running
+                .clone()
+                .stop(policy, server.info.rcon_password.clone());
+
+            server.is_stopping = true;
+
+            return Task::perform(stop, Message::ServerKilled.with(id));
+        }
+
+        let Some(console) = &mut server.console else {
+            return Task::none();
+        };
+
+        match (console.connection, console.sender.clone()) {
+            (Connection::Process, Some(mut sender)) if console.exited.is_none() => {
+                server.is_stopping = true;
+
+                Task::future(async move {
+                    for (delay, command) in commands {
+                        tokio::time::sleep(delay).await;
+
+                        let _ = sender.send(command).await;
+                    }
+
+                    tokio::time::sleep(policy.timeout).await;
+                })
+                .map(move |_| Message::ForceStopServer(id))
+            }
+            // RCON and log sessions are only closed, the server keeps running.
+            _ => {
+                let close_port_task = close_port_forward(console);
+
+                server.console = None;
+                server.status = None;
+
+                if matches!(self.screen, Screen::ServerTerminal(terminal_id) if terminal_id == id) {
+                    self.screen = Screen::ServerList;
+                }
+
+                Task::batch([close_port_task, self.save_runtime_state()])
+            }
+        }
+    }
+
+    fn kill_server(&mut self, id: usize) -> Task<Message> {
+        let Some(server) = self.servers.get_mut(id) else {
+            return Task::none();
+        };
+
+        server.is_stopping = true;
+
+        if let Some(running) = server.detached.clone() {
+            return Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        let result = running.kill();
+
+                        running.close_port_forward();
+
+                        result
+                    })
+                    .await
+                    .unwrap()
+                },
+                Message::ServerKilled.with(id),
+            );
+        }
+
+        match server.console.as_ref().and_then(|console| console.pid) {
+            Some(pid) => Task::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || process::kill(pid))
+                        .await
+                        .unwrap()
+                },
+                Message::ServerKilled.with(id),
+            ),
+            None => Task::done(Message::ServerKilled(id, Ok(()))),
+        }
+    }
+
+    fn save_servers(&self) -> Task<Message> {
+        let servers = self.servers.clone();
+
+        Task::future(async {
+            futures::future::ready(get_config_path())
+                .then(|res| async move {
+                    match res {
+                        Ok(path) => Ok(path),
+                        Err(_) => create_config_file_path().await,
+                    }
+                })
+                .and_then(|path| async move { servers.save(&path).await })
+                .await
+        })
+        .discard()
+    }
+
+    fn save_runtime_state(&self) -> Task<Message> {
+        let runtime_state = self.servers.runtime_state();
+
+        Task::future(async {
+            futures::future::ready(get_config_path())
+                .then(|res| async move {
+                    match res {
+                        Ok(path) => Ok(path),
+                        Err(_) => create_config_file_path().await,
+                    }
+                })
+                .and_then(
+                    |path| async move { runtime_state.save(&runtime_state_path(&path)).await },
+                )
+                .await
+        })
+        .discard()
+    }
+
+    pub fn subscription(&self) -> Subscription<Message> {
+        let queries = if self.servers.iter().any(Server::is_running) {
+            iced::time::every(QUERY_INTERVAL).map(|_| Message::QueryServers)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([
+            queries,
+            iced::time::every(UPDATE_CHECK_INTERVAL).map(|_| Message::CheckServerUpdates),
+        ])
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
         let screen = match &self.screen {
             Screen::Loading => screen::loading::loading(),
             Screen::ServerList => ServerList::view(&self.servers).map(Message::ServerList),
             Screen::ServerCreation(creation) => creation.view().map(Message::ServerCreation),
+            Screen::ServerConfig(config) => config.view().map(Message::ServerConfig),
+            Screen::ServerMaps(maps) => maps.view().map(Message::ServerMaps),
+            Screen::ServerPlugins(plugins) => plugins.view().map(Message::ServerPlugins),
+            Screen::PluginBrowser(browser) => browser.view().map(Message::PluginBrowser),
             Screen::ServerTerminal(index) => {
                 // TODO: remove the unwrap
-                let Server { info, console, .. } = &self.servers[*index];
+                let Server {
+                    info,
+                    console,
+                    status,
+                    ..
+                } = &self.servers[*index];
 
-                ServerTerminal::view(&info.name, console.as_ref().unwrap())
+                ServerTerminal::view(&info.name, console.as_ref().unwrap(), status.as_ref())
                     .map(move |msg| Message::ServerTerminal(*index, msg))
             }
         };
@@ -528,3 +1215,17 @@ impl State {
         .into()
     }
 }
+
+/// Removes the UPnP mapping of the server, if it had one.
+fn close_port_forward(console: &mut Console) -> Task<Message> {
+    let Some(port_forwarder) = console.port_forwarder.take().and_then(Arc::into_inner) else {
+        return Task::none();
+    };
+
+    Task::future(tokio::task::spawn_blocking(move || {
+        let mut port_forwarder = port_forwarder;
+
+        port_forwarder.close()
+    }))
+    .discard()
+}
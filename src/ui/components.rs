@@ -2,6 +2,7 @@ use std::ops::RangeInclusive;
 
 use crate::ui::components::metered_progress_bar::MeteredProgressBar;
 
+pub mod dropdown;
 pub mod metered_progress_bar;
 pub mod modal;
 pub mod notification;
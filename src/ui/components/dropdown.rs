@@ -0,0 +1,60 @@
+use std::fmt::Display;
+
+use iced::{
+    Alignment, Length,
+    widget::{button, container, row, space, text},
+};
+use iced_aw::{
+    Menu, MenuBar,
+    menu::{DrawPath, Item},
+};
+
+use crate::{
+    icon,
+    ui::{Element, themes::tf2},
+};
+
+/// A button that opens a list of `options` to pick from.
+pub fn dropdown<'a, T, Message>(
+    options: impl IntoIterator<Item = T>,
+    selected: Option<&T>,
+    placeholder: &'a str,
+    on_select: impl Fn(T) -> Message + 'a,
+) -> Element<'a, Message>
+where
+    T: Display + Clone + 'a,
+    Message: Clone + 'a,
+{
+    let label = selected.map_or_else(|| placeholder.to_string(), T::to_string);
+
+    let items = options
+        .into_iter()
+        .map(|option| {
+            Item::new(
+                button(text(option.to_string()).size(15))
+                    .on_press(on_select(option))
+                    .width(Length::Fill)
+                    .style(tf2::button::text),
+            )
+        })
+        .collect();
+
+    MenuBar::new(vec![Item::with_menu(
+        container(
+            row![
+                text(label).size(15),
+                space::horizontal(),
+                icon::down_arrow().size(15)
+            ]
+            .align_y(Alignment::Center)
+            .spacing(5),
+        )
+        .width(Length::Fill),
+        Menu::new(items).max_width(200.0).offset(5.0),
+    )])
+    .close_on_background_click_global(true)
+    .close_on_item_click_global(true)
+    .draw_path(DrawPath::Backdrop)
+    .padding(0)
+    .into()
+}
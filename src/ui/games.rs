@@ -2,7 +2,11 @@ use std::{path::PathBuf, sync::LazyLock};
 
 use iced::widget::svg;
 
-use crate::core::{Game, SourceEngineVersion};
+use crate::core::{
+    Game, SourceEngineVersion,
+    launch::{LaunchProfile, Setting},
+    workshop::Workshop,
+};
 
 pub struct SourceGame {
     pub game: Game,
@@ -10,6 +14,7 @@ pub struct SourceGame {
     pub engine: SourceEngineVersion,
     pub can_sdr: bool,
     pub executable_path: PathBuf,
+    pub launch: LaunchProfile,
 }
 
 pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
@@ -24,6 +29,10 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::TeamFortress2, "ctf_2fort")
+                .max_players(100)
+                .unrestricted_above(32, "-unrestricted_maxplayers")
+                .workshop(Workshop::Synced),
         },
         SourceGame {
             game: Game::CounterStrikeSource,
@@ -35,6 +44,9 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::CounterStrikeSource, "de_dust2")
+                .max_players(64)
+                .with(Setting::Tickrate, "-tickrate"),
         },
         SourceGame {
             game: Game::CounterStrikeGlobalOffensive,
@@ -46,6 +58,13 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            // Hosting a collection also needs a Web API key, passed with `-authkey`.
+            launch: LaunchProfile::source1(Game::CounterStrikeGlobalOffensive, "de_dust2")
+                .max_players(64)
+                .with(Setting::Tickrate, "-tickrate")
+                .workshop(Workshop::Hosted {
+                    start_map_arg: "+workshop_start_map",
+                }),
         },
         SourceGame {
             game: Game::LeftForDead1,
@@ -57,6 +76,10 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            // Left 4 Dead servers predate Game Server Login Tokens.
+            launch: LaunchProfile::source1(Game::LeftForDead1, "l4d_hospital01_apartment")
+                .with(Setting::Tickrate, "-tickrate")
+                .without(Setting::Gslt),
         },
         SourceGame {
             game: Game::LeftForDead2,
@@ -68,6 +91,9 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::LeftForDead2, "c1m1_hotel")
+                .with(Setting::Tickrate, "-tickrate")
+                .without(Setting::Gslt),
         },
         SourceGame {
             game: Game::NoMoreRoomInHell,
@@ -79,6 +105,8 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::NoMoreRoomInHell, "nmo_broadway")
+                .with(Setting::Tickrate, "-tickrate"),
         },
         SourceGame {
             game: Game::HalfLife2DM,
@@ -90,6 +118,8 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::HalfLife2DM, "dm_lockdown")
+                .with(Setting::Tickrate, "-tickrate"),
         },
         SourceGame {
             game: Game::CounterStrike2,
@@ -101,6 +131,9 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 ["game", "cs2.sh"].iter().collect()
             },
+            launch: LaunchProfile::source2("de_dust2").workshop(Workshop::Hosted {
+                start_map_arg: "+host_workshop_map",
+            }),
         },
         SourceGame {
             game: Game::Deadlock,
@@ -108,6 +141,7 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             engine: SourceEngineVersion::Source2,
             can_sdr: true,
             executable_path: ["game", "bin", "wind64", "deadlock.exe"].iter().collect(),
+            launch: LaunchProfile::source2("dl_midtown").max_players(32),
         },
         SourceGame {
             game: Game::DayOfDefeatSource,
@@ -119,6 +153,170 @@ pub static SOURCE_GAMES: LazyLock<Vec<SourceGame>> = LazyLock::new(|| {
             } else {
                 PathBuf::from("srcds_run")
             },
+            launch: LaunchProfile::source1(Game::DayOfDefeatSource, "dod_anzio")
+                .with(Setting::Tickrate, "-tickrate"),
         },
     ]
 });
+
+/// The game's entry in [`SOURCE_GAMES`].
+pub fn source_game(game: Game) -> Option<&'static SourceGame> {
+    SOURCE_GAMES
+        .iter()
+        .find(|source_game| source_game.game == game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        launch::{
+            LaunchOptions, MOTD_FILE_NAME, Region,
+            tests::{has, value},
+        },
+        workshop::WorkshopItems,
+    };
+
+    fn args(game: Game, max_players: u32) -> Vec<String> {
+        LaunchOptions {
+            hostname: "My Server".to_string(),
+            map: "some_map".to_string(),
+            max_players,
+            port: 27015,
+            password: Some("secret".to_string()),
+            has_motd: true,
+            region: Some(Region::Europe),
+            tags: Some("custom,vanilla".to_string()),
+            tickrate: Some(100),
+            gslt: Some("TOKEN".to_string()),
+            rcon_password: Some("rcon".to_string()),
+            workshop: WorkshopItems {
+                collection: Some(1),
+                maps: vec![2, 3],
+            },
+            ..Default::default()
+        }
+        .args(&source_game(game).unwrap().launch)
+    }
+
+    #[test]
+    fn team_fortress_2() {
+        let args = args(Game::TeamFortress2, 24);
+
+        assert_eq!(value(&args, "-game"), Some("tf"));
+        assert_eq!(value(&args, "+hostname"), Some("My Server"));
+        assert_eq!(value(&args, "+sv_password"), Some("secret"));
+        assert_eq!(value(&args, "+sv_region"), Some("3"));
+        assert_eq!(value(&args, "+sv_tags"), Some("custom,vanilla"));
+        assert_eq!(value(&args, "+motdfile"), Some(MOTD_FILE_NAME));
+        assert_eq!(value(&args, "+sv_setsteamaccount"), Some("TOKEN"));
+        assert_eq!(value(&args, "+rcon_password"), Some("rcon"));
+        assert!(!has(&args, "-tickrate"));
+        assert!(!has(&args, "-unrestricted_maxplayers"));
+        assert!(!has(&args, "+host_workshop_collection"));
+        assert_eq!(value(&args, "+map"), Some("some_map"));
+        assert_eq!(value(&args, "+tf_workshop_map_sync"), Some("2"));
+    }
+
+    #[test]
+    fn team_fortress_2_over_32_players() {
+        let args = args(Game::TeamFortress2, 100);
+
+        assert_eq!(value(&args, "+maxplayers"), Some("100"));
+        assert!(has(&args, "-unrestricted_maxplayers"));
+    }
+
+    #[test]
+    fn counter_strike_source() {
+        let args = args(Game::CounterStrikeSource, 24);
+
+        assert_eq!(value(&args, "-game"), Some("cstrike"));
+        assert_eq!(value(&args, "-tickrate"), Some("100"));
+        assert_eq!(value(&args, "+sv_password"), Some("secret"));
+        assert_eq!(value(&args, "+map"), Some("some_map"));
+        assert!(!has(&args, "+tf_workshop_map_sync"));
+    }
+
+    #[test]
+    fn counter_strike_global_offensive() {
+        let args = args(Game::CounterStrikeGlobalOffensive, 24);
+
+        assert_eq!(value(&args, "-game"), Some("csgo"));
+        assert_eq!(value(&args, "-tickrate"), Some("100"));
+        assert_eq!(value(&args, "+host_workshop_collection"), Some("1"));
+        assert_eq!(value(&args, "+workshop_start_map"), Some("2"));
+        assert!(!has(&args, "+map"));
+    }
+
+    #[test]
+    fn counter_strike_2() {
+        let args = args(Game::CounterStrike2, 24);
+
+        assert!(has(&args, "-dedicated"));
+        assert!(has(&args, "-usercon"));
+        assert!(!has(&args, "-game"));
+        assert!(!has(&args, "-tickrate"));
+        assert!(!has(&args, "+sv_region"));
+        assert!(!has(&args, "+motdfile"));
+        assert_eq!(value(&args, "+sv_password"), Some("secret"));
+        assert_eq!(value(&args, "+sv_tags"), Some("custom,vanilla"));
+        assert_eq!(value(&args, "+sv_setsteamaccount"), Some("TOKEN"));
+        assert_eq!(value(&args, "+host_workshop_collection"), Some("1"));
+        assert_eq!(value(&args, "+host_workshop_map"), Some("2"));
+    }
+
+    #[test]
+    fn deadlock() {
+        let args = args(Game::Deadlock, 100);
+
+        assert!(has(&args, "-dedicated"));
+        assert!(!has(&args, "-tickrate"));
+        assert!(!has(&args, "+motdfile"));
+        assert_eq!(value(&args, "+maxplayers"), Some("32"));
+    }
+
+    #[test]
+    fn left_4_dead() {
+        for (game, folder) in [
+            (Game::LeftForDead1, "left4dead"),
+            (Game::LeftForDead2, "left4dead2"),
+        ] {
+            let args = args(game, 8);
+
+            assert_eq!(value(&args, "-game"), Some(folder));
+            assert_eq!(value(&args, "-tickrate"), Some("100"));
+            assert!(!has(&args, "+sv_setsteamaccount"));
+        }
+    }
+
+    #[test]
+    fn half_life_2_deathmatch() {
+        let args = args(Game::HalfLife2DM, 24);
+
+        assert_eq!(value(&args, "-game"), Some("hl2mp"));
+        assert_eq!(value(&args, "-tickrate"), Some("100"));
+    }
+
+    #[test]
+    fn no_more_room_in_hell() {
+        let args = args(Game::NoMoreRoomInHell, 24);
+
+        assert_eq!(value(&args, "-game"), Some("nmrih"));
+        assert_eq!(value(&args, "-tickrate"), Some("100"));
+    }
+
+    #[test]
+    fn day_of_defeat_source() {
+        let args = args(Game::DayOfDefeatSource, 24);
+
+        assert_eq!(value(&args, "-game"), Some("dod"));
+        assert_eq!(value(&args, "-tickrate"), Some("100"));
+    }
+
+    #[test]
+    fn every_game_has_a_default_map() {
+        for source_game in SOURCE_GAMES.iter() {
+            assert!(!source_game.launch.default_map.is_empty());
+        }
+    }
+}
@@ -1,11 +1,19 @@
 pub mod loading;
+pub mod pluginbrowser;
 pub mod serverboot;
+pub mod serverconfig;
 pub mod servercreation;
 pub mod serverlist;
+pub mod servermaps;
+pub mod serverplugins;
 
 pub enum Screen {
     Loading,
     ServerList,
     ServerCreation(servercreation::State),
     ServerTerminal(usize),
+    ServerConfig(serverconfig::State),
+    ServerMaps(servermaps::State),
+    ServerPlugins(serverplugins::State),
+    PluginBrowser(pluginbrowser::State),
 }
@@ -0,0 +1,243 @@
+use std::path::PathBuf;
+
+use iced::{
+    Alignment, Font, Length, Shadow, Task, padding,
+    widget::{button, column, container, row, scrollable, space, text, text_input},
+};
+
+use crate::{
+    core::{
+        self, Game, SourceEngineVersion,
+        plugins::index::{self, IndexedPlugin},
+    },
+    icon,
+    ui::{Element, games, server::Server, themes::tf2},
+};
+
+pub struct State {
+    pub id: usize,
+    game: Game,
+    engine: SourceEngineVersion,
+    game_directory: PathBuf,
+    url: String,
+    plugins: Option<Result<Vec<IndexedPlugin>, String>>,
+    search: String,
+    /// The name of the plugin being installed.
+    installing: Option<String>,
+    status: Option<Result<String, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ChangeUrl(String),
+    Fetch,
+    Fetched(Result<Vec<IndexedPlugin>, core::Error>),
+    Search(String),
+    Install(IndexedPlugin),
+    Installed(Result<Vec<String>, core::Error>),
+    GoBack,
+}
+
+pub enum Action {
+    None,
+    GoBack,
+    Run(Task<Message>),
+}
+
+impl State {
+    pub fn new(id: usize, server: &Server) -> (Self, Task<Message>) {
+        let url = index::index_url();
+
+        let state = Self {
+            id,
+            game: server.info.game,
+            engine: games::source_game(server.info.game)
+                .map(|game_info| game_info.engine.clone())
+                .unwrap_or_default(),
+            game_directory: server.info.game_directory(),
+            url: url.clone(),
+            plugins: None,
+            search: String::new(),
+            installing: None,
+            status: None,
+        };
+
+        (state, Task::perform(index::fetch(url), Message::Fetched))
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::ChangeUrl(url) => {
+                self.url = url;
+
+                Action::None
+            }
+            Message::Fetch => {
+                self.plugins = None;
+
+                Action::Run(Task::perform(
+                    index::fetch(self.url.clone()),
+                    Message::Fetched,
+                ))
+            }
+            Message::Fetched(result) => {
+                self.plugins = Some(result.map_err(|err| err.to_string()));
+
+                Action::None
+            }
+            Message::Search(search) => {
+                self.search = search;
+
+                Action::None
+            }
+            Message::Install(plugin) => {
+                self.installing = Some(plugin.name.clone());
+
+                Action::Run(Task::perform(
+                    index::install(self.game_directory.clone(), plugin),
+                    Message::Installed,
+                ))
+            }
+            Message::Installed(result) => {
+                let name = self.installing.take().unwrap_or_default();
+
+                self.status = Some(match result {
+                    Ok(_) => Ok(format!("Installed {name}")),
+                    Err(err) => Err(format!("Couldn't install {name}: {err}")),
+                });
+
+                Action::None
+            }
+            Message::GoBack => Action::GoBack,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let header = container(
+            row![
+                button(icon::left_arrow().size(20).center()).on_press(Message::GoBack),
+                text("Browse plugins")
+                    .font(Font::new("TF2 Build"))
+                    .size(30)
+                    .line_height(1.0),
+                space::horizontal(),
+                text_input("Index URL", &self.url)
+                    .on_input(Message::ChangeUrl)
+                    .on_submit(Message::Fetch)
+                    .size(13)
+                    .width(350),
+                button(text("Refresh").size(13))
+                    .on_press_maybe(self.plugins.is_some().then_some(Message::Fetch))
+                    .style(tf2::button::text),
+            ]
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .padding(padding::all(10)),
+        )
+        .style(|theme| {
+            let mut style = tf2::container::card(theme);
+
+            style.border = style.border.rounded(0);
+            style.shadow = Shadow::default();
+
+            style
+        });
+
+        let status = self.status.as_ref().map(|status| {
+            let status = match status {
+                Ok(message) => text(message).style(tf2::text::muted),
+                Err(error) => text(error).style(tf2::text::error),
+            };
+
+            container(status).padding(padding::horizontal(20))
+        });
+
+        let body: Element<'_, Message> = match &self.plugins {
+            None => text("Loading...").style(tf2::text::muted).into(),
+            Some(Err(error)) => text(error).style(tf2::text::error).into(),
+            Some(Ok(plugins)) => {
+                let search = self.search.to_lowercase();
+
+                let rows = plugins
+                    .iter()
+                    .filter(|plugin| plugin.supports(self.game, &self.engine))
+                    .filter(|plugin| {
+                        plugin.name.to_lowercase().contains(&search)
+                            || plugin.description.as_ref().is_some_and(|description| {
+                                description.to_lowercase().contains(&search)
+                            })
+                    })
+                    .map(|plugin| self.plugin_row(plugin))
+                    .collect::<Vec<_>>();
+
+                let list: Element<'_, Message> = if rows.is_empty() {
+                    text("No plugins found for this game")
+                        .style(tf2::text::muted)
+                        .into()
+                } else {
+                    scrollable(column(rows).spacing(10))
+                        .height(Length::Fill)
+                        .into()
+                };
+
+                column![
+                    text_input("Search plugins", &self.search)
+                        .on_input(Message::Search)
+                        .size(15),
+                    list
+                ]
+                .spacing(10)
+                .into()
+            }
+        };
+
+        column![
+            header,
+            status,
+            container(body)
+                .padding(padding::horizontal(20).bottom(20))
+                .height(Length::Fill)
+        ]
+        .spacing(10)
+        .height(Length::Fill)
+        .into()
+    }
+
+    fn plugin_row<'a>(&'a self, plugin: &'a IndexedPlugin) -> Element<'a, Message> {
+        let is_installing = self.installing.as_ref() == Some(&plugin.name);
+
+        row![
+            column![
+                row![
+                    text(&plugin.name).size(15),
+                    text(&plugin.version).size(12).style(tf2::text::muted),
+                ]
+                .spacing(8)
+                .align_y(Alignment::End),
+                plugin
+                    .description
+                    .as_ref()
+                    .map(|description| text(description).size(12).style(tf2::text::muted)),
+            ]
+            .spacing(2)
+            .width(Length::Fill),
+            button(
+                text(if is_installing {
+                    "Installing..."
+                } else {
+                    "Install"
+                })
+                .size(13)
+            )
+            .on_press_maybe(
+                self.installing
+                    .is_none()
+                    .then(|| Message::Install(plugin.clone()))
+            )
+            .style(tf2::button::primary),
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    }
+}
@@ -1,8 +1,9 @@
 use std::{
-    io,
-    net::{Ipv4Addr, UdpSocket},
+    io::{self, SeekFrom},
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
     path::PathBuf,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use iced::{
@@ -20,12 +21,17 @@ use iced::widget::scrollable;
 use portforwarder_rs::port_forwarder::PortMappingProtocol;
 use snafu::{ResultExt, Snafu};
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
     select,
 };
 
 use crate::{
-    core::portforwarder::{self, PortForwarder, PortForwarderIP},
+    core::{
+        a2s,
+        portforwarder::{self, PortForwarder, PortForwarderIP},
+        process,
+        rcon::{self, RconClient},
+    },
     icon,
     ui::{
         Element,
@@ -43,6 +49,14 @@ pub struct Console {
     pub input_history: Vec<String>,
     pub input_history_index: usize,
     pub handle: task::Handle,
+    pub connection: Connection,
+    pub pid: Option<u32>,
+    /// When the console was opened, which is when the server was launched for spawned ones.
+    pub opened_at: Instant,
+    /// When the process started, to recognize it if the app is reopened while it runs.
+    pub started: Option<u64>,
+    /// Set once the process exited, along with its exit code if it had one.
+    pub exited: Option<Option<i32>>,
     pub sender: Option<mpsc::Sender<String>>,
     pub hosted_port: u16,
     pub port_forwarder: Option<Arc<PortForwarder>>,
@@ -50,6 +64,17 @@ pub struct Console {
     pub is_near_bottom: bool,
 }
 
+/// How the console talks to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    /// The server was spawned by us, and we own its stdin / stdout.
+    Process,
+    /// The server runs on its own, and commands go through RCON.
+    Rcon,
+    /// The server runs on its own, and we can only follow its log.
+    Log,
+}
+
 impl Console {
     pub fn from_handle(handle: task::Handle, port: u16) -> Self {
         Self {
@@ -58,6 +83,11 @@ impl Console {
             input_history: vec![],
             input_history_index: 0,
             handle: handle.abort_on_drop(),
+            connection: Connection::Process,
+            pid: None,
+            opened_at: Instant::now(),
+            started: None,
+            exited: None,
             sender: None,
             hosted_port: port,
             port_forwarder: None,
@@ -66,9 +96,15 @@ impl Console {
         }
     }
 
+    pub fn with_connection(mut self, connection: Connection) -> Self {
+        self.connection = connection;
+
+        self
+    }
+
     pub fn start(
         executable_path: PathBuf,
-        args: String,
+        args: Vec<String>,
     ) -> impl Stream<Item = Result<ServerCommunicationTwoWay, Error>> {
         try_channel(
             1,
@@ -99,7 +135,7 @@ impl Console {
                     {
                         pty_process::Command::new(&executable_path)
                             .current_dir(parent)
-                            .args(args.split_whitespace())
+                            .args(&args)
                             .spawn(&pty.pts().map_err(|err| Error::SpawnProcessError {
                                 msg: err.to_string(),
                             })?)
@@ -116,7 +152,7 @@ impl Console {
 
                         tokio::process::Command::new(&executable_path)
                             .current_dir(parent)
-                            .args(args.split_whitespace())
+                            .args(&args)
                             .stdin(Stdio::piped())
                             .stdout(Stdio::piped())
                             .kill_on_drop(true)
@@ -128,6 +164,17 @@ impl Console {
                     }
                 };
 
+                if let Some(pid) = _process.id() {
+                    let started = tokio::task::spawn_blocking(move || process::start_time(pid))
+                        .await
+                        .ok()
+                        .flatten();
+
+                    let _ = output
+                        .send(ServerCommunicationTwoWay::Started(pid, started))
+                        .await;
+                }
+
                 let (process_reader, mut process_writer) = {
                     #[cfg(target_os = "linux")]
                     {
@@ -136,36 +183,57 @@ impl Console {
 
                     #[cfg(target_os = "windows")]
                     {
-                        (_process.stdout.take().unwrap(), _process.stdin.unwrap())
+                        (
+                            _process.stdout.take().unwrap(),
+                            _process.stdin.take().unwrap(),
+                        )
                     }
                 };
 
                 let mut reader = BufReader::new(process_reader);
-                let mut line = String::new();
 
-                let mut input_bool = false;
+                // Kept across iterations, a read cut short by input carries on where it stopped.
+                let mut line = Vec::new();
 
-                loop {
-                    line.clear();
+                let mut input_bool = false;
 
-                    let read_future = reader.read_line(&mut line);
+                let exit_code = loop {
+                    let read_future = reader.read_until(b'\n', &mut line);
                     let input_future = receiver.select_next_some();
 
                     select! {
                         pty_output = read_future => {
-                            let bytes = pty_output.context(CommunicationSnafu)?;
+                            let has_exited = match &pty_output {
+                                Ok(read) => *read == 0,
+                                Err(err) => is_closed(err),
+                            };
 
-                            if bytes == 0 {
-                                continue;
+                            if has_exited {
+                                let status = _process.wait().await.context(CommunicationSnafu)?;
+
+                                break status.code();
                             }
 
+                            // Any other error would most likely keep coming back, so the console
+                            // ends here too, with the exit code if there's one already.
+                            if pty_output.is_err() {
+                                let status = _process.try_wait().ok().flatten();
+
+                                break status.and_then(|status| status.code());
+                            }
+
+                            // Servers print text in whatever encoding the game uses, not always UTF-8.
+                            let text = String::from_utf8_lossy(&line).trim_end().to_owned();
+
+                            line.clear();
+
                             // This is definitely not error proof, but it's the only thing that came to mind.
                             let text = if input_bool {
                                 input_bool = false;
 
-                                TextType::Input(line.trim_end().to_owned())
+                                TextType::Input(text)
                             } else {
-                                TextType::Output(line.trim_end().to_owned())
+                                TextType::Output(text)
                             };
 
                             let _ = output.send(ServerCommunicationTwoWay::Output(text)).await;
@@ -184,6 +252,114 @@ impl Console {
                             input_bool = true;
                         }
                     }
+                };
+
+                let _ = output
+                    .send(ServerCommunicationTwoWay::Exited(exit_code))
+                    .await;
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Controls a server this app didn't spawn, through RCON.
+    pub fn attach_rcon(
+        address: SocketAddr,
+        password: String,
+    ) -> impl Stream<Item = Result<ServerCommunicationTwoWay, Error>> {
+        try_channel(
+            1,
+            move |mut output: mpsc::Sender<ServerCommunicationTwoWay>| async move {
+                let mut client = RconClient::connect(address, &password)
+                    .await
+                    .context(RconSnafu)?;
+
+                let (sender, mut receiver) = mpsc::channel(100);
+
+                output
+                    .send(ServerCommunicationTwoWay::Input(sender))
+                    .await
+                    .context(ChannelSendSnafu)?;
+
+                let _ = output
+                    .send(ServerCommunicationTwoWay::Output(TextType::Output(
+                        format!("Attached to {address} through RCON"),
+                    )))
+                    .await;
+
+                while let Some(command) = receiver.next().await {
+                    let _ = output
+                        .send(ServerCommunicationTwoWay::Output(TextType::Input(
+                            command.clone(),
+                        )))
+                        .await;
+
+                    let response = client.exec(&command).await.context(RconSnafu)?;
+
+                    for line in response.lines() {
+                        let _ = output
+                            .send(ServerCommunicationTwoWay::Output(TextType::Output(
+                                line.trim_end().to_owned(),
+                            )))
+                            .await;
+                    }
+                }
+
+                Ok(())
+            },
+        )
+    }
+
+    /// Follows the `-condebug` log of a server this app didn't spawn.
+    pub fn tail_log(path: PathBuf) -> impl Stream<Item = Result<ServerCommunicationTwoWay, Error>> {
+        try_channel(
+            1,
+            move |mut output: mpsc::Sender<ServerCommunicationTwoWay>| async move {
+                let mut file = tokio::fs::File::open(&path)
+                    .await
+                    .context(CommunicationSnafu)?;
+
+                let length = file.metadata().await.context(CommunicationSnafu)?.len();
+                let start = length.saturating_sub(LOG_TAIL_LENGTH);
+
+                file.seek(SeekFrom::Start(start))
+                    .await
+                    .context(CommunicationSnafu)?;
+
+                let mut reader = BufReader::new(file);
+                let mut line = Vec::new();
+
+                // We most likely landed in the middle of a line.
+                if start > 0 {
+                    reader
+                        .read_until(b'\n', &mut line)
+                        .await
+                        .context(CommunicationSnafu)?;
+
+                    line.clear();
+                }
+
+                loop {
+                    let bytes = reader
+                        .read_until(b'\n', &mut line)
+                        .await
+                        .context(CommunicationSnafu)?;
+
+                    // Keep what was read of an unfinished line until the server writes the rest.
+                    if bytes == 0 || !line.ends_with(b"\n") {
+                        tokio::time::sleep(LOG_POLL_INTERVAL).await;
+
+                        continue;
+                    }
+
+                    let _ = output
+                        .send(ServerCommunicationTwoWay::Output(TextType::Output(
+                            String::from_utf8_lossy(&line).trim_end().to_owned(),
+                        )))
+                        .await;
+
+                    line.clear();
                 }
             },
         )
@@ -210,6 +386,7 @@ impl Console {
 pub enum Action {
     None,
     GoBack,
+    StopServer,
     Run(Task<Message>),
 }
 
@@ -223,10 +400,30 @@ pub enum Message {
     GoBack,
 }
 
+/// Whether a failed read of the server's output means the server is gone.
+///
+/// Once the child exits, reading the pty fails with EIO on Linux. On Windows the pipe
+/// reaches its end instead.
+fn is_closed(err: &io::Error) -> bool {
+    #[cfg(target_os = "linux")]
+    const EIO: i32 = 5;
+
+    #[cfg(target_os = "linux")]
+    if err.raw_os_error() == Some(EIO) {
+        return true;
+    }
+
+    err.kind() == io::ErrorKind::BrokenPipe
+}
+
 #[derive(Clone, Debug)]
 pub enum ServerCommunicationTwoWay {
     Input(mpsc::Sender<String>),
+    /// The process was spawned, with its PID and when it started.
+    Started(u32, Option<u64>),
     Output(TextType),
+    /// The process exited, with its exit code if it wasn't killed by a signal.
+    Exited(Option<i32>),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -238,14 +435,14 @@ pub enum TextType {
 pub const DEFAULT_PORT: u16 = 27015;
 pub const PORT_OFFSET: u16 = 10;
 
+/// How much of an existing log is shown when attaching to it.
+const LOG_TAIL_LENGTH: u64 = 16 * 1024;
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 impl ServerTerminal {
     pub fn update(console: &mut Console, message: Message) -> Action {
         match message {
-            Message::ShutDownServer => {
-                console.handle.abort();
-
-                Action::None
-            }
+            Message::ShutDownServer => Action::StopServer,
             Message::ServerTerminalInput(string) => {
                 console.input = string;
 
@@ -320,7 +517,11 @@ impl ServerTerminal {
         }
     }
 
-    pub fn view<'a>(title: &String, console: &Console) -> Element<'a, Message> {
+    pub fn view<'a>(
+        title: &String,
+        console: &Console,
+        status: Option<&'a a2s::Status>,
+    ) -> Element<'a, Message> {
         let header = container(
             row![
                 button(icon::left_arrow().size(20).center()).on_press(Message::GoBack),
@@ -332,6 +533,16 @@ impl ServerTerminal {
                         .line_height(1.0)
                 )
                 .padding(padding::top(4).bottom(-4.0)),
+                match console.connection {
+                    Connection::Process => None,
+                    Connection::Rcon => Some("RCON"),
+                    Connection::Log => Some("LOG"),
+                }
+                .map(|tag| {
+                    container(text(tag).size(12))
+                        .padding(padding::horizontal(8).vertical(2))
+                        .style(tf2::container::primary)
+                }),
                 space::horizontal()
             ]
             .width(Length::Fill)
@@ -381,6 +592,54 @@ impl ServerTerminal {
             .padding(padding::left(10))
         };
 
+        let players = status.map(|status| {
+            let header = row![
+                icon::users().size(15),
+                text!("{}/{}", status.info.players, status.info.max_players).size(15),
+                space::horizontal(),
+                text!("{} ms", status.latency.as_millis())
+                    .size(12)
+                    .style(tf2::text::muted),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center);
+
+            let list = column(status.players.iter().map(|player| {
+                let seconds = player.duration.as_secs();
+
+                row![
+                    text(player.name.clone())
+                        .size(13)
+                        .wrapping(text::Wrapping::None)
+                        .width(Length::Fill),
+                    text(player.score).size(13).style(tf2::text::secondary),
+                    text!("{}:{:02}", seconds / 60, seconds % 60)
+                        .size(13)
+                        .font(Font::MONOSPACE)
+                        .style(tf2::text::muted),
+                ]
+                .spacing(10)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(6);
+
+            container(
+                column![
+                    header,
+                    text(status.info.map.clone())
+                        .size(12)
+                        .style(tf2::text::muted),
+                    scrollable(list).height(Length::Fill),
+                ]
+                .spacing(10),
+            )
+            .width(260)
+            .height(Length::Fill)
+            .padding(padding::all(12))
+            .style(tf2::container::card)
+        });
+
         let console_input = textinput_terminal::TextInput::new("Type a command...", &console.input)
             .on_input(Message::ServerTerminalInput)
             .on_submit(Message::SubmitServerTerminalInput)
@@ -397,11 +656,15 @@ impl ServerTerminal {
                 style
             });
 
-        container(column![header, console_output, console_input])
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .style(|theme| tf2::container::base(theme))
-            .into()
+        container(column![
+            header,
+            row![console_output, players],
+            console_input
+        ])
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|theme| tf2::container::base(theme))
+        .into()
     }
 }
 
@@ -427,6 +690,9 @@ pub enum Error {
     #[snafu(display("Channel send failed: {source}"))]
     ChannelSendError { source: mpsc::SendError },
 
+    #[snafu(display("RCON failed: {source}"))]
+    RconError { source: rcon::Error },
+
     #[snafu(display("There was an error while"))]
     PortForwardingError,
 
@@ -0,0 +1,331 @@
+use std::path::PathBuf;
+
+use iced::{
+    Alignment, Font, Length, Shadow, Task, padding,
+    widget::{button, column, container, row, scrollable, space, text, text_editor, text_input},
+};
+
+use crate::{
+    core::{
+        self,
+        cfg::{self, Config, Convar, ConvarKind},
+    },
+    icon,
+    ui::{Element, components::toggle_button_group::grouped_buttons, server::Server, themes::tf2},
+};
+
+pub struct State {
+    path: PathBuf,
+    convars: Vec<Convar>,
+    config: Option<Config>,
+    raw: text_editor::Content,
+    mode: Mode,
+    is_dirty: bool,
+    is_saving: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Mode {
+    #[default]
+    Settings,
+    Raw,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Loaded(Result<Config, core::Error>),
+    ChangeConvar(&'static str, String),
+    ChangeCommand(String, String),
+    ChangeMode(Mode),
+    RawEdit(text_editor::Action),
+    Save,
+    Saved(Result<(), core::Error>),
+    GoBack,
+}
+
+pub enum Action {
+    None,
+    GoBack,
+    Run(Task<Message>),
+}
+
+impl State {
+    pub fn new(server: &Server) -> (Self, Task<Message>) {
+        let path = server.server_cfg_path();
+
+        let state = Self {
+            path: path.clone(),
+            convars: cfg::convars(server.info.game),
+            config: None,
+            raw: text_editor::Content::new(),
+            mode: Mode::default(),
+            is_dirty: false,
+            is_saving: false,
+            error: None,
+        };
+
+        let task = Task::perform(async move { Config::load(&path).await }, Message::Loaded);
+
+        (state, task)
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Loaded(result) => {
+                match result {
+                    Ok(config) => {
+                        self.raw = text_editor::Content::with_text(&config.to_string());
+                        self.config = Some(config);
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                Action::None
+            }
+            Message::ChangeConvar(name, value) => {
+                let Some(config) = &mut self.config else {
+                    return Action::None;
+                };
+
+                // Leaving a known convar empty falls back to the game's default.
+                if value.is_empty() {
+                    config.remove(name);
+                } else {
+                    config.set(name, &value);
+                }
+
+                self.is_dirty = true;
+
+                Action::None
+            }
+            Message::ChangeCommand(name, value) => {
+                let Some(config) = &mut self.config else {
+                    return Action::None;
+                };
+
+                config.set(&name, &value);
+
+                self.is_dirty = true;
+
+                Action::None
+            }
+            Message::ChangeMode(mode) => {
+                if mode == self.mode {
+                    return Action::None;
+                }
+
+                match mode {
+                    Mode::Settings => self.config = Some(Config::parse(&self.raw.text())),
+                    Mode::Raw => {
+                        if let Some(config) = &self.config {
+                            self.raw = text_editor::Content::with_text(&config.to_string());
+                        }
+                    }
+                }
+
+                self.mode = mode;
+
+                Action::None
+            }
+            Message::RawEdit(action) => {
+                if action.is_edit() {
+                    self.is_dirty = true;
+                }
+
+                self.raw.perform(action);
+
+                Action::None
+            }
+            Message::Save => {
+                if self.mode == Mode::Raw {
+                    self.config = Some(Config::parse(&self.raw.text()));
+                }
+
+                let Some(config) = self.config.clone() else {
+                    return Action::None;
+                };
+
+                let path = self.path.clone();
+
+                self.is_saving = true;
+
+                Action::Run(Task::perform(
+                    async move { config.save(&path).await },
+                    Message::Saved,
+                ))
+            }
+            Message::Saved(result) => {
+                self.is_saving = false;
+
+                match result {
+                    Ok(()) => {
+                        self.is_dirty = false;
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                Action::None
+            }
+            Message::GoBack => Action::GoBack,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let header = container(
+            row![
+                button(icon::left_arrow().size(20).center()).on_press(Message::GoBack),
+                column![
+                    text("server.cfg")
+                        .font(Font::new("TF2 Build"))
+                        .size(30)
+                        .line_height(1.0),
+                    text(self.path.to_string_lossy())
+                        .size(12)
+                        .style(tf2::text::muted),
+                ]
+                .spacing(2),
+                space::horizontal(),
+                grouped_buttons(
+                    [
+                        (text("Settings").size(13).into(), Mode::Settings),
+                        (text("Raw").size(13).into(), Mode::Raw),
+                    ],
+                    self.mode,
+                    Message::ChangeMode,
+                    tf2::button::default,
+                ),
+                button(text(if self.is_saving { "Saving..." } else { "Save" }))
+                    .on_press_maybe(
+                        (self.is_dirty && !self.is_saving && self.config.is_some())
+                            .then_some(Message::Save)
+                    )
+                    .style(tf2::button::primary),
+            ]
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .padding(padding::all(10)),
+        )
+        .style(|theme| {
+            let mut style = tf2::container::card(theme);
+
+            style.border = style.border.rounded(0);
+            style.shadow = Shadow::default();
+
+            style
+        });
+
+        let error = self.error.as_ref().map(|error| {
+            container(text(error).style(tf2::text::error)).padding(padding::horizontal(20))
+        });
+
+        let body: Element<'_, Message> = match (&self.config, self.mode) {
+            (None, _) => container(text("Loading...").style(tf2::text::muted))
+                .padding(20)
+                .into(),
+            (Some(_), Mode::Raw) => container(
+                text_editor(&self.raw)
+                    .on_action(Message::RawEdit)
+                    .font(Font::new("Roboto Mono"))
+                    .height(Length::Fill),
+            )
+            .padding(20)
+            .height(Length::Fill)
+            .into(),
+            (Some(config), Mode::Settings) => settings(config, &self.convars),
+        };
+
+        column![header, error, body]
+            .spacing(10)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+fn settings<'a>(config: &'a Config, convars: &'a [Convar]) -> Element<'a, Message> {
+    let known = convars.iter().map(|convar| -> Element<'a, Message> {
+        let value = config.get(convar.name).unwrap_or_default();
+
+        let input: Element<'a, Message> = match convar.kind {
+            ConvarKind::Bool => grouped_buttons(
+                [
+                    (text("Default").size(13).into(), ""),
+                    (text("Off").size(13).into(), "0"),
+                    (text("On").size(13).into(), "1"),
+                ],
+                value,
+                |value| Message::ChangeConvar(convar.name, value.to_string()),
+                tf2::button::default,
+            ),
+            _ => text_input("Default", value)
+                .on_input(|value| Message::ChangeConvar(convar.name, value))
+                .size(15)
+                .width(200)
+                .into(),
+        };
+
+        let hint = match convar.kind {
+            ConvarKind::Integer { min, max } => Some(format!("{min} to {max}")),
+            ConvarKind::Float { min, max } => Some(format!("{min} to {max}")),
+            ConvarKind::Bool | ConvarKind::Text => None,
+        };
+
+        let is_invalid = !value.is_empty() && !convar.kind.is_valid(value);
+
+        row![
+            column![
+                text(convar.name).font(Font::new("Roboto Mono")).size(15),
+                text(convar.description).size(12).style(tf2::text::muted),
+            ]
+            .spacing(2)
+            .width(Length::Fill),
+            hint.map(|hint| {
+                text(hint).size(12).style(if is_invalid {
+                    tf2::text::error
+                } else {
+                    tf2::text::muted
+                })
+            }),
+            input,
+        ]
+        .spacing(10)
+        .align_y(Alignment::Center)
+        .into()
+    });
+
+    let others = config
+        .entries()
+        .filter(|(name, _)| {
+            !convars
+                .iter()
+                .any(|convar| convar.name.eq_ignore_ascii_case(name))
+        })
+        .map(|(name, value)| -> Element<'a, Message> {
+            row![
+                text(name)
+                    .font(Font::new("Roboto Mono"))
+                    .size(15)
+                    .width(Length::Fill),
+                text_input("", value)
+                    .on_input(move |value| Message::ChangeCommand(name.to_string(), value))
+                    .size(15)
+                    .width(200),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        })
+        .collect::<Vec<_>>();
+
+    let others = (!others.is_empty())
+        .then(|| column![text("Other commands").size(18), column(others).spacing(8)].spacing(10));
+
+    scrollable(
+        column![column(known).spacing(12), others]
+            .spacing(25)
+            .padding(padding::horizontal(20).bottom(20)),
+    )
+    .height(Length::Fill)
+    .into()
+}
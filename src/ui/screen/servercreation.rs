@@ -5,17 +5,20 @@ use std::time::Duration;
 
 use crate::icon;
 use crate::ui::Element;
+use crate::ui::components::dropdown::dropdown;
 use crate::ui::components::notification::notification;
 use crate::ui::components::progress_stepper::stepper;
 use crate::ui::components::spinner;
-use crate::ui::games::{SOURCE_GAMES, SourceGame};
-use crate::ui::server::ServerInfo;
+use crate::ui::games::{self, SOURCE_GAMES, SourceGame};
+use crate::ui::screen::servermaps::map_list;
+use crate::ui::server::{InstallStatus, ServerInfo};
 use crate::ui::themes::{Theme, tf2};
+use iced::futures::{StreamExt, channel::mpsc, future};
 use iced::widget::text::Wrapping;
 use iced::widget::{Row, rule, scrollable, space, tooltip};
 use iced::{
     Alignment, ContentFit, Length, Task, padding,
-    task::{Straw, sipper},
+    task::{self, Straw, sipper},
     widget::{button, center, column, container, row, svg, text, text_input},
 };
 use iced::{Font, Shadow, border};
@@ -24,77 +27,40 @@ use rfd::FileHandle;
 use snafu::{ResultExt, Snafu};
 use sweeten::progress_bar;
 use sweeten::widget::transition;
-use tokio::io::{AsyncBufReadExt, BufReader};
 
-use crate::core::depotdownloader::DepotDownloader;
-use crate::core::{self, Game};
+pub use crate::core::installer::{DepotStatus, DownloadPhase, RepairReport};
 
-#[derive(Clone, Debug)]
-pub struct DepotStatus {
-    pub id: u32,
-    pub progress: f32,
-}
+use crate::core::installer::{self, InstallEvent, InstallOptions, InstallState};
+use crate::core::{
+    self, Game,
+    launch::{Region, Setting},
+    maps::{self, Map},
+};
 
 #[derive(Debug, Clone)]
 pub struct DownloadUpdate {
     pub depots: Vec<DepotStatus>,
     pub phase: DownloadPhase,
-    pub raw_line: String,
+    /// The line of output that came with the update, if any.
+    pub log: Option<String>,
+    /// The files repaired so far, when validating.
+    pub repairs: RepairReport,
 }
 
 pub struct State {
     form_page: FormSection,
     server: ServerInfo,
     is_downloading: bool,
+    /// Aborts the running download, killing the installer with it.
+    download_handle: Option<task::Handle>,
+    /// The server's entry in the list, added once its download starts.
+    listed: Option<usize>,
     download_depot_status: Vec<DepotStatus>,
     download_phase: DownloadPhase,
     download_log: Vec<String>,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
-pub enum DownloadPhase {
-    #[default]
-    Connecting,
-    ResolvingDepots,
-    Downloading,
-    Validating,
-    Done,
-}
-
-impl DownloadPhase {
-    fn advance(self, trimmed: &str) -> Self {
-        let next = if trimmed.starts_with("Connecting to Steam")
-            || trimmed.starts_with("Logging")
-            || trimmed.starts_with("Got AppInfo")
-            || trimmed.starts_with("Got depot key")
-        {
-            DownloadPhase::Connecting
-        } else if trimmed.starts_with("Processing depot")
-            || trimmed.starts_with("Downloading depot")
-            || trimmed.starts_with("Got manifest")
-            || trimmed.starts_with("Manifest ")
-            || trimmed.starts_with("Pre-allocating")
-        {
-            DownloadPhase::ResolvingDepots
-        } else if trimmed.find('%').is_some()
-            && trimmed
-                .find('%')
-                .and_then(|i| trimmed[..i].trim().parse::<f32>().ok())
-                .is_some()
-        {
-            DownloadPhase::Downloading
-        } else if trimmed.starts_with("Depot ") && trimmed.contains("Downloaded") {
-            DownloadPhase::Downloading
-        } else if trimmed.starts_with("Total downloaded") {
-            DownloadPhase::Validating
-        } else if trimmed == "Disconnected from Steam" {
-            DownloadPhase::Done
-        } else {
-            return self;
-        };
-
-        next.max(self)
-    }
+    maps: Option<Vec<Map>>,
+    map_search: String,
+    is_picking_map: bool,
 }
 
 impl Default for State {
@@ -103,9 +69,14 @@ impl Default for State {
             form_page: FormSection::GameSelection,
             server: ServerInfo::default(),
             is_downloading: false,
+            download_handle: None,
+            listed: None,
             download_depot_status: vec![],
             download_log: Vec::new(),
             download_phase: DownloadPhase::Connecting,
+            maps: None,
+            map_search: String::new(),
+            is_picking_map: false,
         }
     }
 }
@@ -114,6 +85,8 @@ impl Default for State {
 pub enum Action {
     None,
     SwitchToServerList,
+    /// Adds the server to the list, or updates its entry once it's there, and runs the task.
+    ListServer(ServerInfo, Task<Message>),
     ServerCreated(ServerInfo),
     Run(Task<Message>),
 }
@@ -133,15 +106,22 @@ pub enum Message {
     ChooseServerPath,
     ChooseServerPathFinished(Option<FileHandle>),
     DownloadServer,
+    CancelDownload,
     Downloading(Update),
     SelectMap,
-    SelectMapFinished(Option<FileHandle>),
+    MapsScanned(Result<Vec<Map>, core::Error>),
+    MapSearchUpdate(String),
+    MapChosen(String),
     MessageDescriptionUpdate(String),
     MaxPlayersUpdate(u32),
     PasswordUpdate(String),
     FinishServerCreation,
     PortUpdate(String),
     GsltUpdate(String),
+    RconPasswordUpdate(String),
+    RegionUpdate(Region),
+    TagsUpdate(String),
+    TickrateUpdate(String),
     CloseServerCreation,
 }
 
@@ -150,12 +130,24 @@ impl State {
         Self {
             server: ServerInfo {
                 max_players: 24,
+                map: games::source_game(Game::default())
+                    .map(|game_info| game_info.launch.default_map.to_string())
+                    .unwrap_or_default(),
                 ..Default::default()
             },
             ..Self::default()
         }
     }
 
+    /// The server's entry in the list, if it was added to it.
+    pub fn listed(&self) -> Option<usize> {
+        self.listed
+    }
+
+    pub fn listed_as(&mut self, id: usize) {
+        self.listed = Some(id);
+    }
+
     pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::ServerNameInput(str) => {
@@ -172,6 +164,7 @@ impl State {
             Message::ChooseServerPathFinished(file_handle) => {
                 if let Some(file) = file_handle {
                     self.server.path = file.path().to_path_buf();
+                    self.maps = None;
                 }
 
                 Action::None
@@ -184,75 +177,110 @@ impl State {
                 let server_path = self.server.path.clone();
                 let source_game = self.server.game.clone();
 
-                Action::Run(
-                    Task::sip(
-                        download_server(server_path, source_game),
-                        Update::Downloading,
-                        Update::Finished,
-                    )
-                    .map(Message::Downloading),
+                let (download, handle) = Task::sip(
+                    download_server(server_path, source_game, self.server.install.clone(), false),
+                    Update::Downloading,
+                    Update::Finished,
                 )
+                .abortable();
+
+                self.download_handle = Some(handle.abort_on_drop());
+
+                // Listed right away, so a download cut short can be resumed from the list.
+                self.server.install_status = InstallStatus::Incomplete;
+
+                Action::ListServer(self.server.clone(), download.map(Message::Downloading))
+            }
+            Message::CancelDownload => {
+                if let Some(handle) = self.download_handle.take() {
+                    handle.abort();
+                }
+
+                self.is_downloading = false;
+                self.download_depot_status.clear();
+                self.download_log.clear();
+                self.download_phase = DownloadPhase::default();
+                self.form_page = FormSection::GameSelection;
+
+                Action::None
             }
             Message::Downloading(progress) => match progress {
                 Update::Downloading(status) => {
                     self.download_depot_status = status.depots;
 
-                    self.download_log.push(status.raw_line);
+                    self.download_log.extend(status.log);
 
                     self.download_phase = status.phase;
 
                     Action::None
                 }
-                Update::Finished(_) => {
+                Update::Finished(result) => {
                     self.is_downloading = false;
+                    self.download_handle = None;
                     self.form_page = FormSection::ServerInfo;
 
-                    Action::Run(
-                        Task::future(notification(
-                            "MANNager",
-                            "The server has finished downloading",
-                            Duration::from_secs(5),
-                        ))
-                        .discard(),
+                    let body = match result {
+                        Ok(()) => {
+                            self.server.install_status = InstallStatus::Installed;
+
+                            "The server has finished downloading".to_string()
+                        }
+                        Err(err) => format!("The server couldn't be downloaded: {err}"),
+                    };
+
+                    Action::ListServer(
+                        self.server.clone(),
+                        Task::future(notification("MANNager", body, Duration::from_secs(5)))
+                            .discard(),
                     )
                 }
             },
             Message::GameChosen(source_app_id) => {
+                let previous_default_map = games::source_game(self.server.game)
+                    .map(|game_info| game_info.launch.default_map);
+
                 self.server.game = source_app_id;
+                self.maps = None;
+
+                if let Some(game_info) = games::source_game(source_app_id) {
+                    // Only replace a map that wasn't picked by the user.
+                    if self.server.map.is_empty()
+                        || previous_default_map == Some(self.server.map.as_str())
+                    {
+                        self.server.map = game_info.launch.default_map.to_string();
+                    }
+
+                    self.server.max_players =
+                        self.server.max_players.min(game_info.launch.max_players);
+                }
 
                 Action::None
             }
             Message::SelectMap => {
-                let path = self
-                    .server
-                    .path
-                    .join(&self.server.game.arg_name())
-                    .join("maps");
+                self.is_picking_map = !self.is_picking_map;
+
+                if !self.is_picking_map || self.maps.is_some() {
+                    return Action::None;
+                }
 
                 Action::Run(Task::perform(
-                    rfd::AsyncFileDialog::new()
-                        .set_title("Choose a default map")
-                        .set_directory(path)
-                        .add_filter("Source Map", &["bsp", "vpk"])
-                        .pick_file(),
-                    Message::SelectMapFinished,
+                    maps::scan(self.server.game_directory()),
+                    Message::MapsScanned,
                 ))
             }
-            Message::SelectMapFinished(file_handle) => {
-                let Some(file) = file_handle else {
-                    return Action::None;
-                };
+            Message::MapsScanned(maps) => {
+                self.maps = Some(maps.unwrap_or_default());
 
-                let Some(map) = file
-                    .path()
-                    .file_stem()
-                    .and_then(|stem| stem.to_str())
-                    .map(str::to_owned)
-                else {
-                    return Action::None;
-                };
+                Action::None
+            }
+            Message::MapSearchUpdate(search) => {
+                self.map_search = search;
 
+                Action::None
+            }
+            Message::MapChosen(map) => {
                 self.server.map = map;
+                self.is_picking_map = false;
 
                 Action::None
             }
@@ -288,6 +316,26 @@ impl State {
 
                 Action::None
             }
+            Message::RconPasswordUpdate(password) => {
+                self.server.rcon_password = (!password.is_empty()).then_some(password);
+
+                Action::None
+            }
+            Message::RegionUpdate(region) => {
+                self.server.region = Some(region);
+
+                Action::None
+            }
+            Message::TagsUpdate(tags) => {
+                self.server.tags = (!tags.is_empty()).then_some(tags);
+
+                Action::None
+            }
+            Message::TickrateUpdate(tickrate) => {
+                self.server.tickrate = tickrate.parse().ok();
+
+                Action::None
+            }
             Message::CloseServerCreation => Action::SwitchToServerList,
         }
     }
@@ -301,13 +349,43 @@ impl State {
                 &self.download_log,
                 &self.server.game,
             ),
-            FormSection::ServerInfo => info_view(&self.server),
+            FormSection::ServerInfo => info_view(&self.server, self.map_picker()),
         })
         .width(Length::Fill)
         .height(Length::Fill)
         .direction(transition::Direction::Left)
         .into()
     }
+
+    fn map_picker(&self) -> Option<Element<'_, Message>> {
+        if !self.is_picking_map {
+            return None;
+        }
+
+        let list = match &self.maps {
+            Some(maps) => map_list(
+                maps,
+                &self.map_search,
+                Message::MapSearchUpdate,
+                &self.server.map,
+                |map| {
+                    button(text("Select").size(13))
+                        .on_press(Message::MapChosen(map.name.clone()))
+                        .style(tf2::button::text)
+                        .into()
+                },
+            ),
+            None => text("Scanning...").style(tf2::text::muted).into(),
+        };
+
+        Some(
+            container(list)
+                .height(250)
+                .padding(10)
+                .style(tf2::container::main)
+                .into(),
+        )
+    }
 }
 
 fn choose_game_view<'a>(server: &ServerInfo) -> Element<'a, Message> {
@@ -574,11 +652,19 @@ fn downloading_view<'a>(
             let label = match phase {
                 DownloadPhase::Connecting => "Connecting to Steam…",
                 DownloadPhase::ResolvingDepots => "Resolving depot manifests…",
+                DownloadPhase::Verifying => "Checking installed files…",
                 DownloadPhase::Downloading => "Downloading server files…",
                 DownloadPhase::Validating => "Validating files…",
                 DownloadPhase::Done => "Download complete!",
             };
 
+            let cancel_button = (*phase != DownloadPhase::Done).then(|| {
+                button(text("Cancel").size(13))
+                    .on_press(Message::CancelDownload)
+                    .padding(padding::horizontal(10).vertical(4))
+                    .style(tf2::button::error)
+            });
+
             row![
                 if *phase != DownloadPhase::Done {
                     Element::from(spinner::Circular::new().size(14.0))
@@ -586,6 +672,8 @@ fn downloading_view<'a>(
                     icon::check().size(14).style(tf2::text::success).into()
                 },
                 text(label).size(14).style(tf2::text::secondary),
+                space::horizontal(),
+                cancel_button,
             ]
             .spacing(8)
             .align_y(Alignment::Center)
@@ -759,7 +847,10 @@ fn downloading_view<'a>(
     .into()
 }
 
-fn info_view<'a>(server: &'a ServerInfo) -> Element<'a, Message> {
+fn info_view<'a>(
+    server: &'a ServerInfo,
+    map_picker: Option<Element<'a, Message>>,
+) -> Element<'a, Message> {
     let header = {
         let title = container(column![
             text("Create server")
@@ -831,15 +922,21 @@ fn info_view<'a>(server: &'a ServerInfo) -> Element<'a, Message> {
                     .style(tf2::container::main)
             ]
             .spacing(10)
-            .align_y(Alignment::Center)
+            .align_y(Alignment::Center),
+            map_picker
         ]
         .spacing(5);
 
         let max_players_input = column![
             text("Max Players").style(tf2::text::secondary),
             container(
-                number_input(&server.max_players, 0..=100, Message::MaxPlayersUpdate)
-                    .padding(padding::vertical(10).horizontal(13))
+                number_input(
+                    &server.max_players,
+                    1..=games::source_game(server.game)
+                        .map_or(100, |game_info| game_info.launch.max_players),
+                    Message::MaxPlayersUpdate
+                )
+                .padding(padding::vertical(10).horizontal(13))
             )
         ]
         .spacing(5);
@@ -901,6 +998,84 @@ fn info_view<'a>(server: &'a ServerInfo) -> Element<'a, Message> {
         ]
         .spacing(5);
 
+        let rcon_password_input = column![
+            row![
+                text("RCON Password").style(tf2::text::secondary),
+                optional_tag(),
+                tooltip(
+                    icon::warning().style(tf2::text::secondary),
+                    text("Lets the app control the server even if it wasn't started by it.")
+                        .width(350),
+                    tooltip::Position::Top
+                )
+                .gap(10)
+                .padding(20)
+                .style(|_theme| tf2::container::tooltip(_theme))
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center),
+            text_input(
+                "RCON Password",
+                &server.rcon_password.as_deref().unwrap_or_default()
+            )
+            .on_input(Message::RconPasswordUpdate)
+            .secure(true)
+            .width(Length::Fill)
+            .padding(padding::vertical(10).horizontal(13))
+        ]
+        .spacing(5);
+
+        let region_input = column![
+            row![text("Region").style(tf2::text::secondary), optional_tag()]
+                .spacing(5)
+                .align_y(Alignment::Center),
+            container(dropdown(
+                Region::ALL,
+                server.region.as_ref(),
+                "Default",
+                Message::RegionUpdate
+            ))
+            .padding(padding::vertical(10).horizontal(13))
+            .style(tf2::container::main)
+        ]
+        .width(200)
+        .spacing(5);
+
+        let tags_input = column![
+            row![text("Tags").style(tf2::text::secondary), optional_tag()]
+                .spacing(5)
+                .align_y(Alignment::Center),
+            text_input(
+                "e.g. custom,vanilla",
+                server.tags.as_deref().unwrap_or_default()
+            )
+            .on_input(Message::TagsUpdate)
+            .width(Length::Fill)
+            .padding(padding::vertical(10).horizontal(13))
+        ]
+        .spacing(5);
+
+        let tickrate_input = games::source_game(server.game)
+            .is_some_and(|game_info| game_info.launch.supports(Setting::Tickrate))
+            .then(|| {
+                column![
+                    row![text("Tickrate").style(tf2::text::secondary), optional_tag()]
+                        .spacing(5)
+                        .align_y(Alignment::Center),
+                    text_input(
+                        "e.g. 66",
+                        &server
+                            .tickrate
+                            .map(|tickrate| tickrate.to_string())
+                            .unwrap_or_default()
+                    )
+                    .on_input(Message::TickrateUpdate)
+                    .width(150)
+                    .padding(padding::vertical(10).horizontal(13))
+                ]
+                .spacing(5)
+            });
+
         container(
             column![
                 description_input,
@@ -910,7 +1085,11 @@ fn info_view<'a>(server: &'a ServerInfo) -> Element<'a, Message> {
                 row![password_input, port_input]
                     .width(Length::Fill)
                     .spacing(20),
-                gslt_input
+                row![region_input, tags_input, tickrate_input]
+                    .width(Length::Fill)
+                    .spacing(20),
+                gslt_input,
+                rcon_password_input
             ]
             .spacing(30),
         )
@@ -954,10 +1133,14 @@ fn info_view<'a>(server: &'a ServerInfo) -> Element<'a, Message> {
     .into()
 }
 
-pub fn download_server(path: PathBuf, appid: Game) -> impl Straw<(), DownloadUpdate, Error> {
-    let install_path = path.to_str().unwrap_or("server").to_string();
-    let appid = appid.clone();
-
+/// Downloads the server files of `appid` into `path`, or updates them, with the installer
+/// picked in `options`.
+pub fn download_server(
+    path: PathBuf,
+    appid: Game,
+    options: InstallOptions,
+    validate: bool,
+) -> impl Straw<(), DownloadUpdate, Error> {
     sipper(async move |mut progress| {
         #[cfg(target_os = "windows")]
         {
@@ -970,120 +1153,46 @@ pub fn download_server(path: PathBuf, appid: Game) -> impl Straw<(), DownloadUpd
                 .await
                 .unwrap();
 
-            let _ = std::fs::write(
-                format!("{}/srcds-fix.exe", install_path),
-                srcds_fix_contents,
-            );
+            let _ = std::fs::write(path.join("srcds-fix.exe"), srcds_fix_contents);
         }
 
-        // TODO: Port SteamKit to Rust and use that instead
-        let mut depot_downloader = DepotDownloader::new("./depotdownloader")
-            .await
-            .context(ServerDownloadSnafu)?;
+        // The installer reports synchronously, so its events are forwarded from here.
+        let (sender, mut receiver) = mpsc::unbounded();
 
-        let stdout = depot_downloader
-            .download_app(&install_path, appid.into())
+        let install = async move {
+            installer::install(&options, validate, appid.into(), &path, &|event| {
+                let _ = sender.unbounded_send(event);
+            })
             .await
-            .context(ServerDownloadSnafu)?;
-
-        if let Some(stdout) = stdout {
-            let mut reader = BufReader::new(stdout).lines();
-
-            let mut depots: Vec<DepotStatus> = Vec::new();
-            let mut current_depot: Option<u32> = None;
-            let mut phase = DownloadPhase::Connecting;
-
-            while let Some(line) = reader.next_line().await.context(IoSnafu)? {
-                let trimmed = line.trim();
-
-                phase = phase.advance(trimmed);
-
-                if let Some(rest) = trimmed.strip_prefix("Processing depot ") {
-                    if let Ok(id) = rest.trim().parse::<u32>() {
-                        if !depots.iter().any(|depot| depot.id == id) {
-                            depots.push(DepotStatus { id, progress: 0.0 });
-                        }
-                        current_depot = Some(id);
-                        let _ = progress
-                            .send(DownloadUpdate {
-                                depots: depots.clone(),
-                                phase: phase.clone(),
-                                raw_line: line.clone(),
-                            })
-                            .await;
-                    }
-                    continue;
-                }
+            .context(ServerDownloadSnafu)
+        };
 
-                if trimmed.starts_with("Downloading depot ") {
-                    if let Some(id) = trimmed
-                        .split("depot ")
-                        .nth(1)
-                        .and_then(|s| s.split_whitespace().next())
-                        .and_then(|s| s.parse::<u32>().ok())
-                    {
-                        current_depot = Some(id);
-                        let _ = progress
-                            .send(DownloadUpdate {
-                                depots: depots.clone(),
-                                phase: phase.clone(),
-                                raw_line: line.clone(),
-                            })
-                            .await;
-                    }
-                    continue;
-                }
+        let forward = async {
+            let mut state = InstallState::default();
 
-                if let Some(rest) = trimmed.strip_prefix("Depot ") {
-                    if let Some(dash) = rest.find(" - Downloaded ") {
-                        if let Ok(id) = rest[..dash].trim().parse::<u32>() {
-                            if let Some(depot) = depots.iter_mut().find(|depot| depot.id == id) {
-                                depot.progress = 100.0;
-                            }
-
-                            let _ = progress
-                                .send(DownloadUpdate {
-                                    depots: depots.clone(),
-                                    phase: phase.clone(),
-                                    raw_line: line.clone(),
-                                })
-                                .await;
-                        }
-                    }
-                    continue;
-                }
+            while let Some(event) = receiver.next().await {
+                state.apply(&event);
 
-                if let Some(pct_end) = trimmed.find('%') {
-                    if let Ok(pct) = trimmed[..pct_end].trim().parse::<f32>() {
-                        if let Some(id) = current_depot {
-                            if let Some(d) = depots.iter_mut().find(|d| d.id == id) {
-                                d.progress = pct;
-                            }
-                        }
-                        let _ = progress
-                            .send(DownloadUpdate {
-                                depots: depots.clone(),
-                                phase: phase.clone(),
-                                raw_line: line.clone(),
-                            })
-                            .await;
-                    }
-                    continue;
-                }
+                let log = match event {
+                    InstallEvent::Log(line) => Some(line),
+                    _ => None,
+                };
 
-                if !trimmed.starts_with("Pre-allocating") {
-                    let _ = progress
-                        .send(DownloadUpdate {
-                            depots: depots.clone(),
-                            phase: phase.clone(),
-                            raw_line: line.clone(),
-                        })
-                        .await;
-                }
+                progress
+                    .send(DownloadUpdate {
+                        depots: state.depots.clone(),
+                        phase: state.phase.clone(),
+                        log,
+                        repairs: state.repairs.clone(),
+                    })
+                    .await;
             }
-        }
+        };
+
+        // `install` drops its sender once it's done, which ends `forward`.
+        let (result, ()) = future::join(install, forward).await;
 
-        Ok(())
+        result
     })
 }
 
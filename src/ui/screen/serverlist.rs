@@ -7,6 +7,8 @@ use std::{
     time::Duration,
 };
 
+use iced::futures::{StreamExt, channel::mpsc, future};
+use iced::task::{Straw, sipper};
 use iced::widget::{
     Space,
     text::{Ellipsis, Wrapping},
@@ -22,19 +24,19 @@ use iced::{
         space, stack, svg, text, text_input, tooltip,
     },
 };
-use rfd::FileHandle;
 use sweeten::{progress_bar, widget::drag::DragEvent};
 
 use crate::{
     icon,
     ui::{
         components::{
+            dropdown::dropdown,
             spinner::{self, Circular, easing},
             toggle_button_group::grouped_buttons,
         },
-        games::SOURCE_GAMES,
-        screen::servercreation::DownloadPhase,
-        server::{HostingMode, Server, Servers},
+        games::{self, SOURCE_GAMES},
+        screen::servercreation::{DownloadPhase, RepairReport},
+        server::{HostingMode, RestartMode, Server, ServerInfo, Servers, UpdateMode},
         themes::{Theme, tf2},
     },
 };
@@ -52,8 +54,14 @@ use crate::ui::Element;
 use crate::{
     core::{
         Game, SourceEngineVersion,
-        metamod::{MetamodBranch, MetamodDownloader},
-        sourcemod::{SourcemodBranch, SourcemodDownloader},
+        addons::{self, Addon, AddonVersions, InstallProgress, Version},
+        archive::Verification,
+        installer::{Backend, ManifestPin},
+        launch::{Region, Setting},
+        metamod::{self, MetamodBranch, MetamodDownloader, MetamodRelease},
+        sourcemod::{self, SourcemodBranch, SourcemodDownloader, SourcemodRelease},
+        updates::{self, UpdateCheck},
+        workshop::{self, Workshop},
     },
     ui::components::notification::notification,
 };
@@ -67,10 +75,15 @@ pub enum Action {
     SaveServers,
     CreateServer,
     UpdateServer(usize),
+    ValidateServer(usize),
     EditServer(usize),
     StopEditServer(usize),
+    EditConfig(usize),
+    ManageMaps(usize),
+    ManagePlugins(usize),
     RunServer(usize),
     OpenTerminal(usize),
+    AttachRcon(usize),
     StopServer(usize),
     Run(Task<Message>),
 }
@@ -85,16 +98,32 @@ pub enum Message {
 #[derive(Debug, Clone)]
 pub enum ServerMessage {
     UpdateServer,
+    CancelUpdate,
+    ValidateServer,
+    DismissRepairReport,
     StartEditServer,
     EditServer(EditServer),
+    EditConfig,
+    ManageMaps,
+    ManagePlugins,
+    OpenWorkshopItem(u64),
     StopEditServer,
     DeleteServer,
     DeleteServerFinished,
     StartServer,
     OpenTerminal,
+    AttachRcon,
     StopServer,
-    DownloadSourcemod(SourceEngineVersion, SourcemodBranch),
-    DownloadSourcemodFinished,
+    DownloadSourcemod,
+    SourcemodProgress((Addon, InstallProgress)),
+    DownloadSourcemodFinished(Result<(), Error>),
+    DismissSourcemodError,
+    AddonVersionsFound(AddonVersions),
+    LatestAddonVersionsFound(Option<AddonVersions>),
+    AddonBuildsFound(Vec<Version>, Vec<Version>),
+    BuildFound(Option<u32>),
+    CheckForUpdates,
+    UpdateChecked(Option<UpdateCheck>),
     OpenFolder,
     CopyLink,
     CopyLinkFinished(Option<String>),
@@ -106,11 +135,39 @@ pub enum ServerMessage {
 pub enum EditServer {
     ChangeName(String),
     ChangeMap,
-    ChangeMapFinished(Option<FileHandle>),
     ChangePassword(String),
     ChangePort(String),
     ChangeMaxPlayers(u32),
     ChangeGslt(String),
+    ChangeRconPassword(String),
+    ChangeRegion(Region),
+    ChangeTags(String),
+    ChangeTickrate(String),
+    ChangeRestartMode(RestartMode),
+    ChangeMaxRetries(u32),
+    ChangeRestartBackoff(u64),
+    ChangeStopWarning(u64),
+    ChangeStopTimeout(u64),
+    ChangeExtraArgs(String),
+    AddConvar,
+    ChangeConvarName(usize, String),
+    ChangeConvarValue(usize, String),
+    RemoveConvar(usize),
+    ChangeWorkshopCollection(String),
+    ChangeWorkshopMapInput(String),
+    AddWorkshopMap,
+    RemoveWorkshopMap(usize),
+    ChangeSourcemodRelease(SourcemodRelease),
+    ChangeMetamodRelease(MetamodRelease),
+    ChangeAddonVerification(Verification),
+    ChangeInstaller(Backend),
+    ChangeBeta(String),
+    ChangeBetaPassword(String),
+    ChangeManifestPinInput(String),
+    AddManifestPin,
+    RemoveManifestPin(usize),
+    ChangeLogin(String),
+    ChangeUpdateMode(UpdateMode),
 }
 
 impl ServerList {
@@ -133,6 +190,34 @@ impl ServerList {
                 _ => Action::None,
             },
             Message::ServerMessage(id, ServerMessage::UpdateServer) => Action::UpdateServer(id),
+            Message::ServerMessage(id, ServerMessage::ValidateServer) => Action::ValidateServer(id),
+            Message::ServerMessage(id, ServerMessage::CancelUpdate) => {
+                let Some(server) = servers.get_mut(id) else {
+                    return Action::None;
+                };
+
+                // Aborting drops the installer, which kills its process.
+                if let Some(handle) = server.update_handle.take() {
+                    handle.abort();
+                }
+
+                server.update_depot_status.clear();
+                server.update_phase = None;
+
+                // What was checked so far doesn't cover every file.
+                if std::mem::take(&mut server.is_validating) {
+                    server.repair_report = None;
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::DismissRepairReport) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.repair_report = None;
+                }
+
+                Action::None
+            }
             Message::ServerMessage(id, ServerMessage::DeleteServer) => {
                 let Some(server) = servers.get(id) else {
                     return Action::None;
@@ -155,10 +240,7 @@ impl ServerList {
             Message::ServerMessage(_, ServerMessage::DeleteServerFinished) => Action::SaveServers,
             Message::ServerMessage(id, ServerMessage::StartServer) => Action::RunServer(id),
             Message::ServerMessage(id, ServerMessage::StopServer) => Action::StopServer(id),
-            Message::ServerMessage(
-                id,
-                ServerMessage::DownloadSourcemod(engine_version, sourcemod_branch),
-            ) => {
+            Message::ServerMessage(id, ServerMessage::DownloadSourcemod) => {
                 // TODO: Look into adding the logic to download the correct verison here
                 // insteado of view?
                 let Some(server) = servers.get_mut(id) else {
@@ -169,40 +251,132 @@ impl ServerList {
                     return Action::None;
                 }
 
-                let path = server.info.path.clone();
-                let game = server.info.game.clone();
-                let branch = sourcemod_branch;
+                let info = server.info.clone();
+                let engine_version = addon_engine(info.game);
 
                 server.is_downloading_sourcemod = true;
+                server.addon_error = None;
 
                 Action::Run(
-                    Task::perform(
-                        async move {
-                            let _ = setup_sourcemod(path, game, branch, engine_version).await;
-                        },
-                        |_| ServerMessage::DownloadSourcemodFinished,
+                    Task::sip(
+                        setup_sourcemod(info, engine_version),
+                        ServerMessage::SourcemodProgress,
+                        ServerMessage::DownloadSourcemodFinished,
                     )
                     .map(Message::ServerMessage.with(id)),
                 )
             }
-            Message::ServerMessage(id, ServerMessage::DownloadSourcemodFinished) => {
+            Message::ServerMessage(id, ServerMessage::SourcemodProgress(progress)) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.addon_install = Some(progress);
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::DownloadSourcemodFinished(result)) => {
                 let Some(server) = servers.get_mut(id) else {
                     return Action::None;
                 };
 
                 server.is_downloading_sourcemod = false;
+                server.addon_install = None;
 
                 let server_name = server.info.name.clone();
 
+                let body = match result {
+                    Ok(()) => {
+                        format!("Sourcemod has been successfully downloaded for '{server_name}'.")
+                    }
+                    Err(err) => {
+                        server.addon_error = Some(err.to_string());
+
+                        format!("Sourcemod couldn't be installed for '{server_name}'.")
+                    }
+                };
+
+                Action::Run(Task::batch([
+                    Task::future(notification("MANNager", body, Duration::from_secs(5))).discard(),
+                    check_addons(id, server),
+                ]))
+            }
+            Message::ServerMessage(id, ServerMessage::DismissSourcemodError) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.addon_error = None;
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::AddonVersionsFound(versions)) => {
+                let Some(server) = servers.get_mut(id) else {
+                    return Action::None;
+                };
+
+                server.addon_versions = versions;
+
+                if versions.is_empty() || server.latest_addon_versions.is_some() {
+                    return Action::None;
+                }
+
+                let engine = addon_engine(server.info.game);
+
                 Action::Run(
-                    Task::future(notification(
-                        "MANNager",
-                        format!("Sourcemod has been successfully downloaded for '{server_name}'."),
-                        Duration::from_secs(5),
-                    ))
-                    .discard(),
+                    Task::perform(addons::latest(engine), |latest| {
+                        ServerMessage::LatestAddonVersionsFound(latest.ok())
+                    })
+                    .map(Message::ServerMessage.with(id)),
                 )
             }
+            Message::ServerMessage(id, ServerMessage::LatestAddonVersionsFound(latest)) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.latest_addon_versions = latest;
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::AddonBuildsFound(sourcemod, metamod)) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.sourcemod_builds = sourcemod;
+                    server.metamod_builds = metamod;
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::BuildFound(build_id)) => {
+                if let Some(server) = servers.get_mut(id) {
+                    server.build_id = build_id;
+                }
+
+                Action::None
+            }
+            Message::ServerMessage(id, ServerMessage::CheckForUpdates) => {
+                let Some(server) = servers.get(id) else {
+                    return Action::None;
+                };
+
+                Action::Run(check_updates(id, server))
+            }
+            Message::ServerMessage(id, ServerMessage::UpdateChecked(check)) => {
+                let Some(server) = servers.get_mut(id) else {
+                    return Action::None;
+                };
+
+                let Some(check) = check else {
+                    return Action::None;
+                };
+
+                server.build_id = check.installed.or(server.build_id);
+                server.update_check = Some(check);
+
+                if check.is_outdated
+                    && server.info.update_mode == UpdateMode::Automatic
+                    && !server.is_running()
+                    && server.update_phase.is_none()
+                {
+                    return Action::UpdateServer(id);
+                }
+
+                Action::None
+            }
             Message::ServerMessage(id, ServerMessage::OpenFolder) => {
                 let Some(server) = servers.get(id) else {
                     return Action::None;
@@ -221,17 +395,15 @@ impl ServerList {
                 )
             }
             Message::ServerMessage(id, ServerMessage::CopyLink) => {
-                let Some(Server {
-                    console: Some(console),
-                    hosting_mode,
-                    ..
-                }) = servers.get(id)
-                else {
+                let Some(server) = servers.get(id) else {
+                    return Action::None;
+                };
+
+                let Some(port) = server.running_port() else {
                     return Action::None;
                 };
 
-                let port = console.hosted_port;
-                let hosting_mode = hosting_mode.clone();
+                let hosting_mode = server.hosting_mode.clone();
 
                 Action::Run(
                     Task::perform(
@@ -256,7 +428,13 @@ impl ServerList {
             }
             Message::ServerMessage(id, ServerMessage::StartEditServer) => Action::EditServer(id),
             Message::ServerMessage(id, ServerMessage::EditServer(edit)) => {
-                let Some(Server { info, .. }) = servers.get_mut(id) else {
+                let Some(Server {
+                    info,
+                    workshop_map_input,
+                    manifest_pin_input,
+                    ..
+                }) = servers.get_mut(id)
+                else {
                     return Action::None;
                 };
 
@@ -266,36 +444,7 @@ impl ServerList {
 
                         Action::None
                     }
-                    EditServer::ChangeMap => {
-                        let path = PathBuf::from(info.path.display().to_string())
-                            .join(&info.game.arg_name())
-                            .join("maps");
-
-                        Action::Run(
-                            Task::perform(
-                                rfd::AsyncFileDialog::new()
-                                    .set_title("Choose a default map")
-                                    .set_directory(path)
-                                    .add_filter("Source Map", &["bsp", "vpk"])
-                                    .pick_file(),
-                                EditServer::ChangeMapFinished,
-                            )
-                            .map(ServerMessage::EditServer)
-                            .map(Message::ServerMessage.with(id)),
-                        )
-                    }
-                    EditServer::ChangeMapFinished(file_handle) => {
-                        if let Some(file) = file_handle {
-                            info.map = file
-                                .path()
-                                .file_stem()
-                                .and_then(|stem| stem.to_str())
-                                .and_then(|string| Some(string.to_string()))
-                                .unwrap()
-                        }
-
-                        Action::None
-                    }
+                    EditServer::ChangeMap => Action::ManageMaps(id),
                     EditServer::ChangePort(port) => {
                         info.port = (!port.is_empty())
                             .then(|| port.parse::<u16>().ok())
@@ -316,28 +465,209 @@ impl ServerList {
                     EditServer::ChangeGslt(token) => {
                         info.gslt = (!token.is_empty()).then_some(token);
 
+                        Action::None
+                    }
+                    EditServer::ChangeRconPassword(password) => {
+                        info.rcon_password = (!password.is_empty()).then_some(password);
+
+                        Action::None
+                    }
+                    EditServer::ChangeRegion(region) => {
+                        info.region = Some(region);
+
+                        Action::None
+                    }
+                    EditServer::ChangeTags(tags) => {
+                        info.tags = (!tags.is_empty()).then_some(tags);
+
+                        Action::None
+                    }
+                    EditServer::ChangeTickrate(tickrate) => {
+                        info.tickrate = tickrate.parse().ok();
+
+                        Action::None
+                    }
+                    EditServer::ChangeRestartMode(mode) => {
+                        info.restart_policy.mode = mode;
+
+                        Action::None
+                    }
+                    EditServer::ChangeMaxRetries(retries) => {
+                        info.restart_policy.max_retries = retries;
+
+                        Action::None
+                    }
+                    EditServer::ChangeRestartBackoff(seconds) => {
+                        info.restart_policy.backoff = Duration::from_secs(seconds);
+
+                        Action::None
+                    }
+                    EditServer::ChangeStopWarning(seconds) => {
+                        info.stop_policy.warning = Duration::from_secs(seconds);
+
+                        Action::None
+                    }
+                    EditServer::ChangeStopTimeout(seconds) => {
+                        info.stop_policy.timeout = Duration::from_secs(seconds);
+
+                        Action::None
+                    }
+                    EditServer::ChangeExtraArgs(args) => {
+                        info.extra_args = (!args.is_empty()).then_some(args);
+
+                        Action::None
+                    }
+                    EditServer::AddConvar => {
+                        info.convars.push((String::new(), String::new()));
+
+                        Action::None
+                    }
+                    EditServer::ChangeConvarName(index, name) => {
+                        if let Some((convar, _)) = info.convars.get_mut(index) {
+                            *convar = name;
+                        }
+
+                        Action::None
+                    }
+                    EditServer::ChangeConvarValue(index, value) => {
+                        if let Some((_, convar_value)) = info.convars.get_mut(index) {
+                            *convar_value = value;
+                        }
+
+                        Action::None
+                    }
+                    EditServer::RemoveConvar(index) => {
+                        if index < info.convars.len() {
+                            info.convars.remove(index);
+                        }
+
+                        Action::None
+                    }
+                    EditServer::ChangeWorkshopCollection(collection) => {
+                        info.workshop.collection = workshop::parse_id(&collection);
+
+                        Action::None
+                    }
+                    EditServer::ChangeWorkshopMapInput(input) => {
+                        *workshop_map_input = input;
+
+                        Action::None
+                    }
+                    EditServer::AddWorkshopMap => {
+                        let Some(map) = workshop::parse_id(workshop_map_input) else {
+                            return Action::None;
+                        };
+
+                        if !info.workshop.maps.contains(&map) {
+                            info.workshop.maps.push(map);
+                        }
+
+                        workshop_map_input.clear();
+
+                        Action::None
+                    }
+                    EditServer::RemoveWorkshopMap(index) => {
+                        if index < info.workshop.maps.len() {
+                            info.workshop.maps.remove(index);
+                        }
+
+                        Action::None
+                    }
+                    EditServer::ChangeSourcemodRelease(release) => {
+                        info.sourcemod = release;
+
+                        Action::None
+                    }
+                    EditServer::ChangeMetamodRelease(release) => {
+                        info.metamod = release;
+
+                        Action::None
+                    }
+                    EditServer::ChangeAddonVerification(verification) => {
+                        info.addon_verification = verification;
+
+                        Action::None
+                    }
+                    EditServer::ChangeInstaller(backend) => {
+                        info.install.backend = backend;
+
+                        Action::None
+                    }
+                    EditServer::ChangeBeta(beta) => {
+                        info.install.beta = (!beta.is_empty()).then_some(beta);
+
+                        Action::None
+                    }
+                    EditServer::ChangeBetaPassword(password) => {
+                        info.install.beta_password = (!password.is_empty()).then_some(password);
+
+                        Action::None
+                    }
+                    EditServer::ChangeManifestPinInput(input) => {
+                        *manifest_pin_input = input;
+
+                        Action::None
+                    }
+                    EditServer::AddManifestPin => {
+                        let Ok(pin) = manifest_pin_input.parse::<ManifestPin>() else {
+                            return Action::None;
+                        };
+
+                        // A depot can only be held at one manifest.
+                        info.install
+                            .manifests
+                            .retain(|pinned| pinned.depot != pin.depot);
+                        info.install.manifests.push(pin);
+
+                        manifest_pin_input.clear();
+
+                        Action::None
+                    }
+                    EditServer::RemoveManifestPin(index) => {
+                        if index < info.install.manifests.len() {
+                            info.install.manifests.remove(index);
+                        }
+
+                        Action::None
+                    }
+                    EditServer::ChangeLogin(login) => {
+                        info.install.login = (!login.is_empty()).then_some(login);
+
+                        Action::None
+                    }
+                    EditServer::ChangeUpdateMode(mode) => {
+                        info.update_mode = mode;
+
                         Action::None
                     }
                 }
             }
             Message::ServerMessage(id, ServerMessage::StopEditServer) => Action::StopEditServer(id),
+            Message::ServerMessage(id, ServerMessage::EditConfig) => Action::EditConfig(id),
+            Message::ServerMessage(id, ServerMessage::ManageMaps) => Action::ManageMaps(id),
+            Message::ServerMessage(id, ServerMessage::ManagePlugins) => Action::ManagePlugins(id),
+            Message::ServerMessage(_, ServerMessage::OpenWorkshopItem(id)) => Action::Run(
+                Task::future(async move {
+                    tokio::task::spawn_blocking(move || {
+                        let _ = open::that(workshop::item_url(id));
+                    })
+                    .await
+                })
+                .discard(),
+            ),
             Message::ServerMessage(id, ServerMessage::OpenTerminal) => Action::OpenTerminal(id),
+            Message::ServerMessage(id, ServerMessage::AttachRcon) => Action::AttachRcon(id),
             Message::ServerMessage(_, ServerMessage::DummyButtonEffectMsg) => Action::None,
             Message::ServerMessage(id, ServerMessage::HostingModeChange(mode)) => {
-                let Some(Server {
-                    hosting_mode,
-                    console,
-                    ..
-                }) = servers.get_mut(id)
-                else {
+                let Some(server) = servers.get_mut(id) else {
                     return Action::None;
                 };
 
-                if console.is_some() {
+                if server.is_running() {
                     return Action::None;
                 }
 
-                *hosting_mode = mode;
+                server.hosting_mode = mode;
 
                 Action::None
             }
@@ -441,9 +771,17 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                 .style(|theme, status| tf2::button::text(theme, status))
         }
 
+        let sourcemod_text = match server.addon_versions.sourcemod {
+            Some(version) if server.can_upgrade_addons() => {
+                format!("Sourcemod {version} (outdated)")
+            }
+            Some(version) => format!("Sourcemod {version}"),
+            None => "Download Sourcemod".to_string(),
+        };
+
         let sourcemod_label = if !is_downloading_sourcemod {
             button(row![
-                text!("Download Sourcemod"),
+                text(sourcemod_text),
                 space::horizontal(),
                 icon::right_arrow()
             ])
@@ -455,7 +793,7 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
         } else {
             button(
                 row![
-                    text!("Download Sourcemod"),
+                    text(sourcemod_text),
                     Circular::new()
                         .easing(&easing::EMPHASIZED_DECELERATE)
                         .cycle_duration(Duration::from_secs_f32(5.0))
@@ -473,28 +811,44 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
             .style(|theme, status| tf2::button::text(theme, status))
         };
 
+        let upgrade = server
+            .latest_addon_versions
+            .filter(|_| server.can_upgrade_addons())
+            .map(|latest| {
+                let version = latest
+                    .sourcemod
+                    .or(latest.metamod)
+                    .map(|version| version.to_string())
+                    .unwrap_or_default();
+
+                Item::new(
+                    menu_button(icon::download(), format!("Upgrade to {version}")).on_press_maybe(
+                        (!is_downloading_sourcemod).then_some(ServerMessage::DownloadSourcemod),
+                    ),
+                )
+            });
+
         let sourcemod_sub = Item::with_menu(
             sourcemod_label,
             Menu::new(
-                [
-                    Item::new(
-                        menu_button(icon::download(), "Stable branch").on_press_maybe(
-                            (!is_downloading_sourcemod).then_some(
-                                ServerMessage::DownloadSourcemod(
-                                    SourceEngineVersion::Source1,
-                                    SourcemodBranch::Stable,
+                upgrade
+                    .into_iter()
+                    .chain([
+                        // The builds to install are picked on the edit card.
+                        Item::new(
+                            menu_button(icon::download(), format!("Install ({})", info.sourcemod))
+                                .on_press_maybe(
+                                    (!is_downloading_sourcemod)
+                                        .then_some(ServerMessage::DownloadSourcemod),
                                 ),
-                            ),
                         ),
-                    ),
-                    Item::new(menu_button(icon::download(), "Dev branch").on_press_maybe(
-                        (!is_downloading_sourcemod).then_some(ServerMessage::DownloadSourcemod(
-                            SourceEngineVersion::Source1,
-                            SourcemodBranch::Dev,
-                        )),
-                    )),
-                ]
-                .into(),
+                        Item::new(container(rule::horizontal(1)).padding([5, 10])),
+                        Item::new(
+                            menu_button(icon::folder(), "Manage plugins")
+                                .on_press(ServerMessage::ManagePlugins),
+                        ),
+                    ])
+                    .collect(),
             )
             .offset(8.0)
             .max_width(200.0),
@@ -510,10 +864,30 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                             menu_button(icon::edit(), "Edit")
                                 .on_press(ServerMessage::StartEditServer),
                         ),
+                        Item::new(
+                            menu_button(icon::book(), "Edit server.cfg")
+                                .on_press(ServerMessage::EditConfig),
+                        ),
+                        Item::new(
+                            menu_button(icon::map(), "Maps").on_press(ServerMessage::ManageMaps),
+                        ),
                         Item::new(
                             menu_button(icon::download(), "Update Server")
                                 .on_press(ServerMessage::UpdateServer),
                         ),
+                        Item::new(
+                            menu_button(icon::refresh(), "Check for updates")
+                                .on_press(ServerMessage::CheckForUpdates),
+                        ),
+                        Item::new(menu_button(icon::check(), "Validate files").on_press_maybe(
+                            (!server.is_running()).then_some(ServerMessage::ValidateServer),
+                        )),
+                        Item::new(
+                            menu_button(icon::terminal(), "Attach via RCON").on_press_maybe(
+                                (!server.is_running() && info.rcon_password.is_some())
+                                    .then_some(ServerMessage::AttachRcon),
+                            ),
+                        ),
                         Item::new(container(rule::horizontal(1)).padding([5, 10])),
                         sourcemod_sub,
                         Item::new(container(rule::horizontal(1)).padding([5, 10])),
@@ -573,7 +947,7 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
         ]
         .spacing(5);
 
-        let console_button = server.is_running().then_some(
+        let console_button = server.console.is_some().then_some(
             button(icon::terminal().size(20).center()).on_press(ServerMessage::OpenTerminal),
         );
 
@@ -587,9 +961,15 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
         );
 
         let running_button = if !server.is_running() {
+            // An incomplete install has to be resumed before it can run.
             button(icon::start().size(20).center())
-                .on_press(ServerMessage::StartServer)
+                .on_press_maybe((!server.is_incomplete()).then_some(ServerMessage::StartServer))
                 .style(|theme, status| tf2::button::success(theme, status))
+        } else if server.is_stopping {
+            // Pressing it again kills the server right away.
+            button(icon::close().size(20).center())
+                .on_press(ServerMessage::StopServer)
+                .style(|theme, status| tf2::button::error(theme, status))
         } else {
             button(icon::stop().size(20).center())
                 .on_press(ServerMessage::StopServer)
@@ -606,6 +986,8 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
         .spacing(7)
     };
 
+    let status = server.status.as_ref();
+
     let info = {
         let can_sdr = SOURCE_GAMES
             .iter()
@@ -656,10 +1038,16 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                     container(
                         row![
                             icon::users().size(15),
-                            text(info.max_players)
-                                .ellipsis(Ellipsis::Middle)
-                                .wrapping(Wrapping::None)
-                                .size(15)
+                            text(status.map_or_else(
+                                || info.max_players.to_string(),
+                                |status| format!(
+                                    "{}/{}",
+                                    status.info.players, status.info.max_players
+                                )
+                            ))
+                            .ellipsis(Ellipsis::Middle)
+                            .wrapping(Wrapping::None)
+                            .size(15)
                         ]
                         .align_y(Alignment::Center)
                         .spacing(5)
@@ -685,16 +1073,75 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                     container(
                         row![
                             icon::map().size(15),
-                            text(info.map.as_str())
-                                .ellipsis(Ellipsis::Middle)
-                                .wrapping(Wrapping::None)
-                                .size(15)
+                            text(
+                                status.map_or(info.map.as_str(), |status| status.info.map.as_str())
+                            )
+                            .ellipsis(Ellipsis::Middle)
+                            .wrapping(Wrapping::None)
+                            .size(15)
                         ]
                         .align_y(Alignment::Center)
                         .spacing(5)
                     )
                     .padding(padding::horizontal(10).vertical(6))
                     .style(tf2::container::info_container),
+                    status.map(|status| {
+                        container(
+                            text!("{} ms", status.latency.as_millis())
+                                .wrapping(Wrapping::None)
+                                .size(15),
+                        )
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container)
+                    }),
+                    server.build_id.map(|build_id| {
+                        let build = match &info.install.beta {
+                            Some(beta) => format!("Build {build_id} ({beta})"),
+                            None => format!("Build {build_id}"),
+                        };
+
+                        container(text(build).wrapping(Wrapping::None).size(15))
+                            .padding(padding::horizontal(10).vertical(6))
+                            .style(tf2::container::info_container)
+                    }),
+                    (server.is_incomplete() && server.update_phase.is_none()).then(|| {
+                        button(
+                            row![
+                                icon::download().size(15),
+                                text("Resume download").wrapping(Wrapping::None).size(15)
+                            ]
+                            .align_y(Alignment::Center)
+                            .spacing(5),
+                        )
+                        .on_press(ServerMessage::UpdateServer)
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::button::primary)
+                    }),
+                    server
+                        .update_check
+                        .filter(|check| {
+                            check.is_outdated
+                                && server.update_phase.is_none()
+                                && !server.is_incomplete()
+                        })
+                        .map(|check| {
+                            let label = match check.latest {
+                                Some(latest) => format!("Update to {latest}"),
+                                None => "Update available".to_string(),
+                            };
+
+                            button(
+                                row![
+                                    icon::download().size(15),
+                                    text(label).wrapping(Wrapping::None).size(15)
+                                ]
+                                .align_y(Alignment::Center)
+                                .spacing(5),
+                            )
+                            .on_press(ServerMessage::UpdateServer)
+                            .padding(padding::horizontal(10).vertical(6))
+                            .style(tf2::button::primary)
+                        }),
                     info.password.as_deref().map(|password_str| {
                         container(
                             row![
@@ -730,8 +1177,12 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
 
     // TODO: Change the colors into the theme's, thus using the ones for the play/stop button
     let status_bar = {
-        let color = |theme: &Theme| {
-            if server.is_running() {
+        let is_booting = server.is_running() && status.is_none();
+
+        let color = move |theme: &Theme| {
+            if is_booting {
+                theme.colors().primary.color
+            } else if server.is_running() {
                 theme.colors().success.color
             } else {
                 theme.colors().error.color
@@ -753,31 +1204,61 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
         .width(10)
     };
 
+    let addon_error = server.addon_error.as_deref().map(|error| {
+        row![
+            icon::warning().size(14).style(tf2::text::error),
+            text!("Couldn't install SourceMod: {error}")
+                .size(13)
+                .width(Length::Fill)
+                .style(tf2::text::error),
+            button(icon::close().size(12).center())
+                .on_press(ServerMessage::DismissSourcemodError)
+                .style(tf2::button::text),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center)
+    });
+
+    let repair_report = server
+        .repair_report
+        .as_ref()
+        .map(|report| repair_report(&server.info.path, report));
+
     let card = container(
         row![
             status_bar,
-            row![server_icon, column![header_row, info].spacing(10)]
-                .align_y(Alignment::Center)
-                .spacing(20)
-                .padding(padding::vertical(12).horizontal(14)),
+            row![
+                server_icon,
+                column![header_row, info, addon_error, repair_report].spacing(10)
+            ]
+            .align_y(Alignment::Center)
+            .spacing(20)
+            .padding(padding::vertical(12).horizontal(14)),
         ]
         .align_y(Alignment::Center),
     )
     .width(Length::Fill)
     .style(tf2::container::card);
 
-    // TODO: Should put this into its own function to not clutter
     if let Some(phase) = &server.update_phase {
         let progress_section = {
             let status_label = {
                 let label = match phase {
                     DownloadPhase::Connecting => "Connecting to Steam…",
                     DownloadPhase::ResolvingDepots => "Resolving depot manifests…",
+                    DownloadPhase::Verifying => "Checking installed files…",
                     DownloadPhase::Downloading => "Downloading server files…",
                     DownloadPhase::Validating => "Validating files…",
                     DownloadPhase::Done => "Download complete!",
                 };
 
+                let cancel_button = (*phase != DownloadPhase::Done).then(|| {
+                    button(text("Cancel").size(13))
+                        .on_press(ServerMessage::CancelUpdate)
+                        .padding(padding::horizontal(10).vertical(4))
+                        .style(tf2::button::error)
+                });
+
                 row![
                     if *phase != DownloadPhase::Done {
                         Element::from(spinner::Circular::new().size(14.0))
@@ -785,6 +1266,8 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                         icon::check().size(14).style(tf2::text::success).into()
                     },
                     text(label).size(14).style(tf2::text::secondary),
+                    space::horizontal(),
+                    cancel_button,
                 ]
                 .spacing(8)
                 .align_y(Alignment::Center)
@@ -824,28 +1307,124 @@ fn card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                 .width(Length::Fill)
         };
 
-        stack![
-            card,
-            opaque(
-                center(progress_section)
-                    .width(Length::Fill)
-                    .height(Length::Fill)
-                    .padding(padding::horizontal(30).vertical(10))
-                    .style(
-                        |_theme| container::background(Color::BLACK.scale_alpha(0.8))
-                            .border(border::rounded(4))
-                    )
-            ),
+        with_overlay(card, progress_section)
+    } else if let Some((addon, progress)) = &server.addon_install {
+        let label = match progress {
+            InstallProgress::Resolving => format!("Looking up {addon}…"),
+            InstallProgress::Downloading { .. } => format!("Downloading {addon}…"),
+            InstallProgress::Verifying => format!("Verifying {addon}…"),
+            InstallProgress::Extracting => format!("Extracting {addon}…"),
+        };
+
+        let progress_section = column![
+            row![
+                spinner::Circular::new().size(14.0),
+                text(label).size(14).style(tf2::text::secondary),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+            progress_bar(0.0..=100.0, progress.percent().unwrap_or(0.0))
+                .length(Length::Fill)
+                .girth(8),
         ]
-        .into()
+        .spacing(8)
+        .width(Length::Fill);
+
+        with_overlay(card, progress_section)
     } else {
         card.into()
     }
 }
 
+/// What a validation repaired, listing the first few files.
+fn repair_report<'a>(root: &Path, report: &RepairReport) -> Element<'a, ServerMessage> {
+    const LISTED_FILES: usize = 5;
+
+    let summary = if report.is_empty() {
+        "Validation found no missing or damaged files".to_string()
+    } else {
+        format!(
+            "Validation repaired {} missing and {} damaged files",
+            report.missing.len(),
+            report.mismatched.len()
+        )
+    };
+
+    let relative = |path: &String| {
+        Path::new(path)
+            .strip_prefix(root)
+            .map_or_else(|_| path.clone(), |path| path.display().to_string())
+    };
+
+    let files = report
+        .missing
+        .iter()
+        .map(|path| format!("Missing: {}", relative(path)))
+        .chain(
+            report
+                .mismatched
+                .iter()
+                .map(|path| format!("Damaged: {}", relative(path))),
+        )
+        .take(LISTED_FILES)
+        .map(|line| {
+            text(line)
+                .font(Font::new("Roboto Mono"))
+                .size(12)
+                .wrapping(Wrapping::None)
+                .ellipsis(Ellipsis::Middle)
+                .style(tf2::text::muted)
+                .into()
+        });
+
+    let more = (report.len() > LISTED_FILES).then(|| {
+        text!("and {} more", report.len() - LISTED_FILES)
+            .size(12)
+            .style(tf2::text::muted)
+    });
+
+    column![
+        row![
+            icon::check().size(14).style(tf2::text::success),
+            text(summary).size(13).width(Length::Fill),
+            button(icon::close().size(12).center())
+                .on_press(ServerMessage::DismissRepairReport)
+                .style(tf2::button::text),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center),
+        column(files).push(more).spacing(2),
+    ]
+    .spacing(4)
+    .into()
+}
+
+/// Dims `card` and shows `content` over it, blocking its buttons.
+fn with_overlay<'a>(
+    card: impl Into<Element<'a, ServerMessage>>,
+    content: impl Into<Element<'a, ServerMessage>>,
+) -> Element<'a, ServerMessage> {
+    stack![
+        card.into(),
+        opaque(
+            center(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(padding::horizontal(30).vertical(10))
+                .style(
+                    |_theme| container::background(Color::BLACK.scale_alpha(0.8))
+                        .border(border::rounded(4))
+                )
+        ),
+    ]
+    .into()
+}
+
 fn editable_card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
     let Server { info, .. } = &server;
 
+    let launch_profile = games::source_game(info.game).map(|game_info| &game_info.launch);
+
     // TODO: Remove the unwrap.
     let server_icon = {
         let icon = get_game_image(info.game).unwrap();
@@ -879,9 +1458,13 @@ fn editable_card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                         container(
                             row![
                                 icon::users().size(15),
-                                number_input(&info.max_players, 0..100, |num| {
-                                    ServerMessage::EditServer(EditServer::ChangeMaxPlayers(num))
-                                })
+                                number_input(
+                                    &info.max_players,
+                                    1..=launch_profile.map_or(100, |profile| profile.max_players),
+                                    |num| {
+                                        ServerMessage::EditServer(EditServer::ChangeMaxPlayers(num))
+                                    }
+                                )
                                 .set_size(15)
                             ]
                             .align_y(Alignment::Center)
@@ -937,21 +1520,179 @@ fn editable_card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
                         .style(tf2::container::info_container)
                     ]
                     .spacing(20),
-                    container(
-                        row![
-                            text("GSLT").size(15),
-                            text_input("GSLT", info.gslt.as_deref().unwrap_or_default())
-                                .on_input(|token| ServerMessage::EditServer(
-                                    EditServer::ChangeGslt(token)
+                    row![
+                        container(
+                            row![
+                                text("GSLT").size(15),
+                                text_input("GSLT", info.gslt.as_deref().unwrap_or_default())
+                                    .on_input(|token| ServerMessage::EditServer(
+                                        EditServer::ChangeGslt(token)
+                                    ))
+                                    .secure(true)
+                                    .size(15)
+                            ]
+                            .spacing(5)
+                            .align_y(Alignment::Center)
+                        )
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container),
+                        container(
+                            row![
+                                text("RCON").size(15),
+                                text_input(
+                                    "RCON Password",
+                                    info.rcon_password.as_deref().unwrap_or_default()
+                                )
+                                .on_input(|password| ServerMessage::EditServer(
+                                    EditServer::ChangeRconPassword(password)
                                 ))
                                 .secure(true)
                                 .size(15)
+                            ]
+                            .spacing(5)
+                            .align_y(Alignment::Center)
+                        )
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container)
+                    ]
+                    .spacing(20),
+                    row![
+                        container(
+                            row![
+                                text("Region").size(15),
+                                dropdown(Region::ALL, info.region.as_ref(), "Default", |region| {
+                                    ServerMessage::EditServer(EditServer::ChangeRegion(region))
+                                })
+                            ]
+                            .spacing(5)
+                            .align_y(Alignment::Center)
+                        )
+                        .width(200)
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container),
+                        container(
+                            row![
+                                text("Tags").size(15),
+                                text_input("tag1,tag2", info.tags.as_deref().unwrap_or_default())
+                                    .on_input(|tags| ServerMessage::EditServer(
+                                        EditServer::ChangeTags(tags)
+                                    ))
+                                    .size(15)
+                            ]
+                            .spacing(5)
+                            .align_y(Alignment::Center)
+                        )
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container),
+                        launch_profile
+                            .is_some_and(|profile| profile.supports(Setting::Tickrate))
+                            .then(|| {
+                                container(
+                                    row![
+                                        text("Tickrate").size(15),
+                                        text_input(
+                                            "Default",
+                                            &info
+                                                .tickrate
+                                                .map_or_else(String::new, |tickrate| tickrate
+                                                    .to_string())
+                                        )
+                                        .on_input(|tickrate| ServerMessage::EditServer(
+                                            EditServer::ChangeTickrate(tickrate)
+                                        ))
+                                        .size(15)
+                                    ]
+                                    .spacing(5)
+                                    .align_y(Alignment::Center),
+                                )
+                                .padding(padding::horizontal(10).vertical(6))
+                                .style(tf2::container::info_container)
+                            })
+                    ]
+                    .spacing(20),
+                    row![
+                        text("Restart").size(15),
+                        grouped_buttons(
+                            RestartMode::ALL.map(
+                                |mode| -> (Element<'a, ServerMessage>, RestartMode) {
+                                    (text(mode.to_string()).size(13).into(), mode)
+                                }
+                            ),
+                            info.restart_policy.mode,
+                            |mode| ServerMessage::EditServer(EditServer::ChangeRestartMode(mode)),
+                            tf2::button::default,
+                        ),
+                        (info.restart_policy.mode != RestartMode::Never).then(|| {
+                            container(
+                                row![
+                                    text("Retries").size(15),
+                                    number_input(&info.restart_policy.max_retries, 0..100, |num| {
+                                        ServerMessage::EditServer(EditServer::ChangeMaxRetries(num))
+                                    })
+                                    .set_size(15),
+                                    text("Backoff (s)").size(15),
+                                    number_input(
+                                        &info.restart_policy.backoff.as_secs(),
+                                        1..600,
+                                        |num| {
+                                            ServerMessage::EditServer(
+                                                EditServer::ChangeRestartBackoff(num),
+                                            )
+                                        }
+                                    )
+                                    .set_size(15),
+                                ]
+                                .spacing(5)
+                                .align_y(Alignment::Center),
+                            )
+                            .padding(padding::horizontal(10).vertical(6))
+                            .style(tf2::container::info_container)
+                        }),
+                        container(
+                            row![
+                                icon::stop().size(15),
+                                text("Warning (s)").size(15),
+                                number_input(&info.stop_policy.warning.as_secs(), 0..600, |num| {
+                                    ServerMessage::EditServer(EditServer::ChangeStopWarning(num))
+                                })
+                                .set_size(15),
+                                text("Timeout (s)").size(15),
+                                number_input(&info.stop_policy.timeout.as_secs(), 1..600, |num| {
+                                    ServerMessage::EditServer(EditServer::ChangeStopTimeout(num))
+                                })
+                                .set_size(15),
+                            ]
+                            .spacing(5)
+                            .align_y(Alignment::Center),
+                        )
+                        .padding(padding::horizontal(10).vertical(6))
+                        .style(tf2::container::info_container)
+                    ]
+                    .spacing(20)
+                    .align_y(Alignment::Center),
+                    container(
+                        row![
+                            icon::terminal().size(15),
+                            text_input(
+                                "Extra arguments",
+                                info.extra_args.as_deref().unwrap_or_default()
+                            )
+                            .on_input(|args| ServerMessage::EditServer(
+                                EditServer::ChangeExtraArgs(args)
+                            ))
+                            .size(15)
                         ]
                         .spacing(5)
                         .align_y(Alignment::Center)
                     )
                     .padding(padding::horizontal(10).vertical(6))
-                    .style(tf2::container::info_container)
+                    .style(tf2::container::info_container),
+                    launch_profile
+                        .and_then(|profile| profile.workshop)
+                        .map(|support| workshop_items(server, support)),
+                    install_options(server),
+                    addon_releases(server),
+                    convars(&info.convars)
                 ]
                 .spacing(12)
             )
@@ -1016,21 +1757,502 @@ fn editable_card<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
     .into()
 }
 
-pub async fn setup_sourcemod(
-    path: impl AsRef<Path>,
-    game: Game,
-    branch: SourcemodBranch,
+fn install_options<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
+    let install = &server.info.install;
+
+    let branch = container(
+        row![
+            text_input(
+                "Beta branch (public)",
+                install.beta.as_deref().unwrap_or_default()
+            )
+            .on_input(|beta| ServerMessage::EditServer(EditServer::ChangeBeta(beta)))
+            .size(15),
+            install.beta.is_some().then(|| {
+                text_input(
+                    "Branch password",
+                    install.beta_password.as_deref().unwrap_or_default(),
+                )
+                .on_input(|password| {
+                    ServerMessage::EditServer(EditServer::ChangeBetaPassword(password))
+                })
+                .secure(true)
+                .size(15)
+            }),
+            (install.backend == Backend::SteamCmd).then(|| {
+                text_input(
+                    "Login (anonymous)",
+                    install.login.as_deref().unwrap_or_default(),
+                )
+                .on_input(|login| ServerMessage::EditServer(EditServer::ChangeLogin(login)))
+                .size(15)
+            }),
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center),
+    )
+    .padding(padding::horizontal(10).vertical(6))
+    .style(tf2::container::info_container);
+
+    column![
+        row![
+            text("Installer").size(15),
+            grouped_buttons(
+                Backend::ALL.map(|backend| -> (Element<'a, ServerMessage>, Backend) {
+                    (text(backend.to_string()).size(13).into(), backend)
+                }),
+                install.backend,
+                |backend| ServerMessage::EditServer(EditServer::ChangeInstaller(backend)),
+                tf2::button::default,
+            ),
+            branch,
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center),
+        row![
+            text("Updates").size(15),
+            grouped_buttons(
+                UpdateMode::ALL.map(|mode| -> (Element<'a, ServerMessage>, UpdateMode) {
+                    (text(mode.to_string()).size(13).into(), mode)
+                }),
+                server.info.update_mode,
+                |mode| ServerMessage::EditServer(EditServer::ChangeUpdateMode(mode)),
+                tf2::button::default,
+            ),
+            text("Automatic updates skip running servers")
+                .size(12)
+                .style(tf2::text::muted),
+        ]
+        .spacing(20)
+        .align_y(Alignment::Center),
+        // SteamCMD always installs the latest build of a branch.
+        (install.backend == Backend::DepotDownloader).then(|| manifest_pins(server)),
+    ]
+    .spacing(10)
+    .into()
+}
+
+fn manifest_pins<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
+    let pins = server.info.install.manifests.iter().enumerate().map(
+        |(index, pin)| -> Element<'a, ServerMessage> {
+            row![
+                text(pin.to_string())
+                    .font(Font::new("Roboto Mono"))
+                    .size(15)
+                    .width(Length::Fill),
+                button(icon::trash().size(15))
+                    .on_press(ServerMessage::EditServer(EditServer::RemoveManifestPin(
+                        index
+                    )))
+                    .style(tf2::button::text)
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .into()
+        },
+    );
+
+    let can_add = server.manifest_pin_input.parse::<ManifestPin>().is_ok();
+
+    container(
+        column![
+            row![
+                text("Pinned manifests").size(15),
+                text("Only the pinned depots are downloaded")
+                    .size(12)
+                    .style(tf2::text::muted),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center),
+            column(pins).spacing(5),
+            row![
+                text_input("<depot>:<manifest>", &server.manifest_pin_input)
+                    .on_input(|input| {
+                        ServerMessage::EditServer(EditServer::ChangeManifestPinInput(input))
+                    })
+                    .on_submit_maybe(
+                        can_add.then_some(ServerMessage::EditServer(EditServer::AddManifestPin))
+                    )
+                    .size(15),
+                button(icon::plus().size(15))
+                    .on_press_maybe(
+                        can_add.then_some(ServerMessage::EditServer(EditServer::AddManifestPin))
+                    )
+                    .style(tf2::button::text)
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+        ]
+        .spacing(5),
+    )
+    .padding(padding::horizontal(10).vertical(6))
+    .style(tf2::container::info_container)
+    .into()
+}
+
+fn addon_releases<'a>(server: &'a Server) -> Element<'a, ServerMessage> {
+    let sourcemod_releases = [
+        SourcemodRelease::Latest(SourcemodBranch::Stable),
+        SourcemodRelease::Latest(SourcemodBranch::Dev),
+    ]
+    .into_iter()
+    .chain(
+        server
+            .sourcemod_builds
+            .iter()
+            .copied()
+            .map(SourcemodRelease::Pinned),
+    );
+
+    let metamod_releases = [
+        MetamodRelease::Latest(MetamodBranch::Stable),
+        MetamodRelease::Latest(MetamodBranch::Dev),
+    ]
+    .into_iter()
+    .chain(
+        server
+            .metamod_builds
+            .iter()
+            .copied()
+            .map(MetamodRelease::Pinned),
+    );
+
+    let releases = row![
+        container(
+            row![
+                text("SourceMod").size(15),
+                dropdown(
+                    sourcemod_releases,
+                    Some(&server.info.sourcemod),
+                    "Latest stable",
+                    |release| {
+                        ServerMessage::EditServer(EditServer::ChangeSourcemodRelease(release))
+                    }
+                )
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+        )
+        .width(250)
+        .padding(padding::horizontal(10).vertical(6))
+        .style(tf2::container::info_container),
+        container(
+            row![
+                text("Metamod").size(15),
+                dropdown(
+                    metamod_releases,
+                    Some(&server.info.metamod),
+                    "Latest stable",
+                    |release| ServerMessage::EditServer(EditServer::ChangeMetamodRelease(release))
+                )
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+        )
+        .width(250)
+        .padding(padding::horizontal(10).vertical(6))
+        .style(tf2::container::info_container),
+    ]
+    .spacing(20);
+
+    let verification = server.info.addon_verification;
+
+    // AlliedMods only publishes hashes of some builds, e.g. not of the latest 1.12 ones.
+    let checksums = row![
+        text("Checksums").size(15),
+        grouped_buttons(
+            Verification::ALL.map(
+                |verification| -> (Element<'a, ServerMessage>, Verification) {
+                    (text(verification.to_string()).size(13).into(), verification)
+                }
+            ),
+            verification,
+            |verification| {
+                ServerMessage::EditServer(EditServer::ChangeAddonVerification(verification))
+            },
+            tf2::button::default,
+        ),
+        text(match verification {
+            Verification::Required => "Builds without a published or pinned hash are refused",
+            Verification::Optional =>
+                "Builds without a published or pinned hash are installed unchecked",
+        })
+        .size(12)
+        .style(tf2::text::muted),
+    ]
+    .spacing(20)
+    .align_y(Alignment::Center);
+
+    column![releases, checksums].spacing(10).into()
+}
+
+/// Looks up the SourceMod and Metamod:Source versions installed in `server`.
+pub fn check_addons(id: usize, server: &Server) -> Task<Message> {
+    Task::perform(
+        addons::installed(server.info.game_directory()),
+        ServerMessage::AddonVersionsFound,
+    )
+    .map(Message::ServerMessage.with(id))
+}
+
+/// Looks up the build installed in `server`, as SteamCMD or a DepotDownloader install
+/// recorded it.
+pub fn check_build(id: usize, server: &Server) -> Task<Message> {
+    let path = server.info.path.clone();
+    let app: u32 = server.info.game.into();
+
+    Task::perform(
+        async move {
+            updates::installed(&path, app)
+                .await
+                .and_then(|build| build.build_id)
+        },
+        ServerMessage::BuildFound,
+    )
+    .map(Message::ServerMessage.with(id))
+}
+
+/// Compares the build installed in `server` with the latest one of its branch.
+pub fn check_updates(id: usize, server: &Server) -> Task<Message> {
+    let info = server.info.clone();
+
+    Task::perform(
+        async move {
+            updates::check(
+                &info.path,
+                info.game.into(),
+                info.install.beta.as_deref(),
+                &info.install.manifests,
+            )
+            .await
+            .ok()
+        },
+        ServerMessage::UpdateChecked,
+    )
+    .map(Message::ServerMessage.with(id))
+}
+
+fn addon_engine(game: Game) -> SourceEngineVersion {
+    games::source_game(game)
+        .map(|game_info| game_info.engine.clone())
+        .unwrap_or_default()
+}
+
+/// Lists the SourceMod and Metamod:Source builds the edit card offers to pin.
+pub fn fetch_addon_builds(id: usize, server: &Server) -> Task<Message> {
+    let engine = addon_engine(server.info.game);
+
+    Task::future(async move {
+        let (sourcemod, metamod) =
+            tokio::join!(sourcemod::builds(&engine), metamod::builds(&engine));
+
+        ServerMessage::AddonBuildsFound(sourcemod.unwrap_or_default(), metamod.unwrap_or_default())
+    })
+    .map(Message::ServerMessage.with(id))
+}
+
+/// Installs the SourceMod and Metamod:Source builds picked for the server, reporting which
+/// addon it's at and how far along it is.
+pub fn setup_sourcemod(
+    info: ServerInfo,
     engine: SourceEngineVersion,
-) -> Result<(), Error> {
-    MetamodDownloader::download(&path, &game, &MetamodBranch::Stable, &engine)
-        .await
-        .context(SourcemodDownloadSnafu)?;
+) -> impl Straw<(), (Addon, InstallProgress), Error> {
+    sipper(async move |mut progress| {
+        // The downloaders report synchronously, so their updates are forwarded from here.
+        let (sender, mut receiver) = mpsc::unbounded();
 
-    SourcemodDownloader::download(&path, &game, &branch, &engine)
-        .await
-        .context(SourcemodDownloadSnafu)?;
+        let install = async move {
+            let report = |addon| {
+                let sender = sender.clone();
 
-    Ok(())
+                move |update| {
+                    let _ = sender.unbounded_send((addon, update));
+                }
+            };
+
+            // A pinned hash only makes sense for a pinned build.
+            let metamod_sha256 = matches!(info.metamod, MetamodRelease::Pinned(_))
+                .then_some(info.metamod_sha256.as_deref())
+                .flatten();
+
+            let sourcemod_sha256 = matches!(info.sourcemod, SourcemodRelease::Pinned(_))
+                .then_some(info.sourcemod_sha256.as_deref())
+                .flatten();
+
+            MetamodDownloader::download(
+                &info.path,
+                &info.game,
+                &info.metamod,
+                &engine,
+                metamod_sha256,
+                info.addon_verification,
+                &report(Addon::Metamod),
+            )
+            .await
+            .context(SourcemodDownloadSnafu)?;
+
+            SourcemodDownloader::download(
+                &info.path,
+                &info.game,
+                &info.sourcemod,
+                &engine,
+                sourcemod_sha256,
+                info.addon_verification,
+                &report(Addon::Sourcemod),
+            )
+            .await
+            .context(SourcemodDownloadSnafu)
+        };
+
+        let forward = async {
+            while let Some(update) = receiver.next().await {
+                progress.send(update).await;
+            }
+        };
+
+        // `install` drops its sender once it's done, which ends `forward`.
+        let (result, ()) = future::join(install, forward).await;
+
+        result
+    })
+}
+
+fn convars<'a>(convars: &'a [(String, String)]) -> Element<'a, ServerMessage> {
+    let rows =
+        convars
+            .iter()
+            .enumerate()
+            .map(|(index, (name, value))| -> Element<'a, ServerMessage> {
+                row![
+                    text_input("convar", name)
+                        .on_input(move |name| {
+                            ServerMessage::EditServer(EditServer::ChangeConvarName(index, name))
+                        })
+                        .size(15)
+                        .width(200),
+                    text_input("value", value)
+                        .on_input(move |value| {
+                            ServerMessage::EditServer(EditServer::ChangeConvarValue(index, value))
+                        })
+                        .size(15),
+                    button(icon::trash().size(15))
+                        .on_press(ServerMessage::EditServer(EditServer::RemoveConvar(index)))
+                        .style(tf2::button::text)
+                ]
+                .spacing(5)
+                .align_y(Alignment::Center)
+                .into()
+            });
+
+    container(
+        column![
+            row![
+                text("Convars").size(15),
+                space::horizontal(),
+                button(icon::plus().size(15))
+                    .on_press(ServerMessage::EditServer(EditServer::AddConvar))
+                    .style(tf2::button::text)
+            ]
+            .align_y(Alignment::Center),
+            column(rows).spacing(5)
+        ]
+        .spacing(5),
+    )
+    .padding(padding::horizontal(10).vertical(6))
+    .style(tf2::container::info_container)
+    .into()
+}
+
+fn workshop_items<'a>(server: &'a Server, support: Workshop) -> Element<'a, ServerMessage> {
+    let items = &server.info.workshop;
+
+    let collection = support.supports_collections().then(|| {
+        row![
+            text("Collection").size(15),
+            text_input(
+                "ID or link",
+                &items
+                    .collection
+                    .map_or_else(String::new, |collection| collection.to_string())
+            )
+            .on_input(|collection| {
+                ServerMessage::EditServer(EditServer::ChangeWorkshopCollection(collection))
+            })
+            .size(15),
+            items.collection.map(|collection| {
+                button(icon::link().size(15))
+                    .on_press(ServerMessage::OpenWorkshopItem(collection))
+                    .style(tf2::button::text)
+            })
+        ]
+        .spacing(5)
+        .align_y(Alignment::Center)
+    });
+
+    let maps = items
+        .maps
+        .iter()
+        .enumerate()
+        .map(|(index, map)| -> Element<'a, ServerMessage> {
+            row![
+                text(map.to_string())
+                    .font(Font::new("Roboto Mono"))
+                    .size(15)
+                    .width(Length::Fill),
+                // Hosted games only load the first map on their own, or start a collection on it.
+                (index == 0 && matches!(support, Workshop::Hosted { .. })).then(|| {
+                    text(if items.collection.is_some() {
+                        "Start map"
+                    } else {
+                        "Hosted"
+                    })
+                    .size(12)
+                    .style(tf2::text::muted)
+                }),
+                button(icon::link().size(15))
+                    .on_press(ServerMessage::OpenWorkshopItem(*map))
+                    .style(tf2::button::text),
+                button(icon::trash().size(15))
+                    .on_press(ServerMessage::EditServer(EditServer::RemoveWorkshopMap(
+                        index
+                    )))
+                    .style(tf2::button::text)
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .into()
+        });
+
+    let can_add = workshop::parse_id(&server.workshop_map_input).is_some();
+
+    container(
+        column![
+            text("Workshop").size(15),
+            collection,
+            column(maps).spacing(5),
+            row![
+                text_input("Map ID or link", &server.workshop_map_input)
+                    .on_input(|input| {
+                        ServerMessage::EditServer(EditServer::ChangeWorkshopMapInput(input))
+                    })
+                    .on_submit_maybe(
+                        can_add.then_some(ServerMessage::EditServer(EditServer::AddWorkshopMap))
+                    )
+                    .size(15),
+                button(icon::plus().size(15))
+                    .on_press_maybe(
+                        can_add.then_some(ServerMessage::EditServer(EditServer::AddWorkshopMap))
+                    )
+                    .style(tf2::button::text)
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+        ]
+        .spacing(5),
+    )
+    .padding(padding::horizontal(10).vertical(6))
+    .style(tf2::container::info_container)
+    .into()
 }
 
 pub fn get_game_image(game: Game) -> Option<svg::Handle> {
@@ -0,0 +1,378 @@
+use std::path::PathBuf;
+
+use iced::{
+    Alignment, Font, Length, Shadow, Task, padding,
+    widget::{button, column, container, row, scrollable, space, text, text_input},
+};
+
+use crate::{
+    core::{
+        self,
+        maps::{self, Map},
+    },
+    icon,
+    ui::{Element, server::Server, themes::tf2},
+};
+
+pub struct State {
+    pub id: usize,
+    game_directory: PathBuf,
+    mapcycle_path: PathBuf,
+    startup_map: String,
+    maps: Option<Vec<Map>>,
+    search: String,
+    mapcycle: Option<Vec<String>>,
+    is_mapcycle_dirty: bool,
+    is_saving: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Scan,
+    Scanned(Result<Vec<Map>, core::Error>),
+    MapcycleLoaded(Result<Vec<String>, core::Error>),
+    Search(String),
+    SetStartupMap(String),
+    AddToMapcycle(String),
+    MoveUp(usize),
+    MoveDown(usize),
+    RemoveFromMapcycle(usize),
+    SaveMapcycle,
+    MapcycleSaved(Result<(), core::Error>),
+    GoBack,
+}
+
+pub enum Action {
+    None,
+    GoBack,
+    ChangeStartupMap(String),
+    Run(Task<Message>),
+}
+
+impl State {
+    pub fn new(id: usize, server: &Server) -> (Self, Task<Message>) {
+        let game_directory = server.info.game_directory();
+        let mapcycle_path = maps::mapcycle_path(&game_directory, server.info.game);
+
+        let state = Self {
+            id,
+            game_directory: game_directory.clone(),
+            mapcycle_path: mapcycle_path.clone(),
+            startup_map: server.info.map.clone(),
+            maps: None,
+            search: String::new(),
+            mapcycle: None,
+            is_mapcycle_dirty: false,
+            is_saving: false,
+            error: None,
+        };
+
+        let task = Task::batch([
+            Task::perform(maps::scan(game_directory), Message::Scanned),
+            Task::perform(
+                async move { maps::load_mapcycle(&mapcycle_path).await },
+                Message::MapcycleLoaded,
+            ),
+        ]);
+
+        (state, task)
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Scan => {
+                self.maps = None;
+
+                Action::Run(Task::perform(
+                    maps::scan(self.game_directory.clone()),
+                    Message::Scanned,
+                ))
+            }
+            Message::Scanned(result) => {
+                match result {
+                    Ok(maps) => self.maps = Some(maps),
+                    Err(err) => {
+                        self.maps = Some(vec![]);
+                        self.error = Some(err.to_string());
+                    }
+                }
+
+                Action::None
+            }
+            Message::MapcycleLoaded(result) => {
+                match result {
+                    Ok(mapcycle) => self.mapcycle = Some(mapcycle),
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                Action::None
+            }
+            Message::Search(search) => {
+                self.search = search;
+
+                Action::None
+            }
+            Message::SetStartupMap(map) => {
+                self.startup_map = map.clone();
+
+                Action::ChangeStartupMap(map)
+            }
+            Message::AddToMapcycle(map) => {
+                if let Some(mapcycle) = &mut self.mapcycle {
+                    mapcycle.push(map);
+                    self.is_mapcycle_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::MoveUp(index) => {
+                if let Some(mapcycle) = &mut self.mapcycle
+                    && index > 0
+                    && index < mapcycle.len()
+                {
+                    mapcycle.swap(index, index - 1);
+                    self.is_mapcycle_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::MoveDown(index) => {
+                if let Some(mapcycle) = &mut self.mapcycle
+                    && index + 1 < mapcycle.len()
+                {
+                    mapcycle.swap(index, index + 1);
+                    self.is_mapcycle_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::RemoveFromMapcycle(index) => {
+                if let Some(mapcycle) = &mut self.mapcycle
+                    && index < mapcycle.len()
+                {
+                    mapcycle.remove(index);
+                    self.is_mapcycle_dirty = true;
+                }
+
+                Action::None
+            }
+            Message::SaveMapcycle => {
+                let Some(mapcycle) = self.mapcycle.clone() else {
+                    return Action::None;
+                };
+
+                let path = self.mapcycle_path.clone();
+
+                self.is_saving = true;
+
+                Action::Run(Task::perform(
+                    async move { maps::save_mapcycle(&path, &mapcycle).await },
+                    Message::MapcycleSaved,
+                ))
+            }
+            Message::MapcycleSaved(result) => {
+                self.is_saving = false;
+
+                match result {
+                    Ok(()) => {
+                        self.is_mapcycle_dirty = false;
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+
+                Action::None
+            }
+            Message::GoBack => Action::GoBack,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let header = container(
+            row![
+                button(icon::left_arrow().size(20).center()).on_press(Message::GoBack),
+                text("Maps")
+                    .font(Font::new("TF2 Build"))
+                    .size(30)
+                    .line_height(1.0),
+                space::horizontal(),
+                row![icon::map().size(15), text(&self.startup_map).size(15)]
+                    .spacing(5)
+                    .align_y(Alignment::Center),
+            ]
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .padding(padding::all(10)),
+        )
+        .style(|theme| {
+            let mut style = tf2::container::card(theme);
+
+            style.border = style.border.rounded(0);
+            style.shadow = Shadow::default();
+
+            style
+        });
+
+        let error = self.error.as_ref().map(|error| {
+            container(text(error).style(tf2::text::error)).padding(padding::horizontal(20))
+        });
+
+        let available = column![
+            row![
+                text("Available").size(18),
+                space::horizontal(),
+                button(text("Rescan").size(13))
+                    .on_press_maybe(self.maps.is_some().then_some(Message::Scan))
+                    .style(tf2::button::text),
+            ]
+            .align_y(Alignment::Center),
+            match &self.maps {
+                Some(maps) => map_list(
+                    maps,
+                    &self.search,
+                    Message::Search,
+                    &self.startup_map,
+                    |map| {
+                        row![
+                            button(text("Start on").size(13))
+                                .on_press(Message::SetStartupMap(map.name.clone()))
+                                .style(tf2::button::text),
+                            button(icon::plus().size(15))
+                                .on_press_maybe(
+                                    self.mapcycle
+                                        .is_some()
+                                        .then(|| Message::AddToMapcycle(map.name.clone()))
+                                )
+                                .style(tf2::button::text),
+                        ]
+                        .spacing(5)
+                        .into()
+                    }
+                ),
+                None => text("Scanning...").style(tf2::text::muted).into(),
+            }
+        ]
+        .spacing(10)
+        .width(Length::FillPortion(3));
+
+        let mapcycle = column![
+            row![
+                text("Map cycle").size(18),
+                space::horizontal(),
+                button(text(if self.is_saving { "Saving..." } else { "Save" }))
+                    .on_press_maybe(
+                        (self.is_mapcycle_dirty && !self.is_saving)
+                            .then_some(Message::SaveMapcycle)
+                    )
+                    .style(tf2::button::primary),
+            ]
+            .align_y(Alignment::Center),
+            text(self.mapcycle_path.to_string_lossy())
+                .size(12)
+                .style(tf2::text::muted),
+            match &self.mapcycle {
+                Some(mapcycle) => mapcycle_list(mapcycle, self.maps.as_deref()),
+                None => text("Loading...").style(tf2::text::muted).into(),
+            }
+        ]
+        .spacing(10)
+        .width(Length::FillPortion(2));
+
+        column![
+            header,
+            error,
+            row![available, mapcycle]
+                .spacing(30)
+                .padding(padding::horizontal(20).bottom(20))
+                .height(Length::Fill)
+        ]
+        .spacing(10)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+fn mapcycle_list<'a>(mapcycle: &'a [String], maps: Option<&'a [Map]>) -> Element<'a, Message> {
+    let last_index = mapcycle.len().saturating_sub(1);
+
+    let rows = mapcycle
+        .iter()
+        .enumerate()
+        .map(|(index, map)| -> Element<'a, Message> {
+            let is_missing = maps.is_some_and(|maps| maps.iter().all(|found| found.name != *map));
+
+            row![
+                text(map).size(15).width(Length::Fill).style(if is_missing {
+                    tf2::text::error
+                } else {
+                    tf2::text::default
+                }),
+                button(icon::up_arrow().size(15))
+                    .on_press_maybe((index > 0).then_some(Message::MoveUp(index)))
+                    .style(tf2::button::text),
+                button(icon::down_arrow().size(15))
+                    .on_press_maybe((index < last_index).then_some(Message::MoveDown(index)))
+                    .style(tf2::button::text),
+                button(icon::trash().size(15))
+                    .on_press(Message::RemoveFromMapcycle(index))
+                    .style(tf2::button::text),
+            ]
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .into()
+        });
+
+    scrollable(column(rows).spacing(5))
+        .height(Length::Fill)
+        .into()
+}
+
+/// A searchable list of `maps`, with `selected` highlighted and `actions` shown beside each map.
+pub fn map_list<'a, Message: Clone + 'a>(
+    maps: &'a [Map],
+    search: &str,
+    on_search: impl Fn(String) -> Message + 'a,
+    selected: &str,
+    actions: impl Fn(&'a Map) -> Element<'a, Message>,
+) -> Element<'a, Message> {
+    let search_input = text_input("Search maps", search)
+        .on_input(on_search)
+        .size(15);
+
+    let search = search.to_lowercase();
+
+    let rows = maps
+        .iter()
+        .filter(|map| map.name.to_lowercase().contains(&search))
+        .map(|map| -> Element<'a, Message> {
+            row![
+                text(&map.name)
+                    .size(15)
+                    .width(Length::Fill)
+                    .style(if map.name == selected {
+                        tf2::text::primary
+                    } else {
+                        tf2::text::default
+                    }),
+                text(map.source.to_string())
+                    .size(12)
+                    .style(tf2::text::muted),
+                actions(map),
+            ]
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .into()
+        })
+        .collect::<Vec<_>>();
+
+    let list: Element<'a, Message> = if rows.is_empty() {
+        text("No maps found").style(tf2::text::muted).into()
+    } else {
+        scrollable(column(rows).spacing(5))
+            .height(Length::Fill)
+            .into()
+    };
+
+    column![search_input, list].spacing(10).into()
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+
+use iced::{
+    Alignment, Font, Length, Shadow, Task, padding,
+    widget::{button, column, container, row, scrollable, space, text},
+};
+use rfd::FileHandle;
+
+use crate::{
+    core::{
+        self,
+        plugins::{self, Plugin},
+    },
+    icon,
+    ui::{Element, server::Server, themes::tf2},
+};
+
+pub struct State {
+    pub id: usize,
+    game_directory: PathBuf,
+    plugins: Option<Vec<Plugin>>,
+    is_installing: bool,
+    /// The outcome of the last install, or of the last failed operation.
+    status: Option<Result<String, String>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Refresh,
+    Listed(Result<Vec<Plugin>, core::Error>),
+    SetEnabled(Plugin, bool),
+    Remove(Plugin),
+    Changed(Result<(), core::Error>),
+    Install,
+    InstallFileChosen(Option<FileHandle>),
+    Installed(Result<Vec<String>, core::Error>),
+    Browse,
+    GoBack,
+}
+
+pub enum Action {
+    None,
+    GoBack,
+    Browse,
+    Run(Task<Message>),
+}
+
+impl State {
+    pub fn new(id: usize, server: &Server) -> (Self, Task<Message>) {
+        let game_directory = server.info.game_directory();
+
+        let state = Self {
+            id,
+            game_directory: game_directory.clone(),
+            plugins: None,
+            is_installing: false,
+            status: None,
+        };
+
+        (
+            state,
+            Task::perform(plugins::list(game_directory), Message::Listed),
+        )
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Refresh => Action::Run(self.refresh()),
+            Message::Listed(result) => {
+                match result {
+                    Ok(plugins) => self.plugins = Some(plugins),
+                    Err(err) => {
+                        self.plugins = Some(vec![]);
+                        self.status = Some(Err(err.to_string()));
+                    }
+                }
+
+                Action::None
+            }
+            Message::SetEnabled(plugin, is_enabled) => Action::Run(Task::perform(
+                plugins::set_enabled(self.game_directory.clone(), plugin, is_enabled),
+                Message::Changed,
+            )),
+            Message::Remove(plugin) => Action::Run(Task::perform(
+                plugins::remove(self.game_directory.clone(), plugin),
+                Message::Changed,
+            )),
+            Message::Changed(result) => {
+                if let Err(err) = result {
+                    self.status = Some(Err(err.to_string()));
+                }
+
+                Action::Run(self.refresh())
+            }
+            Message::Install => Action::Run(Task::perform(
+                rfd::AsyncFileDialog::new()
+                    .set_title("Pick a plugin to install")
+                    .add_filter("SourceMod plugin", &["smx", "zip", "gz", "tgz"])
+                    .pick_file(),
+                Message::InstallFileChosen,
+            )),
+            Message::InstallFileChosen(file_handle) => {
+                let Some(file) = file_handle else {
+                    return Action::None;
+                };
+
+                self.is_installing = true;
+
+                Action::Run(Task::perform(
+                    plugins::install(self.game_directory.clone(), file.path().to_path_buf()),
+                    Message::Installed,
+                ))
+            }
+            Message::Installed(result) => {
+                self.is_installing = false;
+
+                self.status = Some(match result {
+                    Ok(installed) => Ok(format!("Installed {}", installed.join(", "))),
+                    Err(err) => Err(err.to_string()),
+                });
+
+                Action::Run(self.refresh())
+            }
+            Message::Browse => Action::Browse,
+            Message::GoBack => Action::GoBack,
+        }
+    }
+
+    fn refresh(&mut self) -> Task<Message> {
+        self.plugins = None;
+
+        Task::perform(plugins::list(self.game_directory.clone()), Message::Listed)
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let header = container(
+            row![
+                button(icon::left_arrow().size(20).center()).on_press(Message::GoBack),
+                text("Plugins")
+                    .font(Font::new("TF2 Build"))
+                    .size(30)
+                    .line_height(1.0),
+                space::horizontal(),
+                button(text("Refresh").size(13))
+                    .on_press_maybe(self.plugins.is_some().then_some(Message::Refresh))
+                    .style(tf2::button::text),
+                button(text("Browse"))
+                    .on_press(Message::Browse)
+                    .style(tf2::button::default),
+                button(text(if self.is_installing {
+                    "Installing..."
+                } else {
+                    "Install"
+                }))
+                .on_press_maybe((!self.is_installing).then_some(Message::Install))
+                .style(tf2::button::primary),
+            ]
+            .spacing(15)
+            .align_y(Alignment::Center)
+            .padding(padding::all(10)),
+        )
+        .style(|theme| {
+            let mut style = tf2::container::card(theme);
+
+            style.border = style.border.rounded(0);
+            style.shadow = Shadow::default();
+
+            style
+        });
+
+        let status = self.status.as_ref().map(|status| {
+            let status = match status {
+                Ok(message) => text(message).style(tf2::text::muted),
+                Err(error) => text(error).style(tf2::text::error),
+            };
+
+            container(status).padding(padding::horizontal(20))
+        });
+
+        let body: Element<'_, Message> = match &self.plugins {
+            None => text("Loading...").style(tf2::text::muted).into(),
+            Some(plugins) if plugins.is_empty() => {
+                let hint = if plugins::is_sourcemod_installed(&self.game_directory) {
+                    "No plugins installed"
+                } else {
+                    "SourceMod isn't installed. Install it from the server's menu first."
+                };
+
+                text(hint).style(tf2::text::muted).into()
+            }
+            Some(plugins) => scrollable(column(plugins.iter().map(plugin_row)).spacing(8))
+                .height(Length::Fill)
+                .into(),
+        };
+
+        column![
+            header,
+            status,
+            container(body)
+                .padding(padding::horizontal(20).bottom(20))
+                .height(Length::Fill)
+        ]
+        .spacing(10)
+        .height(Length::Fill)
+        .into()
+    }
+}
+
+fn plugin_row(plugin: &Plugin) -> Element<'_, Message> {
+    row![
+        column![
+            text(plugin.name()).size(15).style(if plugin.is_enabled {
+                tf2::text::default
+            } else {
+                tf2::text::muted
+            }),
+            text(plugin.path.to_string_lossy())
+                .font(Font::new("Roboto Mono"))
+                .size(12)
+                .style(tf2::text::muted),
+        ]
+        .spacing(2)
+        .width(Length::Fill),
+        button(
+            text(if plugin.is_enabled {
+                "Disable"
+            } else {
+                "Enable"
+            })
+            .size(13)
+        )
+        .on_press(Message::SetEnabled(plugin.clone(), !plugin.is_enabled))
+        .style(tf2::button::text),
+        button(icon::trash().size(15))
+            .on_press(Message::Remove(plugin.clone()))
+            .style(tf2::button::text),
+    ]
+    .spacing(10)
+    .align_y(Alignment::Center)
+    .into()
+}
@@ -1,17 +1,37 @@
 use std::{
+    net::{Ipv4Addr, SocketAddr},
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     str::FromStr,
+    time::Duration,
 };
 
 use decoder::Value;
+use iced::task;
+use portforwarder_rs::port_forwarder::PortMappingProtocol;
 
 use crate::{
-    core::Game,
-    ui::screen::{
-        serverboot::Console,
-        servercreation::{DepotStatus, DownloadPhase},
-        serverlist::Error,
+    core::{
+        self, Game, a2s,
+        addons::{Addon, AddonVersions, InstallProgress, Version},
+        archive::Verification,
+        installer::InstallOptions,
+        launch::{self, LaunchOptions, Region},
+        metamod::MetamodRelease,
+        portforwarder::{PortForwarder, PortForwarderIP},
+        process,
+        rcon::RconClient,
+        sourcemod::SourcemodRelease,
+        updates::UpdateCheck,
+        workshop::WorkshopItems,
+    },
+    ui::{
+        games,
+        screen::{
+            serverboot::{Connection, Console},
+            servercreation::{DepotStatus, DownloadPhase, RepairReport},
+            serverlist::Error,
+        },
     },
 };
 
@@ -42,6 +62,29 @@ impl Servers {
         Ok(())
     }
 
+    /// The processes of the servers that are currently running, to be written to the runtime state file.
+    pub fn runtime_state(&self) -> RuntimeState {
+        RuntimeState {
+            processes: self.iter().filter_map(Server::running_process).collect(),
+        }
+    }
+
+    /// Marks the servers whose process from a previous session is still alive as running.
+    ///
+    /// A process that only shares the PID, after a reboot or once PIDs wrapped around, is left alone.
+    pub fn restore(&mut self, state: &RuntimeState) {
+        for server in self.iter_mut() {
+            server.detached = state
+                .get(&server.info.path)
+                .filter(|running| running.is_alive())
+                .cloned();
+
+            if let Some(running) = &server.detached {
+                server.hosting_mode = running.hosting_mode;
+            }
+        }
+    }
+
     pub fn encode(&self) -> Value {
         use decoder::encode::{map, sequence};
 
@@ -91,10 +134,42 @@ pub struct Server {
     pub info: ServerInfo,
     pub console: Option<Console>,
     pub is_downloading_sourcemod: bool,
+    /// The step the SourceMod install is at, once it has started reporting.
+    pub addon_install: Option<(Addon, InstallProgress)>,
+    /// Why the last SourceMod install failed, shown on the card until dismissed.
+    pub addon_error: Option<String>,
     pub update_depot_status: Vec<DepotStatus>,
     pub update_phase: Option<DownloadPhase>,
+    /// Aborts the running update, killing the installer with it.
+    pub update_handle: Option<task::Handle>,
+    /// Set while the running update also checks the installed files.
+    pub is_validating: bool,
+    /// What the last validation repaired, shown on the card until dismissed.
+    pub repair_report: Option<RepairReport>,
     pub is_editing: bool,
     pub hosting_mode: HostingMode,
+    /// A server process left running by a previous session of the app.
+    pub detached: Option<RunningProcess>,
+    pub status: Option<a2s::Status>,
+    /// Automatic restarts done since the server was last started by hand.
+    pub restart_attempts: u32,
+    /// Set while the server is being asked to quit, so its exit isn't treated as a crash.
+    pub is_stopping: bool,
+    /// The Workshop map being typed in on the edit card, before it's added.
+    pub workshop_map_input: String,
+    /// The `<depot>:<manifest>` pin being typed in on the edit card, before it's added.
+    pub manifest_pin_input: String,
+    /// The build installed in the game folder, when SteamCMD left a record of it.
+    pub build_id: Option<u32>,
+    /// The last comparison with the latest build of the server's branch.
+    pub update_check: Option<UpdateCheck>,
+    /// The SourceMod and Metamod:Source versions found in the game folder.
+    pub addon_versions: AddonVersions,
+    /// The latest stable versions, once looked up.
+    pub latest_addon_versions: Option<AddonVersions>,
+    /// The builds the edit card offers to pin, listed when it's first opened.
+    pub sourcemod_builds: Vec<Version>,
+    pub metamod_builds: Vec<Version>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -104,17 +179,34 @@ pub enum HostingMode {
     Upnp,
 }
 
+impl HostingMode {
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::string;
+
+        match string(value)?.as_str() {
+            "local" => Ok(HostingMode::Local),
+            "sdr" => Ok(HostingMode::Sdr),
+            "upnp" => Ok(HostingMode::Upnp),
+            mode => Err(decoder::Error::Custom(format!(
+                "'{mode}' is not a valid hosting mode"
+            ))),
+        }
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::string;
+
+        string(match self {
+            HostingMode::Local => "local",
+            HostingMode::Sdr => "sdr",
+            HostingMode::Upnp => "upnp",
+        })
+    }
+}
+
 impl Server {
     pub fn new() -> Self {
-        Self {
-            info: ServerInfo::default(),
-            console: None,
-            is_downloading_sourcemod: false,
-            update_depot_status: vec![],
-            update_phase: None,
-            is_editing: false,
-            hosting_mode: HostingMode::Local,
-        }
+        Self::with_info(ServerInfo::default())
     }
 
     pub fn with_info(info: ServerInfo) -> Self {
@@ -122,20 +214,161 @@ impl Server {
             info,
             console: None,
             is_downloading_sourcemod: false,
+            addon_install: None,
+            addon_error: None,
             update_depot_status: vec![],
             update_phase: None,
+            update_handle: None,
+            is_validating: false,
+            repair_report: None,
             is_editing: false,
             hosting_mode: HostingMode::Local,
+            detached: None,
+            status: None,
+            restart_attempts: 0,
+            is_stopping: false,
+            workshop_map_input: String::new(),
+            manifest_pin_input: String::new(),
+            build_id: None,
+            update_check: None,
+            addon_versions: AddonVersions::default(),
+            latest_addon_versions: None,
+            sourcemod_builds: vec![],
+            metamod_builds: vec![],
         }
     }
 
     pub fn is_running(&self) -> bool {
-        self.console.is_some()
+        self.console
+            .as_ref()
+            .is_some_and(|console| console.exited.is_none())
+            || self.detached.is_some()
+    }
+
+    /// The port the server is currently listening on.
+    pub fn running_port(&self) -> Option<u16> {
+        self.console
+            .as_ref()
+            .map(|console| console.hosted_port)
+            .or(self.detached.as_ref().map(|running| running.port))
+    }
+
+    pub fn running_process(&self) -> Option<RunningProcess> {
+        if let Some(running) = &self.detached {
+            return Some(running.clone());
+        }
+
+        let console = self.console.as_ref()?;
+
+        if console.connection != Connection::Process {
+            return None;
+        }
+
+        Some(RunningProcess {
+            path: self.info.path.clone(),
+            pid: console.pid?,
+            started: console.started?,
+            port: console.hosted_port,
+            hosting_mode: self.hosting_mode,
+        })
+    }
+
+    /// Where the server writes its console output when launched with `-condebug`.
+    pub fn console_log_path(&self) -> PathBuf {
+        self.info.game_directory().join("console.log")
+    }
+
+    /// The config the server runs on startup.
+    pub fn server_cfg_path(&self) -> PathBuf {
+        self.info.game_directory().join("cfg").join("server.cfg")
+    }
+
+    /// Whether a newer stable SourceMod or Metamod:Source than the installed one is out.
+    ///
+    /// Pinned addons are left out, they're meant to stay on their build.
+    pub fn can_upgrade_addons(&self) -> bool {
+        let unpinned = AddonVersions {
+            sourcemod: self
+                .addon_versions
+                .sourcemod
+                .filter(|_| !matches!(self.info.sourcemod, SourcemodRelease::Pinned(_))),
+            metamod: self
+                .addon_versions
+                .metamod
+                .filter(|_| !matches!(self.info.metamod, MetamodRelease::Pinned(_))),
+        };
+
+        self.latest_addon_versions
+            .is_some_and(|latest| unpinned.is_outdated(&latest))
     }
 
     pub fn is_updating(&self) -> bool {
         self.update_phase.is_some()
     }
+
+    /// Whether the server's download was left unfinished.
+    pub fn is_incomplete(&self) -> bool {
+        self.info.install_status == InstallStatus::Incomplete
+    }
+
+    /// Returns the executable and the arguments needed to launch the server on `port`.
+    pub fn launch_command(&self, port: u16) -> Option<(PathBuf, Vec<String>)> {
+        let info = &self.info;
+
+        let game_info = games::source_game(info.game)?;
+
+        let binary_path = info.path.join(&game_info.executable_path);
+
+        let options = LaunchOptions {
+            hostname: info.name.clone(),
+            map: info.map.clone(),
+            max_players: info.max_players,
+            port,
+            password: info.password.clone(),
+            has_motd: info
+                .description
+                .as_ref()
+                .is_some_and(|description| !description.trim().is_empty()),
+            region: info.region,
+            tags: info.tags.clone(),
+            tickrate: info.tickrate,
+            gslt: info.gslt.clone(),
+            rcon_password: info.rcon_password.clone(),
+            fake_ip: matches!(self.hosting_mode, HostingMode::Sdr),
+            extra_args: info
+                .extra_args
+                .as_deref()
+                .map(launch::split_args)
+                .unwrap_or_default(),
+            convars: info.convars.clone(),
+            workshop: info.workshop.clone(),
+        };
+
+        Some((binary_path, options.args(&game_info.launch)))
+    }
+
+    /// Writes the description where the server reads its MOTD from, see [`launch::MOTD_FILE_NAME`].
+    pub fn write_motd(&self) -> std::io::Result<()> {
+        let Some(description) = &self.info.description else {
+            return Ok(());
+        };
+
+        std::fs::write(
+            self.info.game_directory().join(launch::MOTD_FILE_NAME),
+            description,
+        )
+    }
+
+    /// Moves the `-condebug` log of the last run aside, so it only ever holds the current and
+    /// the previous one instead of growing forever.
+    pub fn rotate_console_log(&self) -> std::io::Result<()> {
+        let path = self.console_log_path();
+
+        match std::fs::rename(&path, path.with_extension("log.old")) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -149,11 +382,45 @@ pub struct ServerInfo {
     pub password: Option<String>,
     pub port: Option<u16>,
     pub gslt: Option<String>,
+    pub rcon_password: Option<String>,
+    pub region: Option<Region>,
+    /// Comma separated, as `sv_tags` expects them.
+    pub tags: Option<String>,
+    pub tickrate: Option<u32>,
+    pub restart_policy: RestartPolicy,
+    pub stop_policy: StopPolicy,
+    /// Extra command line arguments, split like a shell would.
+    pub extra_args: Option<String>,
+    /// `+convar value` overrides, applied after everything else.
+    pub convars: Vec<(String, String)>,
+    pub workshop: WorkshopItems,
+    /// The SourceMod and Metamod:Source builds installed from the server's menu.
+    pub sourcemod: SourcemodRelease,
+    pub metamod: MetamodRelease,
+    /// The SHA-256 pinned builds are checked against, instead of the published one.
+    pub sourcemod_sha256: Option<String>,
+    pub metamod_sha256: Option<String>,
+    /// Whether builds without a published or pinned hash are installed.
+    pub addon_verification: Verification,
+    /// How the server's files are downloaded and updated.
+    pub install: InstallOptions,
+    pub update_mode: UpdateMode,
+    /// Whether the server's files were downloaded in full.
+    pub install_status: InstallStatus,
 }
 
 impl ServerInfo {
+    /// The folder of the game inside the server, e.g. `tf` or `game/csgo`.
+    pub fn game_directory(&self) -> PathBuf {
+        let engine = games::source_game(self.game)
+            .map(|game_info| game_info.engine.clone())
+            .unwrap_or_default();
+
+        self.game.directory(&self.path, &engine)
+    }
+
     pub fn decode(value: Value) -> Result<Self, decoder::Error> {
-        use decoder::decode::{map, string, u16, u32};
+        use decoder::decode::{map, sequence, string, u16, u32};
 
         let mut server = map(value)?;
 
@@ -167,11 +434,50 @@ impl ServerInfo {
             password: server.optional("password", string)?,
             port: server.optional("port", u16)?,
             gslt: server.optional("gslt", string)?,
+            rcon_password: server.optional("rcon_password", string)?,
+            region: server.optional("region", u32)?.and_then(Region::from_code),
+            tags: server.optional("tags", string)?,
+            tickrate: server.optional("tickrate", u32)?,
+            restart_policy: server
+                .optional("restart_policy", RestartPolicy::decode)?
+                .unwrap_or_default(),
+            stop_policy: server
+                .optional("stop_policy", StopPolicy::decode)?
+                .unwrap_or_default(),
+            extra_args: server.optional("extra_args", string)?,
+            convars: server
+                .optional("convars", sequence(decode_convar))?
+                .unwrap_or_default(),
+            workshop: server
+                .optional("workshop", decode_workshop)?
+                .unwrap_or_default(),
+            sourcemod: server
+                .optional("sourcemod", decode_release)?
+                .unwrap_or_default(),
+            metamod: server
+                .optional("metamod", decode_release)?
+                .unwrap_or_default(),
+            sourcemod_sha256: server.optional("sourcemod_sha256", string)?,
+            metamod_sha256: server.optional("metamod_sha256", string)?,
+            addon_verification: server
+                .optional("addon_verification", string)?
+                .map(|verification| verification.parse().map_err(decoder::Error::Custom))
+                .transpose()?
+                .unwrap_or_default(),
+            install: server
+                .optional("install", InstallOptions::decode)?
+                .unwrap_or_default(),
+            update_mode: server
+                .optional("update_mode", UpdateMode::decode)?
+                .unwrap_or_default(),
+            install_status: server
+                .optional("install_status", InstallStatus::decode)?
+                .unwrap_or_default(),
         })
     }
 
     pub fn encode(&self) -> Value {
-        use decoder::encode::{map, optional, string, u16, u32};
+        use decoder::encode::{map, optional, sequence, string, u16, u32};
 
         map([
             ("name", string(&self.name)),
@@ -183,7 +489,596 @@ impl ServerInfo {
             ("password", optional(string, self.password.clone())),
             ("port", optional(u16, self.port)),
             ("gslt", optional(string, self.gslt.clone())),
+            (
+                "rcon_password",
+                optional(string, self.rcon_password.clone()),
+            ),
+            (
+                "region",
+                optional(u32, self.region.map(|region| region.code())),
+            ),
+            ("tags", optional(string, self.tags.clone())),
+            ("tickrate", optional(u32, self.tickrate)),
+            ("restart_policy", self.restart_policy.encode()),
+            ("stop_policy", self.stop_policy.encode()),
+            ("extra_args", optional(string, self.extra_args.clone())),
+            ("convars", sequence(encode_convar, &self.convars)),
+            ("workshop", encode_workshop(&self.workshop)),
+            ("sourcemod", string(&self.sourcemod.encode())),
+            ("metamod", string(&self.metamod.encode())),
+            (
+                "sourcemod_sha256",
+                optional(string, self.sourcemod_sha256.clone()),
+            ),
+            (
+                "metamod_sha256",
+                optional(string, self.metamod_sha256.clone()),
+            ),
+            (
+                "addon_verification",
+                string(self.addon_verification.encode()),
+            ),
+            ("install", self.install.encode()),
+            ("update_mode", self.update_mode.encode()),
+            ("install_status", self.install_status.encode()),
+        ])
+        .into()
+    }
+}
+
+fn decode_convar(value: Value) -> Result<(String, String), decoder::Error> {
+    use decoder::decode::{map, string};
+
+    let mut convar = map(value)?;
+
+    Ok((
+        convar.required("name", string)?,
+        convar.required("value", string)?,
+    ))
+}
+
+fn encode_convar((name, value): &(String, String)) -> Value {
+    use decoder::encode::{map, string};
+
+    map([("name", string(name)), ("value", string(value))]).into()
+}
+
+// Workshop IDs are stored as strings, like the Steam Web API returns them.
+fn decode_workshop(value: Value) -> Result<WorkshopItems, decoder::Error> {
+    use decoder::decode::{map, sequence, string};
+
+    let mut workshop = map(value)?;
+
+    let id = |value| -> Result<u64, decoder::Error> {
+        let id = string(value)?;
+
+        id.parse()
+            .map_err(|_| decoder::Error::Custom(format!("invalid Workshop ID: {id}")))
+    };
+
+    Ok(WorkshopItems {
+        collection: workshop.optional("collection", id)?,
+        maps: workshop.optional("maps", sequence(id))?.unwrap_or_default(),
+    })
+}
+
+fn encode_workshop(workshop: &WorkshopItems) -> Value {
+    use decoder::encode::{map, optional, sequence, string};
+
+    let id = |id: &u64| string(&id.to_string());
+
+    map([
+        (
+            "collection",
+            optional(string, workshop.collection.map(|id| id.to_string())),
+        ),
+        ("maps", sequence(id, &workshop.maps)),
+    ])
+    .into()
+}
+
+// `stable`, `dev` or a pinned version, e.g. `1.11.0.6968`.
+fn decode_release<T: FromStr<Err = String>>(value: Value) -> Result<T, decoder::Error> {
+    use decoder::decode::string;
+
+    string(value)?.parse().map_err(decoder::Error::Custom)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestartPolicy {
+    pub mode: RestartMode,
+    pub max_retries: u32,
+    /// The delay before the first restart, doubled on every following attempt.
+    pub backoff: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RestartMode {
+    #[default]
+    Never,
+    OnCrash,
+    Always,
+}
+
+impl RestartPolicy {
+    /// How long a server has to stay up for its restarts to be counted from zero again, so one
+    /// that crashes every now and then keeps being restarted.
+    pub const STABLE_UPTIME: Duration = Duration::from_secs(10 * 60);
+
+    /// How long to wait before restarting a server that exited with `exit_code`,
+    /// or `None` if it should stay stopped.
+    pub fn restart_delay(&self, exit_code: Option<i32>, attempt: u32) -> Option<Duration> {
+        // No exit code means the process was killed by a signal.
+        let crashed = exit_code != Some(0);
+
+        let should_restart = match self.mode {
+            RestartMode::Never => false,
+            RestartMode::OnCrash => crashed,
+            RestartMode::Always => true,
+        };
+
+        (should_restart && attempt < self.max_retries)
+            .then(|| self.backoff.saturating_mul(2u32.saturating_pow(attempt)))
+    }
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, u32};
+
+        let mut policy = map(value)?;
+        let default = Self::default();
+
+        Ok(Self {
+            mode: policy
+                .optional("mode", RestartMode::decode)?
+                .unwrap_or(default.mode),
+            max_retries: policy
+                .optional("max_retries", u32)?
+                .unwrap_or(default.max_retries),
+            backoff: policy
+                .optional("backoff_seconds", u32)?
+                .map_or(default.backoff, |seconds| {
+                    Duration::from_secs(seconds.into())
+                }),
+        })
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::{map, u32};
+
+        map([
+            ("mode", self.mode.encode()),
+            ("max_retries", u32(self.max_retries)),
+            (
+                "backoff_seconds",
+                u32(self.backoff.as_secs().try_into().unwrap_or(u32::MAX)),
+            ),
         ])
         .into()
     }
 }
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            mode: RestartMode::Never,
+            max_retries: 3,
+            backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RestartMode {
+    pub const ALL: [RestartMode; 3] = [
+        RestartMode::Never,
+        RestartMode::OnCrash,
+        RestartMode::Always,
+    ];
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::string;
+
+        string(value)?.parse().map_err(decoder::Error::Custom)
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::string;
+
+        string(match self {
+            RestartMode::Never => "never",
+            RestartMode::OnCrash => "on_crash",
+            RestartMode::Always => "always",
+        })
+    }
+}
+
+impl FromStr for RestartMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "never" => Ok(RestartMode::Never),
+            "on_crash" | "on-crash" => Ok(RestartMode::OnCrash),
+            "always" => Ok(RestartMode::Always),
+            mode => Err(format!("'{mode}' is not a valid restart mode")),
+        }
+    }
+}
+
+impl std::fmt::Display for RestartMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RestartMode::Never => "Never",
+            RestartMode::OnCrash => "On crash",
+            RestartMode::Always => "Always",
+        })
+    }
+}
+
+/// What happens when a newer build of the server is out.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UpdateMode {
+    /// The server is flagged as outdated on its card.
+    #[default]
+    Manual,
+    /// The server is updated right away, unless it's running.
+    Automatic,
+}
+
+impl UpdateMode {
+    pub const ALL: [UpdateMode; 2] = [UpdateMode::Manual, UpdateMode::Automatic];
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::string;
+
+        string(value)?.parse().map_err(decoder::Error::Custom)
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::string;
+
+        string(match self {
+            UpdateMode::Manual => "manual",
+            UpdateMode::Automatic => "automatic",
+        })
+    }
+}
+
+impl FromStr for UpdateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "manual" => Ok(UpdateMode::Manual),
+            "automatic" => Ok(UpdateMode::Automatic),
+            mode => Err(format!("'{mode}' is not a valid update mode")),
+        }
+    }
+}
+
+impl std::fmt::Display for UpdateMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UpdateMode::Manual => "Manual",
+            UpdateMode::Automatic => "Automatic",
+        })
+    }
+}
+
+/// How far the download of the server's files got.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InstallStatus {
+    #[default]
+    Installed,
+    /// The download was cancelled or cut short, and can be resumed.
+    Incomplete,
+}
+
+impl InstallStatus {
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::string;
+
+        string(value)?.parse().map_err(decoder::Error::Custom)
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::string;
+
+        string(match self {
+            InstallStatus::Installed => "installed",
+            InstallStatus::Incomplete => "incomplete",
+        })
+    }
+}
+
+impl FromStr for InstallStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "installed" => Ok(InstallStatus::Installed),
+            "incomplete" => Ok(InstallStatus::Incomplete),
+            status => Err(format!("'{status}' is not a valid install status")),
+        }
+    }
+}
+
+impl std::fmt::Display for InstallStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            InstallStatus::Installed => "Installed",
+            InstallStatus::Incomplete => "Incomplete",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopPolicy {
+    /// How long players are warned before the server quits. Zero skips the warning.
+    pub warning: Duration,
+    /// How long the server gets to quit on its own before it's killed.
+    pub timeout: Duration,
+}
+
+impl StopPolicy {
+    /// The console commands that warn the players and then stop the server, each to be sent
+    /// after the delay it comes with.
+    pub fn commands(&self) -> Vec<(Duration, String)> {
+        let total = self.warning.as_secs();
+
+        let mut marks: Vec<u64> = std::iter::once(total)
+            .chain([60, 30, 10, 5, 4, 3, 2, 1])
+            .filter(|mark| *mark > 0 && *mark <= total)
+            .collect();
+
+        marks.dedup();
+
+        let mut commands = vec![];
+        let mut left = total;
+
+        for mark in marks {
+            commands.push((
+                Duration::from_secs(left - mark),
+                format!("say The server is shutting down in {mark} seconds"),
+            ));
+
+            left = mark;
+        }
+
+        commands.push((Duration::from_secs(left), "quit".to_string()));
+
+        commands
+    }
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, u32};
+
+        let mut policy = map(value)?;
+        let default = Self::default();
+
+        let seconds = |seconds: u32| Duration::from_secs(seconds.into());
+
+        Ok(Self {
+            warning: policy
+                .optional("warning_seconds", u32)?
+                .map_or(default.warning, seconds),
+            timeout: policy
+                .optional("timeout_seconds", u32)?
+                .map_or(default.timeout, seconds),
+        })
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::{map, u32};
+
+        let seconds = |duration: Duration| u32(duration.as_secs().try_into().unwrap_or(u32::MAX));
+
+        map([
+            ("warning_seconds", seconds(self.warning)),
+            ("timeout_seconds", seconds(self.timeout)),
+        ])
+        .into()
+    }
+}
+
+impl Default for StopPolicy {
+    fn default() -> Self {
+        Self {
+            warning: Duration::ZERO,
+            timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Processes spawned by the app, persisted beside the server list so they can be found
+/// again if the app is closed while they are running.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeState {
+    pub processes: Vec<RunningProcess>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningProcess {
+    pub path: PathBuf,
+    pub pid: u32,
+    /// When the process started, as told by [`process::start_time`].
+    pub started: u64,
+    pub port: u16,
+    pub hosting_mode: HostingMode,
+}
+
+impl RuntimeState {
+    /// Reads the runtime state file. A missing or broken file is treated as empty.
+    pub async fn load(path: &Path) -> Self {
+        let Ok(file_contents) = tokio::fs::read_to_string(path).await else {
+            return Self::default();
+        };
+
+        decoder::run(toml::from_str, RuntimeState::decode, &file_contents).unwrap_or_default()
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), Error> {
+        let toml = toml::to_string_pretty(&self.encode()).map_err(|_| Error::ServerSaveError)?;
+
+        tokio::fs::write(path, toml)
+            .await
+            .map_err(|_| Error::ServerSaveError)
+    }
+
+    pub fn get(&self, server_path: &Path) -> Option<&RunningProcess> {
+        self.processes
+            .iter()
+            .find(|running| running.path == server_path)
+    }
+
+    pub fn insert(&mut self, running: RunningProcess) {
+        self.remove(&running.path.clone());
+
+        self.processes.push(running);
+    }
+
+    pub fn remove(&mut self, server_path: &Path) {
+        self.processes.retain(|running| running.path != server_path);
+    }
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, sequence};
+
+        Ok(Self {
+            processes: map(value)?
+                .optional("processes", sequence(RunningProcess::decode))?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::{map, sequence};
+
+        map([(
+            "processes",
+            sequence(RunningProcess::encode, &self.processes),
+        )])
+        .into_value()
+    }
+}
+
+impl RunningProcess {
+    /// Whether the process is still the one the server was started as.
+    pub fn is_alive(&self) -> bool {
+        process::is_same(self.pid, self.started)
+    }
+
+    /// Kills the process, unless it's gone and its PID was given to another one.
+    pub fn kill(&self) -> Result<(), core::Error> {
+        if !self.is_alive() {
+            return Ok(());
+        }
+
+        process::kill(self.pid)
+    }
+
+    /// Removes the UPnP mapping opened for the server, the attached console closes its own.
+    pub fn close_port_forward(&self) {
+        if self.hosting_mode == HostingMode::Upnp {
+            let _ =
+                PortForwarder::release(PortForwarderIP::Any, self.port, PortMappingProtocol::UDP);
+        }
+    }
+
+    /// Asks the server to quit through RCON, and kills it if that isn't possible or
+    /// it doesn't quit within the timeout.
+    pub async fn stop(
+        self,
+        policy: StopPolicy,
+        rcon_password: Option<String>,
+    ) -> Result<(), core::Error> {
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, self.port));
+
+        let mut has_exited = false;
+
+        if let Some(password) = rcon_password
+            && let Ok(mut client) = RconClient::connect(address, &password).await
+        {
+            for (delay, command) in policy.commands() {
+                tokio::time::sleep(delay).await;
+
+                let _ = client.exec(&command).await;
+            }
+
+            has_exited = process::wait_for_exit(self.pid, self.started, policy.timeout).await;
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let result = if has_exited { Ok(()) } else { self.kill() };
+
+            self.close_port_forward();
+
+            result
+        })
+        .await
+        .unwrap()
+    }
+
+    pub fn decode(value: Value) -> Result<Self, decoder::Error> {
+        use decoder::decode::{map, string, u16, u32};
+
+        let mut running = map(value)?;
+
+        Ok(Self {
+            path: PathBuf::from(running.required("path", string)?),
+            pid: running.required("pid", u32)?,
+            started: running
+                .required("started", string)?
+                .parse()
+                .map_err(|_| decoder::Error::Custom("invalid process start time".to_string()))?,
+            port: running.required("port", u16)?,
+            hosting_mode: running.required("hosting_mode", HostingMode::decode)?,
+        })
+    }
+
+    pub fn encode(&self) -> Value {
+        use decoder::encode::{map, string, u16, u32};
+
+        map([
+            ("path", string(self.path.to_str().unwrap_or_default())),
+            ("pid", u32(self.pid)),
+            ("started", string(&self.started.to_string())),
+            ("port", u16(self.port)),
+            ("hosting_mode", self.hosting_mode.encode()),
+        ])
+        .into()
+    }
+}
+
+/// The runtime state file lives beside the server list file.
+pub fn runtime_state_path(config_path: &Path) -> PathBuf {
+    config_path.with_file_name(RUNTIME_STATE_FILE_NAME)
+}
+
+const RUNTIME_STATE_FILE_NAME: &str = "runtime_state.toml";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_directories_follow_the_engine() {
+        let info = |game| ServerInfo {
+            game,
+            path: PathBuf::from("server"),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            info(Game::TeamFortress2).game_directory(),
+            Path::new("server/tf")
+        );
+        assert_eq!(
+            info(Game::CounterStrike2).game_directory(),
+            Path::new("server/game/csgo")
+        );
+        assert_eq!(
+            info(Game::Deadlock).game_directory(),
+            Path::new("server/game/citadel")
+        );
+    }
+}
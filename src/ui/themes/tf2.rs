@@ -22,6 +22,7 @@ pub mod svg;
 pub mod sweeten_column;
 pub mod table;
 pub mod text;
+pub mod text_editor;
 pub mod text_input;
 pub mod toggler;
 